@@ -0,0 +1,32 @@
+use path_tracer::cli::{self, Command, HELP};
+use path_tracer::commands;
+use path_tracer::net::run_worker;
+
+fn exit_with(message: impl std::fmt::Display) -> ! {
+    eprintln!("{message}");
+    std::process::exit(1);
+}
+
+fn main() {
+    let result = match cli::parse(std::env::args().skip(1)) {
+        #[cfg(feature = "sdl")]
+        Ok(Command::View(options)) => commands::view(&options),
+        #[cfg(not(feature = "sdl"))]
+        Ok(Command::View(_)) => {
+            Err("Built without the window, try `path_tracer render`".to_string())
+        }
+        Ok(Command::Render(options)) => commands::render(&options),
+        Ok(Command::Bench(options)) => commands::bench(&options),
+        Ok(Command::Merge { output, inputs }) => commands::merge(&output, &inputs),
+        // Workers get everything else from the coordinator and never open a window
+        Ok(Command::Worker { address }) => run_worker(&address),
+        Ok(Command::Help) => {
+            print!("{HELP}");
+            Ok(())
+        }
+        Err(e) => Err(e),
+    };
+    if let Err(e) = result {
+        exit_with(e);
+    }
+}
@@ -0,0 +1,22 @@
+use path_tracer::cli::{self, Command, HELP};
+use path_tracer::commands;
+
+/// Just the window, taking the same options as `path_tracer view`
+fn main() {
+    let args = std::iter::once("view".to_string()).chain(std::env::args().skip(1));
+    let result = match cli::parse(args) {
+        Ok(Command::View(options)) => commands::view(&options),
+        Ok(Command::Help) => {
+            print!("{HELP}");
+            Ok(())
+        }
+        Ok(_) => {
+            Err("The viewer only shows renders, use path_tracer for anything else".to_string())
+        }
+        Err(e) => Err(e),
+    };
+    if let Err(e) = result {
+        eprintln!("{e}");
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,380 @@
+use std::path::Path;
+
+use crate::integrator::IntegratorKind;
+
+pub const HELP: &str = "\
+Usage: path_tracer [command] [options]
+
+Commands:
+  view                 Render progressively in a window (the default)
+  render               Render an image to a file without a window
+  bench                Time how fast each integrator renders
+  merge <output> <checkpoint>...
+                       Sum checkpoints from separate processes into an image or checkpoint
+  worker <address:port>
+                       Render tiles handed out by a coordinator
+  help                 Show this message
+
+Options:
+  --width <pixels>             Image width [default: 400]
+  --height <pixels>            Image height [default: width at 16:9]
+  --samples <n>                Samples per pixel [default: 1 for view, 16 for render, 4 for bench]
+  --max-depth <n>              Longest path the integrator may trace
+  --seed <n>                   Seed for the random numbers, for repeatable renders
+  --threads <n>                Threads to render with (render, bench) [default: all cores]
+  --scene <file>               Scene file to render instead of the built in scene
+  --integrator <name>          normals, ao, path, whitted, bdpt, pm, sppm or spectral
+                               [default: normals, bench tries them all]
+  --output <file>              Where to write the image (render) [default: render.ppm]
+  --format <ppm|pfm>           Image format, otherwise taken from the output's extension
+  --checkpoint <file>          Save the render periodically and on exit (view), or once
+                               finished (render), for resuming or merging
+  --checkpoint-interval <secs> How often to save the checkpoint [default: 60]
+  --resume <file>              Carry on from a checkpoint (view)
+  --coordinator <address:port> Share the render out to workers (view)
+  --font <file>                TrueType font for the overlay (view) [default: built in]
+  --snapshot-at <spp,...>      Save the image when it reaches these samples per pixel (view)
+";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    Ppm,
+    Pfm,
+}
+
+impl ImageFormat {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "ppm" => Some(ImageFormat::Ppm),
+            "pfm" => Some(ImageFormat::Pfm),
+            _ => None,
+        }
+    }
+}
+
+/// Settings shared by the commands which render
+#[derive(Debug, Clone, PartialEq)]
+pub struct Options {
+    pub width: u32,
+    pub height: u32,
+    pub samples: u32,
+    pub max_depth: Option<u32>,
+    pub seed: Option<u64>,
+    pub threads: usize,
+    pub scene: Option<String>,
+    /// None means every integrator, only for bench
+    pub integrator: Option<IntegratorKind>,
+    pub output: String,
+    pub format: ImageFormat,
+    pub checkpoint: Option<String>,
+    pub checkpoint_interval: f64,
+    pub resume: Option<String>,
+    pub coordinator: Option<String>,
+    pub font: Option<String>,
+    /// Samples per pixel at which the viewer saves the image
+    pub snapshot_at: Vec<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    View(Options),
+    Render(Options),
+    Bench(Options),
+    Merge { output: String, inputs: Vec<String> },
+    Worker { address: String },
+    Help,
+}
+
+fn value(flag: &str, value: Option<String>) -> Result<String, String> {
+    value.ok_or_else(|| format!("{flag} needs a value"))
+}
+
+fn number<T: std::str::FromStr>(flag: &str, value: Option<String>) -> Result<T, String> {
+    let text = self::value(flag, value)?;
+    text.parse()
+        .map_err(|_| format!("{flag} expects a number, not '{text}'"))
+}
+
+/// Works out what to do from the arguments, without the program name
+pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Command, String> {
+    let mut args = args.into_iter().peekable();
+    let command = match args.peek().map(|arg| arg.as_str()) {
+        None => "view".to_string(),
+        Some(arg) if arg.starts_with('-') => "view".to_string(),
+        Some(_) => args.next().unwrap(),
+    };
+
+    match command.as_str() {
+        "view" | "render" | "bench" => {}
+        "merge" => {
+            let args: Vec<String> = args.collect();
+            return match args.split_first() {
+                Some((output, inputs)) if !inputs.is_empty() => Ok(Command::Merge {
+                    output: output.clone(),
+                    inputs: inputs.to_vec(),
+                }),
+                _ => Err("merge needs an output and at least one checkpoint".to_string()),
+            };
+        }
+        "worker" => {
+            let address = value("worker", args.next())?;
+            return match args.next() {
+                None => Ok(Command::Worker { address }),
+                Some(arg) => Err(format!("Unexpected argument '{arg}'")),
+            };
+        }
+        "help" | "-h" | "--help" => return Ok(Command::Help),
+        _ => {
+            return Err(format!(
+                "Unknown command '{command}', see `path_tracer help`"
+            ))
+        }
+    }
+
+    let mut width = 400;
+    let mut height = None;
+    let mut samples = None;
+    let mut integrator = None;
+    let mut format = None;
+    let mut options = Options {
+        width,
+        height: 0,
+        samples: 0,
+        max_depth: None,
+        seed: None,
+        threads: std::thread::available_parallelism().map_or(1, |n| n.get()),
+        scene: None,
+        integrator: None,
+        output: "render.ppm".to_string(),
+        format: ImageFormat::Ppm,
+        checkpoint: None,
+        checkpoint_interval: 60.0,
+        resume: None,
+        coordinator: None,
+        font: None,
+        snapshot_at: Vec::new(),
+    };
+
+    while let Some(flag) = args.next() {
+        // Which commands each flag makes sense for
+        let allowed: &[&str] = match flag.as_str() {
+            "--width" => {
+                width = number(&flag, args.next())?;
+                &["view", "render", "bench"]
+            }
+            "--height" => {
+                height = Some(number(&flag, args.next())?);
+                &["view", "render", "bench"]
+            }
+            "--samples" => {
+                samples = Some(number(&flag, args.next())?);
+                &["view", "render", "bench"]
+            }
+            "--max-depth" => {
+                options.max_depth = Some(number(&flag, args.next())?);
+                &["view", "render", "bench"]
+            }
+            "--seed" => {
+                options.seed = Some(number(&flag, args.next())?);
+                &["view", "render", "bench"]
+            }
+            "--threads" => {
+                options.threads = number(&flag, args.next())?;
+                &["render", "bench"]
+            }
+            "--scene" => {
+                options.scene = Some(value(&flag, args.next())?);
+                &["view", "render", "bench"]
+            }
+            "--integrator" => {
+                integrator = Some(value(&flag, args.next())?.parse()?);
+                &["view", "render", "bench"]
+            }
+            "--output" => {
+                options.output = value(&flag, args.next())?;
+                &["render"]
+            }
+            "--format" => {
+                let name = value(&flag, args.next())?;
+                format = Some(
+                    ImageFormat::parse(&name)
+                        .ok_or_else(|| format!("Unknown format '{name}', expected ppm or pfm"))?,
+                );
+                &["render"]
+            }
+            "--checkpoint" => {
+                options.checkpoint = Some(value(&flag, args.next())?);
+                &["view", "render"]
+            }
+            "--checkpoint-interval" => {
+                options.checkpoint_interval = number(&flag, args.next())?;
+                &["view"]
+            }
+            "--resume" => {
+                options.resume = Some(value(&flag, args.next())?);
+                &["view"]
+            }
+            "--coordinator" => {
+                options.coordinator = Some(value(&flag, args.next())?);
+                &["view"]
+            }
+            "--font" => {
+                options.font = Some(value(&flag, args.next())?);
+                &["view"]
+            }
+            "--snapshot-at" => {
+                for milestone in value(&flag, args.next())?.split(',') {
+                    options
+                        .snapshot_at
+                        .push(number(&flag, Some(milestone.to_string()))?);
+                }
+                &["view"]
+            }
+            "-h" | "--help" => return Ok(Command::Help),
+            _ => return Err(format!("Unknown option '{flag}', see `path_tracer help`")),
+        };
+        if !allowed.contains(&command.as_str()) {
+            return Err(format!("{flag} doesn't apply to {command}"));
+        }
+    }
+
+    options.width = width;
+    options.height = height.unwrap_or((width as f64 * 9.0 / 16.0) as u32);
+    if options.width < 2 || options.height < 2 {
+        return Err("The image must be at least 2x2 pixels".to_string());
+    }
+    options.samples = samples.unwrap_or(match command.as_str() {
+        "render" => 16,
+        "bench" => 4,
+        _ => 1,
+    });
+    if options.samples == 0 {
+        return Err("--samples must be at least 1".to_string());
+    }
+    if options.threads == 0 {
+        return Err("--threads must be at least 1".to_string());
+    }
+    if options.max_depth == Some(0) {
+        return Err("--max-depth must be at least 1".to_string());
+    }
+    if options.snapshot_at.contains(&0) {
+        return Err("--snapshot-at milestones must be at least 1".to_string());
+    }
+    if let Some(n) = options.snapshot_at.iter().find(|&&n| n > options.samples) {
+        return Err(format!(
+            "--snapshot-at {n} is more than --samples {}, so it would never be reached",
+            options.samples
+        ));
+    }
+    if !options.checkpoint_interval.is_finite() || options.checkpoint_interval <= 0.0 {
+        return Err("--checkpoint-interval must be a positive number of seconds".to_string());
+    }
+
+    let extension = Path::new(&options.output)
+        .extension()
+        .and_then(|e| e.to_str())
+        .and_then(ImageFormat::parse);
+    options.format = match (format, extension) {
+        (Some(format), _) => format,
+        (None, Some(format)) => format,
+        (None, None) if command == "render" => {
+            return Err(format!(
+                "Can't tell the format of '{}', use --format",
+                options.output
+            ))
+        }
+        (None, None) => ImageFormat::Ppm,
+    };
+
+    options.integrator = match command.as_str() {
+        "bench" => integrator,
+        _ => Some(integrator.unwrap_or(IntegratorKind::Normals)),
+    };
+
+    Ok(match command.as_str() {
+        "render" => Command::Render(options),
+        "bench" => Command::Bench(options),
+        _ => Command::View(options),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cli::{parse, Command, ImageFormat};
+    use crate::integrator::IntegratorKind;
+
+    fn args(text: &str) -> Vec<String> {
+        text.split_whitespace().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_commands() {
+        // No command is the window, as it always was
+        let view = match parse(args(
+            "--integrator bdpt --font mono.ttf --samples 256 --snapshot-at 16,64,256",
+        ))
+        .unwrap()
+        {
+            Command::View(options) => options,
+            _ => panic!("expected view"),
+        };
+        assert_eq!(view.integrator, Some(IntegratorKind::Bidirectional));
+        assert_eq!((view.width, view.height, view.samples), (400, 225, 256));
+        assert_eq!(view.font.as_deref(), Some("mono.ttf"));
+        assert_eq!(view.snapshot_at, vec![16, 64, 256]);
+
+        let render = match parse(args(
+            "render --width 64 --height 32 --samples 8 --max-depth 5 --seed 7 --threads 2 \
+             --output out.pfm --checkpoint r.ckpt",
+        ))
+        .unwrap()
+        {
+            Command::Render(options) => options,
+            _ => panic!("expected render"),
+        };
+        assert_eq!((render.width, render.height, render.samples), (64, 32, 8));
+        assert_eq!(
+            (render.max_depth, render.seed, render.threads),
+            (Some(5), Some(7), 2)
+        );
+        assert_eq!(render.format, ImageFormat::Pfm);
+        assert_eq!(render.checkpoint.as_deref(), Some("r.ckpt"));
+        assert_eq!(render.integrator, Some(IntegratorKind::Normals));
+
+        match parse(args("bench")).unwrap() {
+            Command::Bench(options) => assert_eq!(options.integrator, None),
+            _ => panic!("expected bench"),
+        }
+        assert_eq!(
+            parse(args("merge out.ppm a.ckpt b.ckpt")).unwrap(),
+            Command::Merge {
+                output: "out.ppm".to_string(),
+                inputs: args("a.ckpt b.ckpt"),
+            }
+        );
+        assert_eq!(parse(args("render --help")).unwrap(), Command::Help);
+
+        // Bad values and flags in the wrong place are refused
+        for bad in [
+            "render --samples 0",
+            "render --width ten",
+            "render --output out.png",
+            "bench --checkpoint a.ckpt",
+            "render --font mono.ttf",
+            "render --snapshot-at 16",
+            "view --snapshot-at 16,,64",
+            "view --snapshot-at 0",
+            "view --samples 64 --snapshot-at 16,128",
+            "view --checkpoint-interval nan",
+            "view --checkpoint-interval inf",
+            "view --threads 4",
+            "view --format jpg",
+            "view --integrator magic",
+            "view --samples",
+            "merge out.ppm",
+            "paint",
+        ] {
+            assert!(parse(args(bad)).is_err(), "{bad}");
+        }
+    }
+}
@@ -0,0 +1,479 @@
+use std::io::Write;
+use std::time::Instant;
+
+use crate::cli::{ImageFormat, Options};
+use crate::integrator::{Integrator, IntegratorKind};
+use crate::renderer::{
+    hash_str, render_image, settings_hash, write_image, write_pfm, write_ppm, Checkpoint, Scene,
+    SceneFile,
+};
+
+/// Sums checkpoints of the same scene rendered by separate processes, writing an image or, for
+/// any other extension, another checkpoint
+pub fn merge(output: &str, inputs: &[String]) -> Result<(), String> {
+    let (first, rest) = inputs
+        .split_first()
+        .ok_or("merge needs at least one checkpoint")?;
+    let mut merged = Checkpoint::load(first)?;
+    let mut seeds = vec![(merged.random_state, first)];
+    for path in rest {
+        let checkpoint = Checkpoint::load(path)?;
+        if checkpoint.hash != merged.hash {
+            return Err(format!(
+                "{path} was rendered from a different scene or with different settings to {first}"
+            ));
+        }
+        // The same random numbers give the same samples, which would only be counted twice
+        if let Some((_, other)) = seeds
+            .iter()
+            .find(|(seed, _)| *seed == checkpoint.random_state)
+        {
+            return Err(format!(
+                "{path} has the same samples as {other}, render each with its own --seed"
+            ));
+        }
+        seeds.push((checkpoint.random_state, path));
+        merged
+            .sample_map
+            .merge(&checkpoint.sample_map)
+            .map_err(|e| format!("{path}: {e}"))?;
+    }
+
+    if output.ends_with(".ppm") || output.ends_with(".pfm") {
+        write_image(output, &merged.sample_map)
+    } else {
+        merged.save(output)
+    }
+}
+
+/// The scene to render and the text identifying it, with a camera matching the image's shape
+pub fn load_scene(options: &Options) -> Result<(Scene, String), String> {
+    let (mut scene, source) = match &options.scene {
+        Some(path) => {
+            let file = SceneFile::load(path)?;
+            (file.build()?, file.to_string())
+        }
+        None => (Scene::builtin(), String::new()),
+    };
+    scene.camera = scene
+        .camera
+        .with_aspect(options.width as f64 / options.height as f64);
+    Ok((scene, source))
+}
+
+pub fn create_integrator(kind: IntegratorKind, options: &Options) -> Box<dyn Integrator> {
+    let mut integrator = kind.create();
+    if let Some(depth) = options.max_depth {
+        integrator.set_max_depth(depth);
+    }
+    integrator
+}
+
+/// Renders an image to a file without opening a window
+pub fn render(options: &Options) -> Result<(), String> {
+    let (scene, scene_source) = load_scene(options)?;
+    let kind = options.integrator.unwrap_or(IntegratorKind::Normals);
+    let mut integrator = create_integrator(kind, options);
+
+    let start = Instant::now();
+    let sample_map = render_image(
+        &scene,
+        integrator.as_mut(),
+        (options.width as usize, options.height as usize),
+        options.samples,
+        options.seed.unwrap_or_default(),
+        options.threads,
+        |done, total| {
+            eprint!("\rRendered {done}/{total} tiles");
+            std::io::stderr().flush().ok();
+        },
+    );
+    eprintln!(" in {:.2}s", start.elapsed().as_secs_f64());
+
+    match options.format {
+        ImageFormat::Ppm => write_ppm(&options.output, &sample_map)?,
+        ImageFormat::Pfm => write_pfm(&options.output, &sample_map)?,
+    }
+
+    // The same hash as the viewer's, so these merge with each other and with its checkpoints
+    if let Some(path) = &options.checkpoint {
+        Checkpoint {
+            hash: settings_hash(
+                hash_str(&scene_source),
+                (options.width as usize, options.height as usize),
+                options.samples,
+                kind,
+                options.max_depth,
+            ),
+            // Every sample comes from the seed, so it's what tells renders apart
+            random_state: options.seed.unwrap_or_default(),
+            sample_map,
+        }
+        .save(path)?;
+    }
+    Ok(())
+}
+
+/// Times a render with each integrator, or just the one asked for
+pub fn bench(options: &Options) -> Result<(), String> {
+    let (scene, _) = load_scene(options)?;
+    let kinds = match options.integrator {
+        Some(kind) => vec![kind],
+        None => IntegratorKind::ALL.to_vec(),
+    };
+    let pixels = options.width as f64 * options.height as f64;
+
+    println!(
+        "{}x{} at {} spp on {} threads",
+        options.width, options.height, options.samples, options.threads
+    );
+    for kind in kinds {
+        let start = Instant::now();
+        let mut integrator = create_integrator(kind, options);
+        render_image(
+            &scene,
+            integrator.as_mut(),
+            (options.width as usize, options.height as usize),
+            options.samples,
+            options.seed.unwrap_or_default(),
+            options.threads,
+            |_, _| {},
+        );
+        let seconds = start.elapsed().as_secs_f64();
+        println!(
+            "{:>10} {:>8.2}s {:>12.0} samples/s",
+            kind.name(),
+            seconds,
+            pixels * options.samples as f64 / seconds
+        );
+    }
+    Ok(())
+}
+
+/// Renders progressively in a window until it's closed
+#[cfg(feature = "sdl")]
+pub fn view(options: &Options) -> Result<(), String> {
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use sdl2::event::{Event, WindowEvent};
+    use sdl2::keyboard::Keycode;
+    use sdl2::mouse::MouseButton;
+
+    use crate::net::{Coordinator, Schedule, Setup};
+    use crate::renderer::{hash_str, seed_random, Graphics, Lookdev, Renderer, SceneWatcher};
+
+    if let Some(seed) = options.seed {
+        seed_random(seed);
+    }
+    let integrator = options.integrator.unwrap_or(IntegratorKind::Normals);
+    let (scene, scene_source) = load_scene(options)?;
+
+    let (graphics, canvas) = Graphics::new(options.width, options.height);
+    let mut renderer = Renderer::new(
+        &graphics,
+        canvas,
+        options.height,
+        options.width,
+        options.samples,
+        integrator,
+        scene,
+    );
+    if let Some(depth) = options.max_depth {
+        renderer.set_max_depth(depth);
+    }
+    if let Some(path) = &options.font {
+        renderer.set_font(path)?;
+    }
+    renderer.set_snapshots(options.snapshot_at.clone());
+    renderer.set_scene_hash(hash_str(&scene_source));
+    if let Some(path) = &options.resume {
+        renderer.resume(path)?;
+    }
+    // Resuming keeps saving to the same file unless told otherwise
+    if let Some(path) = options.checkpoint.clone().or(options.resume.clone()) {
+        renderer.set_checkpoint(path, Duration::from_secs_f64(options.checkpoint_interval));
+    }
+
+    // Editing or reloading the scene would leave any workers rendering the old one
+    let (mut lookdev, mut watcher) = match (&options.scene, &options.coordinator) {
+        (Some(path), None) => (
+            Some(Lookdev::new(SceneFile::parse(&scene_source)?)?.0),
+            Some(SceneWatcher::new(path, Instant::now())),
+        ),
+        _ => (None, None),
+    };
+    // Clicks shorter than this are picks rather than drags
+    let mut dragged = 0;
+
+    let coordinator = match &options.coordinator {
+        Some(address) => {
+            let setup = Setup {
+                width: options.width as usize,
+                height: options.height as usize,
+                integrator: integrator.name().to_string(),
+                max_depth: options.max_depth.unwrap_or_default(),
+                scene: scene_source,
+            };
+            // Workers prepare before every job, so progressive ones need a sample per job
+            let schedule = Schedule {
+                samples: options.samples,
+                seed: options.seed.unwrap_or_default(),
+                samples_per_job: if integrator.is_progressive() {
+                    1
+                } else {
+                    Schedule::default().samples_per_job
+                },
+                ..Default::default()
+            };
+            let coordinator = Coordinator::start(address, setup, schedule)?;
+            println!("Waiting for workers on {}", coordinator.address());
+            Some(coordinator)
+        }
+        None => None,
+    };
+
+    let mut event_pump = renderer.get_event_pump();
+
+    loop {
+        for event in event_pump.poll_iter() {
+            match event {
+                Event::Quit { .. }
+                | Event::KeyDown {
+                    keycode: Some(Keycode::Escape),
+                    ..
+                } => {
+                    if let Err(e) = renderer.save_checkpoint() {
+                        eprintln!("Failed to save checkpoint: {e}");
+                    }
+                    return Ok(());
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::Left),
+                    ..
+                } => {
+                    // camera.translate_x(-0.1);
+                    // sample_map.invalidate_samples();
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::Right),
+                    ..
+                } => {
+                    // camera.translate_x(0.1);
+                    // sample_map.invalidate_samples();
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::Up),
+                    ..
+                } => {
+                    // camera.translate_z(-0.1);
+                    // sample_map.invalidate_samples();
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::Down),
+                    ..
+                } => {
+                    // camera.translate_z(0.1);
+                    // sample_map.invalidate_samples();
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::I),
+                    ..
+                } if coordinator.is_none() => {
+                    renderer.set_integrator(renderer.integrator_kind().next());
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::H),
+                    ..
+                } => renderer.toggle_stats(),
+                Event::KeyDown {
+                    keycode: Some(Keycode::P),
+                    ..
+                } => renderer.toggle_probe(),
+                Event::KeyDown {
+                    keycode: Some(Keycode::Num0),
+                    ..
+                } => renderer.reset_view(),
+                Event::KeyDown {
+                    keycode: Some(Keycode::S),
+                    ..
+                } => match renderer.screenshot() {
+                    Ok(stem) => renderer.set_info(vec![format!("Saved {stem}.ppm and .pfm")]),
+                    Err(e) => renderer.set_info(vec![format!("Failed to save screenshot: {e}")]),
+                },
+                Event::MouseWheel { y, .. } => renderer.zoom(1.25_f64.powi(y)),
+                Event::MouseMotion {
+                    x,
+                    y,
+                    xrel,
+                    yrel,
+                    mousestate,
+                    ..
+                } => {
+                    renderer.set_mouse(x, y);
+                    if mousestate.left() {
+                        renderer.pan(xrel, yrel);
+                        dragged += xrel.abs() + yrel.abs();
+                    }
+                }
+                Event::MouseButtonDown {
+                    mouse_btn: MouseButton::Left,
+                    ..
+                } => dragged = 0,
+                Event::MouseButtonUp {
+                    mouse_btn: MouseButton::Left,
+                    x,
+                    y,
+                    ..
+                } if dragged < 4 => {
+                    renderer.set_mouse(x, y);
+                    match (&mut lookdev, renderer.ray_at_mouse()) {
+                        (Some(lookdev), Some(ray)) => {
+                            lookdev.pick(&ray);
+                            show_lookdev(&mut renderer, lookdev);
+                        }
+                        (Some(_), None) => {}
+                        (None, _) if coordinator.is_some() => renderer.set_info(vec![
+                            "Materials can't be edited while sharing the render".to_string(),
+                        ]),
+                        (None, _) => renderer.set_info(vec![
+                            "Materials can only be edited in a --scene".to_string(),
+                        ]),
+                    }
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::Tab),
+                    ..
+                } => {
+                    if let Some(lookdev) = &mut lookdev {
+                        lookdev.next_param();
+                        show_lookdev(&mut renderer, lookdev);
+                    }
+                }
+                Event::KeyDown {
+                    keycode:
+                        Some(
+                            key @ (Keycode::Minus
+                            | Keycode::Equals
+                            | Keycode::KpMinus
+                            | Keycode::KpPlus),
+                        ),
+                    ..
+                } => {
+                    if let Some(lookdev) = &mut lookdev {
+                        let direction = match key {
+                            Keycode::Minus | Keycode::KpMinus => -1.0,
+                            _ => 1.0,
+                        };
+                        match lookdev.adjust(direction) {
+                            Ok(Some(scene)) => {
+                                renderer.set_scene(scene);
+                                renderer.set_scene_hash(hash_str(&lookdev.file.to_string()));
+                            }
+                            Ok(None) => {}
+                            Err(e) => eprintln!("Failed to edit the material: {e}"),
+                        }
+                        show_lookdev(&mut renderer, lookdev);
+                    }
+                }
+                // Workers render at the size they were given, so the image just stretches
+                Event::Window {
+                    win_event: WindowEvent::Resized(width, height),
+                    ..
+                } if coordinator.is_none() => {
+                    renderer.resize(width as u32, height as u32);
+                    if let Some(lookdev) = &lookdev {
+                        show_lookdev(&mut renderer, lookdev);
+                    }
+                }
+                _ => {}
+            }
+        }
+        match &coordinator {
+            Some(coordinator) => {
+                for result in coordinator.poll() {
+                    if let Err(e) = renderer.add_tile(&result) {
+                        eprintln!("Bad result from a worker: {e}");
+                    }
+                }
+                let (done, total) = coordinator.progress();
+                renderer.present(format!(
+                    "{done}/{total} jobs, {} workers",
+                    coordinator.workers()
+                ));
+                thread::sleep(Duration::from_millis(16));
+            }
+            None => {
+                if let Some(watcher) = &mut watcher {
+                    match watcher
+                        .poll(Instant::now())
+                        .map(|file| file.and_then(Lookdev::new))
+                    {
+                        Some(Ok((reloaded, scene))) => {
+                            renderer.set_scene(scene);
+                            renderer.set_scene_hash(hash_str(&reloaded.file.to_string()));
+                            renderer.clear_highlight();
+                            renderer.set_info(vec![format!("Reloaded {}", watcher.path())]);
+                            lookdev = Some(reloaded);
+                        }
+                        // Carry on with what was there before until it's fixed
+                        Some(Err(e)) => renderer.set_info(vec![
+                            "Couldn't reload the scene, showing the last one".to_string(),
+                            e,
+                        ]),
+                        None => {}
+                    }
+                }
+                renderer.render()
+            }
+        }
+    }
+}
+
+/// Outlines the picked object and shows what's being edited
+#[cfg(feature = "sdl")]
+fn show_lookdev(renderer: &mut crate::renderer::Renderer, lookdev: &crate::renderer::Lookdev) {
+    match lookdev.selected() {
+        Some(selected) => renderer.highlight(|ray| lookdev.object_at(ray) == Some(selected)),
+        None => renderer.clear_highlight(),
+    }
+    renderer.set_info(lookdev.lines());
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::commands::merge;
+    use crate::renderer::{Checkpoint, F64x4, SampleMap};
+
+    #[test]
+    fn test_merge_checkpoints() {
+        let dir = std::env::temp_dir();
+        let save = |name: &str, hash: u64, random_state: u64| {
+            let mut sample_map = SampleMap::new(1, 2, 2);
+            sample_map.set_value(0, 0, F64x4::splat(0.5));
+            let path = dir.join(name).to_str().unwrap().to_string();
+            Checkpoint {
+                hash,
+                random_state,
+                sample_map,
+            }
+            .save(&path)
+            .unwrap();
+            path
+        };
+        let a = save("merge_a.ckpt", 1, 10);
+        let b = save("merge_b.ckpt", 1, 20);
+        let other_scene = save("merge_c.ckpt", 2, 30);
+        let repeat = save("merge_d.ckpt", 1, 10);
+        let output = dir.join("merge_out.ckpt").to_str().unwrap().to_string();
+
+        merge(&output, &[a.clone(), b.clone()]).unwrap();
+        let merged = Checkpoint::load(&output).unwrap();
+        assert_eq!(merged.sample_map.get_values(0, 0).1, 2);
+
+        let err = merge(&output, &[a.clone(), other_scene]).unwrap_err();
+        assert!(err.contains("different scene"));
+        let err = merge(&output, &[a, b, repeat]).unwrap_err();
+        assert!(err.contains("same samples"));
+    }
+}
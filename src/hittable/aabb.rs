@@ -0,0 +1,84 @@
+use crate::renderer::{Ray, Vec3};
+
+use Vec3 as Point3;
+
+/// An axis aligned bounding box
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Aabb {
+    pub min: Point3,
+    pub max: Point3,
+}
+
+impl Aabb {
+    pub fn new(min: Point3, max: Point3) -> Self {
+        Self { min, max }
+    }
+
+    /// Slab test, returns the parametric range of the ray that lies inside the box
+    pub fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<(f64, f64)> {
+        let mut t0 = t_min;
+        let mut t1 = t_max;
+        for axis in 0..3 {
+            let inv_d = 1.0 / ray.dir.0[axis];
+            let mut near = (self.min.0[axis] - ray.orig.0[axis]) * inv_d;
+            let mut far = (self.max.0[axis] - ray.orig.0[axis]) * inv_d;
+            if inv_d < 0.0 {
+                std::mem::swap(&mut near, &mut far);
+            }
+            // NaNs from rays lying in a slab plane fall through these comparisons
+            if near > t0 {
+                t0 = near;
+            }
+            if far < t1 {
+                t1 = far;
+            }
+            if t1 <= t0 {
+                return None;
+            }
+        }
+        Some((t0, t1))
+    }
+
+    /// The smallest box containing all the points
+    pub fn from_points(points: &[Point3]) -> Self {
+        let mut aabb = Aabb::new(points[0], points[0]);
+        for point in &points[1..] {
+            aabb = aabb.surrounding(&Aabb::new(*point, *point));
+        }
+        aabb
+    }
+
+    /// Bounds a flat disk
+    pub fn disk(center: Point3, normal: Vec3, radius: f64) -> Self {
+        let normal = normal.unit_vector();
+        let extent = |n: f64| radius * f64::sqrt((1.0 - n * n).max(0.0));
+        let half = Vec3::new(extent(normal.x()), extent(normal.y()), extent(normal.z()));
+        Aabb::new(center - half, center + half)
+    }
+
+    pub fn size(&self) -> Vec3 {
+        self.max - self.min
+    }
+
+    /// Grows the box by `delta` in every direction, flat objects need some thickness
+    pub fn pad(&self, delta: f64) -> Aabb {
+        let delta = Vec3::new(delta, delta, delta);
+        Aabb::new(self.min - delta, self.max + delta)
+    }
+
+    /// The smallest box containing both boxes
+    pub fn surrounding(&self, other: &Aabb) -> Aabb {
+        Aabb {
+            min: Vec3::new(
+                self.min.x().min(other.min.x()),
+                self.min.y().min(other.min.y()),
+                self.min.z().min(other.min.z()),
+            ),
+            max: Vec3::new(
+                self.max.x().max(other.max.x()),
+                self.max.y().max(other.max.y()),
+                self.max.z().max(other.max.z()),
+            ),
+        }
+    }
+}
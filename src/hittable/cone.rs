@@ -0,0 +1,155 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use crate::hittable::{nearest_boundary, Aabb, HitRecord, Hittable, Interval};
+use crate::material::Material;
+use crate::renderer::{Frame, Ray, Vec3};
+
+use Vec3 as Point3;
+
+/// A closed cone with its base centred on `base`, narrowing to a point `height` along `axis`
+pub struct Cone {
+    pub base: Point3,
+    pub radius: f64,
+    pub height: f64,
+    pub material: Option<Arc<dyn Material>>,
+    frame: Frame,
+}
+
+impl Cone {
+    pub fn new(base: Point3, axis: Vec3, radius: f64, height: f64) -> Self {
+        Self {
+            base,
+            radius,
+            height,
+            material: None,
+            frame: Frame::from_normal(axis),
+        }
+    }
+
+    pub fn with_material(
+        base: Point3,
+        axis: Vec3,
+        radius: f64,
+        height: f64,
+        material: Arc<dyn Material>,
+    ) -> Self {
+        Self {
+            material: Some(material),
+            ..Self::new(base, axis, radius, height)
+        }
+    }
+
+    pub fn axis(&self) -> Vec3 {
+        self.frame.n
+    }
+
+    pub fn apex(&self) -> Point3 {
+        self.base + self.height * self.axis()
+    }
+
+    fn record(&self, ray: &Ray, t: f64, local_normal: Vec3, u: f64, v: f64) -> HitRecord {
+        let normal = self.frame.to_world(local_normal).unit_vector();
+        HitRecord {
+            p: ray.at(t),
+            normal,
+            t,
+            u,
+            v,
+            front_face: ray.dir.dot(&normal) < 0.0,
+            material: self.material.clone(),
+        }
+    }
+
+    /// Every crossing of the surface along the ray's line, sorted by t
+    fn boundaries(&self, ray: &Ray) -> Vec<HitRecord> {
+        // Work in the cone's frame, the axis is +z and the base is at the origin
+        let o = self.frame.to_local(ray.orig - self.base);
+        let d = self.frame.to_local(ray.dir);
+        let mut boundaries = Vec::with_capacity(2);
+
+        // The slanted side, x^2 + y^2 = (k(h - z))^2
+        let k2 = (self.radius / self.height).powi(2);
+        let h = self.height - o.z();
+        let a = d.x() * d.x() + d.y() * d.y() - k2 * d.z() * d.z();
+        let half_b = o.x() * d.x() + o.y() * d.y() + k2 * h * d.z();
+        let c = o.x() * o.x() + o.y() * o.y() - k2 * h * h;
+
+        let mut roots = Vec::with_capacity(2);
+        if a.abs() < 1e-12 {
+            // Parallel to the side, only one crossing
+            if half_b.abs() > 1e-12 {
+                roots.push(-c / (2.0 * half_b));
+            }
+        } else {
+            let discriminant = half_b * half_b - a * c;
+            if discriminant >= 0.0 {
+                let sqrtd = discriminant.sqrt();
+                roots.push((-half_b - sqrtd) / a);
+                roots.push((-half_b + sqrtd) / a);
+            }
+        }
+        for t in roots {
+            let p = o + t * d;
+            // The equation also describes a second cone above the apex
+            if (0.0..=self.height).contains(&p.z()) {
+                let normal = Vec3::new(p.x(), p.y(), k2 * (self.height - p.z()));
+                let normal = if normal.length_squared() > 0.0 {
+                    normal
+                } else {
+                    Vec3::new(0.0, 0.0, 1.0)
+                };
+                let u = (f64::atan2(p.y(), p.x()) + PI) / (2.0 * PI);
+                boundaries.push(self.record(ray, t, normal, u, p.z() / self.height));
+            }
+        }
+
+        // The base
+        if d.z().abs() > 1e-12 {
+            let t = -o.z() / d.z();
+            let p = o + t * d;
+            if p.x() * p.x() + p.y() * p.y() <= self.radius * self.radius {
+                let u = (p.x() / self.radius + 1.0) / 2.0;
+                let v = (p.y() / self.radius + 1.0) / 2.0;
+                boundaries.push(self.record(ray, t, Vec3::new(0.0, 0.0, -1.0), u, v));
+            }
+        }
+
+        boundaries.sort_by(|a, b| a.t.total_cmp(&b.t));
+        boundaries
+    }
+}
+
+impl Hittable for Cone {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        nearest_boundary(ray, &self.boundaries(ray), t_min, t_max, rec)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let apex = self.apex();
+        Some(Aabb::disk(self.base, self.axis(), self.radius).surrounding(&Aabb::new(apex, apex)))
+    }
+
+    fn intervals(&self, ray: &Ray) -> Option<Vec<Interval>> {
+        Some(Interval::from_boundaries(self.boundaries(ray)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hittable::{Cone, HitRecord, Hittable};
+    use crate::renderer::{Ray, Vec3};
+
+    #[test]
+    fn test_cone_apex() {
+        let cone = Cone::new(Vec3::new(0., -1., -2.), Vec3::new(0., 1., 0.), 1., 2.);
+        let mut hit_record = HitRecord::default();
+
+        // Passing just above the apex misses, just below it hits close to the axis
+        let ray = Ray::new(Vec3::new(0., 1.5, 0.), Vec3::new(0., 0., -1.));
+        assert!(!cone.hit(&ray, 0., f64::INFINITY, &mut hit_record));
+        let ray = Ray::new(Vec3::new(0., 0.9, 0.), Vec3::new(0., 0., -1.));
+        assert!(cone.hit(&ray, 0., f64::INFINITY, &mut hit_record));
+        assert!((hit_record.t - 1.95).abs() < 1e-9);
+    }
+}
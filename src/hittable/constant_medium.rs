@@ -0,0 +1,150 @@
+use std::sync::Arc;
+
+use crate::hittable::{Aabb, HitRecord, Hittable};
+use crate::material::Material;
+use crate::renderer::{random_double, Ray, Vec3};
+
+/// A volume of constant density inside a closed boundary, such as smoke or fog
+pub struct ConstantMedium {
+    boundary: Box<dyn Hittable>,
+    neg_inv_density: f64,
+    phase_function: Arc<dyn Material>,
+}
+
+impl ConstantMedium {
+    /// Creates a new medium
+    /// # Arguments
+    /// * `boundary` - A closed hittable, the medium fills its inside
+    /// * `density` - The extinction coefficient, higher is thicker
+    /// * `phase_function` - Decides which direction a ray scatters in once it collides inside the medium
+    pub fn new(
+        boundary: Box<dyn Hittable>,
+        density: f64,
+        phase_function: Arc<dyn Material>,
+    ) -> Self {
+        Self {
+            boundary,
+            neg_inv_density: -1.0 / density,
+            phase_function,
+        }
+    }
+
+    /// The part of the ray inside the boundary and within the requested range
+    fn clip(
+        &self,
+        rec_entry: &HitRecord,
+        rec_exit: &HitRecord,
+        t_min: f64,
+        t_max: f64,
+    ) -> Option<(f64, f64)> {
+        let t_entry = rec_entry.t.max(t_min).max(0.0);
+        let t_exit = rec_exit.t.min(t_max);
+        if t_entry >= t_exit {
+            None
+        } else {
+            Some((t_entry, t_exit))
+        }
+    }
+}
+
+impl Hittable for ConstantMedium {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        // Find where the ray enters and leaves the boundary, the ray may start inside it
+        let mut rec_entry = HitRecord::default();
+        let mut rec_exit = HitRecord::default();
+
+        if !self
+            .boundary
+            .hit(ray, f64::NEG_INFINITY, f64::INFINITY, &mut rec_entry)
+        {
+            return false;
+        }
+        if !self
+            .boundary
+            .hit(ray, rec_entry.t + 0.0001, f64::INFINITY, &mut rec_exit)
+        {
+            return false;
+        }
+
+        let Some((t_entry, t_exit)) = self.clip(&rec_entry, &rec_exit, t_min, t_max) else {
+            return false;
+        };
+
+        // Exponential free-flight sampling, most rays pass straight through a thin medium
+        let ray_length = ray.dir.length();
+        let distance_inside = (t_exit - t_entry) * ray_length;
+        let hit_distance = self.neg_inv_density * random_double().ln();
+        if hit_distance > distance_inside {
+            return false;
+        }
+
+        rec.t = t_entry + hit_distance / ray_length;
+        rec.p = ray.at(rec.t);
+        // Normals mean nothing inside a medium, any value will do
+        rec.normal = Vec3::new(1.0, 0.0, 0.0);
+        rec.front_face = true;
+        rec.material = Some(self.phase_function.clone());
+
+        true
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        self.boundary.bounding_box()
+    }
+
+    fn transmittance(&self, ray: &Ray, t_min: f64, t_max: f64) -> f64 {
+        let mut rec_entry = HitRecord::default();
+        let mut rec_exit = HitRecord::default();
+
+        if !self
+            .boundary
+            .hit(ray, f64::NEG_INFINITY, f64::INFINITY, &mut rec_entry)
+            || !self
+                .boundary
+                .hit(ray, rec_entry.t + 0.0001, f64::INFINITY, &mut rec_exit)
+        {
+            return 1.0;
+        }
+
+        match self.clip(&rec_entry, &rec_exit, t_min, t_max) {
+            // Beer-Lambert
+            Some((t_entry, t_exit)) => {
+                f64::exp(self.neg_inv_density.recip() * (t_exit - t_entry) * ray.dir.length())
+            }
+            None => 1.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::hittable::{ConstantMedium, HitRecord, Hittable, Sphere};
+    use crate::material::Isotropic;
+    use crate::renderer::{Ray, Vec3};
+
+    #[test]
+    fn test_constant_medium_density() {
+        let phase = Arc::new(Isotropic::new(Vec3::new(1., 1., 1.)));
+        let thick = ConstantMedium::new(
+            Box::new(Sphere::new(Vec3::new(0., 0., -2.), 1.)),
+            1e6,
+            phase.clone(),
+        );
+        let thin = ConstantMedium::new(
+            Box::new(Sphere::new(Vec3::new(0., 0., -2.), 1.)),
+            1e-9,
+            phase,
+        );
+        let ray = Ray::new(Vec3::new(0., 0., 0.), Vec3::new(0., 0., -1.));
+        let mut hit_record = HitRecord {
+            ..Default::default()
+        };
+
+        // A very dense medium scatters right at the boundary, a very thin one lets the ray through
+        assert!(thick.hit(&ray, 0., f64::INFINITY, &mut hit_record));
+        assert!((hit_record.t - 1.).abs() < 1e-3);
+        assert!(!thin.hit(&ray, 0., f64::INFINITY, &mut hit_record));
+    }
+}
@@ -0,0 +1,147 @@
+use crate::hittable::{nearest_boundary, Aabb, HitRecord, Hittable, Interval};
+use crate::renderer::Ray;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum CsgOp {
+    Union,
+    Intersection,
+    /// The left object with the right one cut out of it
+    Difference,
+}
+
+impl CsgOp {
+    fn inside(&self, in_left: bool, in_right: bool) -> bool {
+        match self {
+            CsgOp::Union => in_left || in_right,
+            CsgOp::Intersection => in_left && in_right,
+            CsgOp::Difference => in_left && !in_right,
+        }
+    }
+}
+
+/// A boolean combination of two closed hittables, both must report their intervals
+pub struct Csg {
+    pub op: CsgOp,
+    left: Box<dyn Hittable>,
+    right: Box<dyn Hittable>,
+}
+
+impl Csg {
+    pub fn new(op: CsgOp, left: Box<dyn Hittable>, right: Box<dyn Hittable>) -> Self {
+        Self { op, left, right }
+    }
+
+    pub fn union(left: Box<dyn Hittable>, right: Box<dyn Hittable>) -> Self {
+        Self::new(CsgOp::Union, left, right)
+    }
+
+    pub fn intersection(left: Box<dyn Hittable>, right: Box<dyn Hittable>) -> Self {
+        Self::new(CsgOp::Intersection, left, right)
+    }
+
+    pub fn difference(left: Box<dyn Hittable>, right: Box<dyn Hittable>) -> Self {
+        Self::new(CsgOp::Difference, left, right)
+    }
+}
+
+/// Combines two sets of intervals by sweeping over their boundaries in order
+pub fn combine_intervals(op: CsgOp, left: Vec<Interval>, right: Vec<Interval>) -> Vec<Interval> {
+    // (t, from the left, entering, boundary)
+    let mut events = Vec::with_capacity(2 * (left.len() + right.len()));
+    for (intervals, is_left) in [(left, true), (right, false)] {
+        for interval in intervals {
+            events.push((interval.enter.t, is_left, true, interval.enter));
+            events.push((interval.exit.t, is_left, false, interval.exit));
+        }
+    }
+    events.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let mut result = Vec::new();
+    let mut depth_left = 0;
+    let mut depth_right = 0;
+    let mut enter: Option<HitRecord> = None;
+    for (_, is_left, entering, mut boundary) in events {
+        let was_inside = op.inside(depth_left > 0, depth_right > 0);
+        let depth = if is_left {
+            &mut depth_left
+        } else {
+            &mut depth_right
+        };
+        if entering {
+            *depth += 1;
+        } else {
+            *depth -= 1;
+        }
+        let is_inside = op.inside(depth_left > 0, depth_right > 0);
+
+        // The cut out object's surface faces into the result
+        if op == CsgOp::Difference && !is_left {
+            boundary.normal = -boundary.normal;
+        }
+
+        if !was_inside && is_inside {
+            enter = Some(boundary);
+        } else if was_inside && !is_inside {
+            if let Some(enter) = enter.take() {
+                result.push(Interval {
+                    enter,
+                    exit: boundary,
+                });
+            }
+        }
+    }
+    result
+}
+
+impl Hittable for Csg {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        let Some(intervals) = self.intervals(ray) else {
+            return false;
+        };
+
+        // The first boundary inside the range is the visible surface
+        let boundaries = intervals
+            .iter()
+            .flat_map(|interval| [&interval.enter, &interval.exit]);
+        nearest_boundary(ray, boundaries, t_min, t_max, rec)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        match self.op {
+            CsgOp::Union => Some(
+                self.left
+                    .bounding_box()?
+                    .surrounding(&self.right.bounding_box()?),
+            ),
+            // Both only ever remove parts of the left object
+            CsgOp::Intersection | CsgOp::Difference => self.left.bounding_box(),
+        }
+    }
+
+    fn intervals(&self, ray: &Ray) -> Option<Vec<Interval>> {
+        let left = self.left.intervals(ray)?;
+        let right = self.right.intervals(ray)?;
+        Some(combine_intervals(self.op, left, right))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hittable::{Csg, HitRecord, Hittable, Sphere};
+    use crate::renderer::{Ray, Vec3};
+
+    #[test]
+    fn test_csg_difference() {
+        // A hollow sphere, seen from inside the hole
+        let shell = Csg::difference(
+            Box::new(Sphere::new(Vec3::new(0., 0., 0.), 1.)),
+            Box::new(Sphere::new(Vec3::new(0., 0., 0.), 0.5)),
+        );
+        let ray = Ray::new(Vec3::new(0., 0., 0.), Vec3::new(0., 0., -1.));
+        let mut hit_record = HitRecord::default();
+        assert!(shell.hit(&ray, 0., f64::INFINITY, &mut hit_record));
+        assert!((hit_record.t - 0.5).abs() < 1e-9);
+        assert!(hit_record.front_face);
+        assert_eq!(hit_record.normal, Vec3::new(0., 0., 1.));
+    }
+}
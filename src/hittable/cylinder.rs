@@ -0,0 +1,146 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use crate::hittable::{nearest_boundary, Aabb, HitRecord, Hittable, Interval};
+use crate::material::Material;
+use crate::renderer::{Frame, Ray, Vec3};
+
+use Vec3 as Point3;
+
+/// A closed cylinder running `height` along `axis` from the centre of its base
+pub struct Cylinder {
+    pub base: Point3,
+    pub radius: f64,
+    pub height: f64,
+    pub material: Option<Arc<dyn Material>>,
+    frame: Frame,
+}
+
+impl Cylinder {
+    pub fn new(base: Point3, axis: Vec3, radius: f64, height: f64) -> Self {
+        Self {
+            base,
+            radius,
+            height,
+            material: None,
+            frame: Frame::from_normal(axis),
+        }
+    }
+
+    pub fn with_material(
+        base: Point3,
+        axis: Vec3,
+        radius: f64,
+        height: f64,
+        material: Arc<dyn Material>,
+    ) -> Self {
+        Self {
+            material: Some(material),
+            ..Self::new(base, axis, radius, height)
+        }
+    }
+
+    pub fn axis(&self) -> Vec3 {
+        self.frame.n
+    }
+
+    fn record(&self, ray: &Ray, t: f64, local_normal: Vec3, u: f64, v: f64) -> HitRecord {
+        let normal = self.frame.to_world(local_normal);
+        HitRecord {
+            p: ray.at(t),
+            normal,
+            t,
+            u,
+            v,
+            front_face: ray.dir.dot(&normal) < 0.0,
+            material: self.material.clone(),
+        }
+    }
+
+    /// Every crossing of the surface along the ray's line, sorted by t
+    fn boundaries(&self, ray: &Ray) -> Vec<HitRecord> {
+        // Work in the cylinder's frame, the axis is +z and the base is at the origin
+        let o = self.frame.to_local(ray.orig - self.base);
+        let d = self.frame.to_local(ray.dir);
+        let mut boundaries = Vec::with_capacity(2);
+
+        // The curved side
+        let a = d.x() * d.x() + d.y() * d.y();
+        if a > 1e-12 {
+            let half_b = o.x() * d.x() + o.y() * d.y();
+            let c = o.x() * o.x() + o.y() * o.y() - self.radius * self.radius;
+            let discriminant = half_b * half_b - a * c;
+            if discriminant >= 0.0 {
+                let sqrtd = discriminant.sqrt();
+                for t in [(-half_b - sqrtd) / a, (-half_b + sqrtd) / a] {
+                    let p = o + t * d;
+                    if (0.0..=self.height).contains(&p.z()) {
+                        let normal = Vec3::new(p.x(), p.y(), 0.0) / self.radius;
+                        let u = (f64::atan2(p.y(), p.x()) + PI) / (2.0 * PI);
+                        boundaries.push(self.record(ray, t, normal, u, p.z() / self.height));
+                    }
+                }
+            }
+        }
+
+        // The end caps
+        if d.z().abs() > 1e-12 {
+            for (z, normal_z) in [(0.0, -1.0), (self.height, 1.0)] {
+                let t = (z - o.z()) / d.z();
+                let p = o + t * d;
+                if p.x() * p.x() + p.y() * p.y() <= self.radius * self.radius {
+                    let u = (p.x() / self.radius + 1.0) / 2.0;
+                    let v = (p.y() / self.radius + 1.0) / 2.0;
+                    boundaries.push(self.record(ray, t, Vec3::new(0.0, 0.0, normal_z), u, v));
+                }
+            }
+        }
+
+        boundaries.sort_by(|a, b| a.t.total_cmp(&b.t));
+        boundaries
+    }
+}
+
+impl Hittable for Cylinder {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        nearest_boundary(ray, &self.boundaries(ray), t_min, t_max, rec)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let top = self.base + self.height * self.axis();
+        Some(
+            Aabb::disk(self.base, self.axis(), self.radius).surrounding(&Aabb::disk(
+                top,
+                self.axis(),
+                self.radius,
+            )),
+        )
+    }
+
+    fn intervals(&self, ray: &Ray) -> Option<Vec<Interval>> {
+        Some(Interval::from_boundaries(self.boundaries(ray)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hittable::{Cylinder, HitRecord, Hittable};
+    use crate::renderer::{Ray, Vec3};
+
+    #[test]
+    fn test_cylinder_caps() {
+        let cylinder = Cylinder::new(Vec3::new(0., -1., -2.), Vec3::new(0., 1., 0.), 0.5, 2.);
+        let mut hit_record = HitRecord::default();
+
+        // Halfway up the side
+        let ray = Ray::new(Vec3::new(0., 0., 0.), Vec3::new(0., 0., -1.));
+        assert!(cylinder.hit(&ray, 0., f64::INFINITY, &mut hit_record));
+        assert!((hit_record.v - 0.5).abs() < 1e-9);
+
+        // Straight down onto the top cap
+        let ray = Ray::new(Vec3::new(0.1, 3., -2.), Vec3::new(0., -1., 0.));
+        assert!(cylinder.hit(&ray, 0., f64::INFINITY, &mut hit_record));
+        assert!((hit_record.t - 2.).abs() < 1e-9);
+        assert!((hit_record.normal - Vec3::new(0., 1., 0.)).length() < 1e-9);
+    }
+}
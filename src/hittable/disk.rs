@@ -0,0 +1,108 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use crate::hittable::{Aabb, HitRecord, Hittable, Sampleable};
+use crate::material::Material;
+use crate::renderer::{random_double, Frame, Ray, Vec3};
+
+use Vec3 as Point3;
+
+/// A flat, one sided, circular disk
+pub struct Disk {
+    pub center: Point3,
+    pub radius: f64,
+    pub material: Option<Arc<dyn Material>>,
+    frame: Frame,
+}
+
+impl Disk {
+    pub fn new(center: Point3, normal: Vec3, radius: f64) -> Self {
+        Self {
+            center,
+            radius,
+            material: None,
+            frame: Frame::from_normal(normal),
+        }
+    }
+
+    pub fn with_material(
+        center: Point3,
+        normal: Vec3,
+        radius: f64,
+        material: Arc<dyn Material>,
+    ) -> Self {
+        Self {
+            material: Some(material),
+            ..Self::new(center, normal, radius)
+        }
+    }
+
+    pub fn normal(&self) -> Vec3 {
+        self.frame.n
+    }
+}
+
+impl Hittable for Disk {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        let denom = self.normal().dot(&ray.dir);
+        // Parallel to the disk
+        if denom.abs() < 1e-12 {
+            return false;
+        }
+
+        let t = self.normal().dot(&(self.center - ray.orig)) / denom;
+        if t < t_min || t > t_max {
+            return false;
+        }
+
+        let p = ray.at(t);
+        let local = self.frame.to_local(p - self.center);
+        let r2 = local.x() * local.x() + local.y() * local.y();
+        if r2 > self.radius * self.radius {
+            return false;
+        }
+
+        rec.t = t;
+        rec.p = p;
+        rec.u = (f64::atan2(local.y(), local.x()) + PI) / (2.0 * PI);
+        rec.v = r2.sqrt() / self.radius;
+        rec.set_face_normal(ray, self.normal());
+        rec.material = self.material.clone();
+
+        true
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(Aabb::disk(self.center, self.normal(), self.radius).pad(1e-4))
+    }
+}
+
+impl Sampleable for Disk {
+    fn area(&self) -> f64 {
+        PI * self.radius * self.radius
+    }
+
+    fn sample_surface(&self) -> (Point3, Vec3) {
+        let r = self.radius * random_double().sqrt();
+        let phi = 2.0 * PI * random_double();
+        let local = Vec3::new(r * phi.cos(), r * phi.sin(), 0.0);
+        (self.center + self.frame.to_world(local), self.normal())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hittable::{Disk, HitRecord, Hittable};
+    use crate::renderer::{Ray, Vec3};
+
+    #[test]
+    fn test_disk_uv() {
+        let disk = Disk::new(Vec3::new(0., 0., -2.), Vec3::new(0., 0., 1.), 1.);
+        let ray = Ray::new(Vec3::new(0.5, 0., 0.), Vec3::new(0., 0., -1.));
+        let mut hit_record = HitRecord::default();
+
+        // v is the distance out from the centre
+        assert!(disk.hit(&ray, 0., f64::INFINITY, &mut hit_record));
+        assert!((hit_record.v - 0.5).abs() < 1e-9);
+    }
+}
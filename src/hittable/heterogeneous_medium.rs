@@ -0,0 +1,364 @@
+use std::sync::Arc;
+
+use crate::hittable::{Aabb, HitRecord, Hittable, VoxelGrid};
+use crate::material::{Material, Scatter};
+use crate::renderer::{random_double, Ray, Vec3};
+
+/// How many voxels along each axis share one majorant cell
+const MAJORANT_BLOCK: usize = 8;
+
+/// A coarse grid holding the largest density found in each block of voxels, lets delta tracking
+/// take big steps through thin regions without being held back by the densest part of the volume
+struct MajorantGrid {
+    resolution: [usize; 3],
+    values: Vec<f64>,
+}
+
+impl MajorantGrid {
+    fn new(density: &VoxelGrid) -> Self {
+        let resolution = density.resolution.map(|r| r.div_ceil(MAJORANT_BLOCK));
+        let mut values = vec![0.0; resolution[0] * resolution[1] * resolution[2]];
+
+        for z in 0..resolution[2] {
+            for y in 0..resolution[1] {
+                for x in 0..resolution[0] {
+                    // Interpolation reaches one voxel past the block on each side
+                    let range = |cell: usize, axis: usize| {
+                        let start = (cell * MAJORANT_BLOCK).saturating_sub(1);
+                        let end = ((cell + 1) * MAJORANT_BLOCK + 1).min(density.resolution[axis]);
+                        start..end
+                    };
+                    let mut max: f64 = 0.0;
+                    for k in range(z, 2) {
+                        for j in range(y, 1) {
+                            for i in range(x, 0) {
+                                max = max.max(density.voxel(i, j, k));
+                            }
+                        }
+                    }
+                    values[x + resolution[0] * (y + resolution[1] * z)] = max;
+                }
+            }
+        }
+
+        Self { resolution, values }
+    }
+
+    /// Walks the cells the ray passes through between `t_start` and `t_end` (3D DDA), calling
+    /// `visit` with the range of each cell and its majorant until it returns true
+    fn traverse(
+        &self,
+        density: &VoxelGrid,
+        ray: &Ray,
+        t_start: f64,
+        t_end: f64,
+        mut visit: impl FnMut(f64, f64, f64) -> bool,
+    ) {
+        let size = density.bounds.size();
+        let entry = ray.at(t_start) - density.bounds.min;
+
+        let mut cell = [0i64; 3];
+        let mut step = [0i64; 3];
+        let mut t_next = [f64::INFINITY; 3];
+        let mut t_delta = [f64::INFINITY; 3];
+        for axis in 0..3 {
+            let res = self.resolution[axis];
+            // Cells cover whole blocks of voxels, so the last one can hang past the bounds
+            let cell_size = MAJORANT_BLOCK as f64 * size.0[axis] / density.resolution[axis] as f64;
+            let c = ((entry.0[axis] / cell_size) as i64).clamp(0, res as i64 - 1);
+            cell[axis] = c;
+
+            let d = ray.dir.0[axis];
+            if d > 0.0 {
+                step[axis] = 1;
+                let boundary = (density.bounds.min.0[axis] + (c + 1) as f64 * cell_size)
+                    .min(density.bounds.max.0[axis]);
+                t_next[axis] = (boundary - ray.orig.0[axis]) / d;
+                t_delta[axis] = cell_size / d;
+            } else if d < 0.0 {
+                step[axis] = -1;
+                let boundary = density.bounds.min.0[axis] + c as f64 * cell_size;
+                t_next[axis] = (boundary - ray.orig.0[axis]) / d;
+                t_delta[axis] = -cell_size / d;
+            }
+        }
+
+        let mut t = t_start;
+        while t < t_end {
+            let axis = if t_next[0] < t_next[1] {
+                if t_next[0] < t_next[2] {
+                    0
+                } else {
+                    2
+                }
+            } else if t_next[1] < t_next[2] {
+                1
+            } else {
+                2
+            };
+            let t_cell_end = t_next[axis].min(t_end);
+
+            let index = cell[0] as usize
+                + self.resolution[0] * (cell[1] as usize + self.resolution[1] * cell[2] as usize);
+            if visit(t, t_cell_end, self.values[index]) {
+                return;
+            }
+
+            t = t_cell_end;
+            cell[axis] += step[axis];
+            if cell[axis] < 0 || cell[axis] >= self.resolution[axis] as i64 {
+                return;
+            }
+            t_next[axis] += t_delta[axis];
+        }
+    }
+}
+
+/// The material handed out at real collisions, emission comes from the volume's temperature grid
+#[derive(Debug)]
+struct VolumeMaterial {
+    phase_function: Arc<dyn Material>,
+    albedo: f64,
+    emission: Option<Arc<VoxelGrid>>,
+    emission_colour: Vec3,
+}
+
+impl Material for VolumeMaterial {
+    fn scatter(&self, ray_in: &Ray, rec: &HitRecord) -> Option<Scatter> {
+        self.phase_function
+            .scatter(ray_in, rec)
+            .map(|scatter| Scatter {
+                attenuation: self.albedo * scatter.attenuation,
+                ..scatter
+            })
+    }
+
+    fn eval(&self, wo: Vec3, wi: Vec3, rec: &HitRecord) -> Vec3 {
+        self.albedo * self.phase_function.eval(wo, wi, rec)
+    }
+
+    fn pdf(&self, wo: Vec3, wi: Vec3, rec: &HitRecord) -> f64 {
+        self.phase_function.pdf(wo, wi, rec)
+    }
+
+    fn is_medium(&self) -> bool {
+        true
+    }
+
+    fn emitted(&self, rec: &HitRecord) -> Vec3 {
+        match &self.emission {
+            // Only the absorbed fraction of collisions emit
+            Some(grid) => ((1.0 - self.albedo) * grid.sample(rec.p)) * self.emission_colour,
+            None => Vec3::new(0.0, 0.0, 0.0),
+        }
+    }
+}
+
+/// A volume whose density varies through space, read from a voxel grid
+pub struct HeterogeneousMedium {
+    density: VoxelGrid,
+    density_scale: f64,
+    majorants: MajorantGrid,
+    material: Arc<VolumeMaterial>,
+}
+
+impl HeterogeneousMedium {
+    /// Creates a new medium filling the bounds of `density`
+    /// # Arguments
+    /// * `density` - Density values, multiplied by `density_scale` to get the extinction coefficient
+    /// * `density_scale` - Scales the grid values, higher is thicker
+    /// * `albedo` - The fraction of collisions that scatter rather than absorb
+    /// * `phase_function` - Decides which direction a ray scatters in
+    pub fn new(
+        density: VoxelGrid,
+        density_scale: f64,
+        albedo: f64,
+        phase_function: Arc<dyn Material>,
+    ) -> Self {
+        let majorants = MajorantGrid::new(&density);
+        Self {
+            density,
+            density_scale,
+            majorants,
+            material: Arc::new(VolumeMaterial {
+                phase_function,
+                albedo: albedo.clamp(0.0, 1.0),
+                emission: None,
+                emission_colour: Vec3::new(0.0, 0.0, 0.0),
+            }),
+        }
+    }
+
+    /// Makes the absorbing part of the volume glow, for fire. `emission` is usually a temperature
+    /// grid, its values are multiplied by `colour` to give the emitted radiance
+    pub fn with_emission(mut self, emission: VoxelGrid, colour: Vec3) -> Self {
+        let material = VolumeMaterial {
+            phase_function: self.material.phase_function.clone(),
+            albedo: self.material.albedo,
+            emission: Some(Arc::new(emission)),
+            emission_colour: colour,
+        };
+        self.material = Arc::new(material);
+        self
+    }
+
+    fn extinction(&self, ray: &Ray, t: f64) -> f64 {
+        self.density_scale * self.density.sample(ray.at(t))
+    }
+
+    /// Estimates the fraction of light that makes it between `t_min` and `t_max` along the ray,
+    /// using ratio tracking
+    pub fn transmittance(&self, ray: &Ray, t_min: f64, t_max: f64) -> f64 {
+        let Some((t_start, t_end)) = self.density.bounds.hit(ray, t_min, t_max) else {
+            return 1.0;
+        };
+
+        let ray_length = ray.dir.length();
+        let mut transmittance = 1.0;
+        self.majorants
+            .traverse(&self.density, ray, t_start, t_end, |t0, t1, majorant| {
+                let majorant = majorant * self.density_scale * ray_length;
+                if majorant <= 0.0 {
+                    return false;
+                }
+                let mut t = t0;
+                loop {
+                    t -= (1.0 - random_double()).ln() / majorant;
+                    if t >= t1 {
+                        return false;
+                    }
+                    transmittance *= 1.0 - self.extinction(ray, t) * ray_length / majorant;
+                    if transmittance <= 0.0 {
+                        return true;
+                    }
+                }
+            });
+        transmittance.max(0.0)
+    }
+}
+
+impl Hittable for HeterogeneousMedium {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        let Some((t_start, t_end)) = self.density.bounds.hit(ray, t_min, t_max) else {
+            return false;
+        };
+
+        // Delta tracking, sample tentative collisions against the majorant and accept them with
+        // probability density / majorant, the rest are null collisions
+        let ray_length = ray.dir.length();
+        let mut collision = None;
+        self.majorants
+            .traverse(&self.density, ray, t_start, t_end, |t0, t1, majorant| {
+                let majorant = majorant * self.density_scale * ray_length;
+                if majorant <= 0.0 {
+                    return false;
+                }
+                let mut t = t0;
+                loop {
+                    t -= (1.0 - random_double()).ln() / majorant;
+                    if t >= t1 {
+                        return false;
+                    }
+                    if random_double() * majorant < self.extinction(ray, t) * ray_length {
+                        collision = Some(t);
+                        return true;
+                    }
+                }
+            });
+
+        match collision {
+            Some(t) => {
+                rec.t = t;
+                rec.p = ray.at(t);
+                rec.normal = Vec3::new(1.0, 0.0, 0.0);
+                rec.front_face = true;
+                rec.material = Some(self.material.clone());
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(self.density.bounds)
+    }
+
+    fn transmittance(&self, ray: &Ray, t_min: f64, t_max: f64) -> f64 {
+        HeterogeneousMedium::transmittance(self, ray, t_min, t_max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::hittable::heterogeneous_medium::MajorantGrid;
+    use crate::hittable::{Aabb, HeterogeneousMedium, HitRecord, Hittable, VoxelGrid};
+    use crate::material::Isotropic;
+    use crate::renderer::{Ray, Vec3};
+
+    #[test]
+    fn test_heterogeneous_transmittance() {
+        // A constant grid should match Beer-Lambert
+        let bounds = Aabb::new(Vec3::new(-1., -1., -3.), Vec3::new(1., 1., -1.));
+        let grid = VoxelGrid::new([20, 20, 20], bounds, vec![1.0; 8000]);
+        let medium = HeterogeneousMedium::new(
+            grid,
+            0.5,
+            1.0,
+            Arc::new(Isotropic::new(Vec3::new(1., 1., 1.))),
+        );
+        let ray = Ray::new(Vec3::new(0.1, 0.1, 0.), Vec3::new(0., 0., -1.));
+
+        let runs = 20_000;
+        let mean: f64 = (0..runs)
+            .map(|_| medium.transmittance(&ray, 0., f64::INFINITY))
+            .sum::<f64>()
+            / runs as f64;
+        assert!((mean - f64::exp(-1.)).abs() < 0.02);
+
+        let mut hits = 0;
+        let mut hit_record = HitRecord {
+            ..Default::default()
+        };
+        for _ in 0..runs {
+            if medium.hit(&ray, 0., f64::INFINITY, &mut hit_record) {
+                hits += 1;
+                assert!(hit_record.t >= 1. && hit_record.t <= 3.);
+            }
+        }
+        assert!((hits as f64 / runs as f64 - (1. - f64::exp(-1.))).abs() < 0.02);
+    }
+
+    #[test]
+    fn test_majorants_cover_density() {
+        // Twenty voxels don't split into blocks of eight evenly, and a thin slab near the end
+        // of a block is the easiest thing to miss
+        let bounds = Aabb::new(Vec3::new(0., 0., 0.), Vec3::new(20., 20., 20.));
+        let mut data = vec![0.0; 8000];
+        for z in 0..20 {
+            for y in 0..20 {
+                data[13 + 20 * (y + 20 * z)] = 1.0;
+            }
+        }
+        let grid = VoxelGrid::new([20, 20, 20], bounds, data);
+        let majorants = MajorantGrid::new(&grid);
+
+        for dir in [Vec3::new(1., 0., 0.), Vec3::new(-1., 0.1, 0.2)] {
+            let start = if dir.x() > 0. { 0. } else { 20. };
+            let ray = Ray::new(Vec3::new(start, 7.3, 5.1), dir);
+            let (t_start, t_end) = grid.bounds.hit(&ray, 0., f64::INFINITY).unwrap();
+            let mut covered = t_start;
+            majorants.traverse(&grid, &ray, t_start, t_end, |t0, t1, majorant| {
+                assert!((t0 - covered).abs() < 1e-9);
+                covered = t1;
+                for i in 0..100 {
+                    let t = t0 + (t1 - t0) * (i as f64 + 0.5) / 100.;
+                    assert!(grid.sample(ray.at(t)) <= majorant, "{t} in {t0}..{t1}");
+                }
+                false
+            });
+            assert!((covered - t_end).abs() < 1e-9);
+        }
+    }
+}
@@ -1,12 +1,32 @@
-use crate::renderer::Vec3;
+use std::sync::Arc;
+
+use crate::material::Material;
+use crate::renderer::{Ray, Vec3};
 
 type Point3 = Vec3;
 
-#[derive(Copy, Clone, PartialEq, Debug)]
+#[derive(Clone, Debug)]
 pub struct HitRecord {
     pub p: Point3,
     pub normal: Vec3,
     pub t: f64,
+    /// Surface coordinates of the hit, for texturing
+    pub u: f64,
+    pub v: f64,
+    pub front_face: bool,
+    pub material: Option<Arc<dyn Material>>,
+}
+
+impl HitRecord {
+    /// Stores the normal so that it always points against the incoming ray
+    pub fn set_face_normal(&mut self, ray: &Ray, outward_normal: Vec3) {
+        self.front_face = ray.dir.dot(&outward_normal) < 0.0;
+        self.normal = if self.front_face {
+            outward_normal
+        } else {
+            -outward_normal
+        };
+    }
 }
 
 impl Default for HitRecord {
@@ -15,6 +35,10 @@ impl Default for HitRecord {
             p: Vec3::new(0.0, 0.0, 0.0),
             normal: Vec3::new(0.0, 0.0, 0.0),
             t: 0.0,
+            u: 0.0,
+            v: 0.0,
+            front_face: true,
+            material: None,
         }
     }
 }
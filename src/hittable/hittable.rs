@@ -1,6 +1,157 @@
-use crate::hittable::HitRecord;
+use std::sync::Arc;
+
+use crate::hittable::{Aabb, HitRecord, Interval};
 use crate::renderer::Ray;
 
-pub trait Hittable {
+pub trait Hittable: Send + Sync {
     fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool;
+
+    /// A box the object fits inside, None for unbounded objects
+    fn bounding_box(&self) -> Option<Aabb> {
+        None
+    }
+
+    /// The fraction of light which passes along the ray between `t_min` and `t_max`, solid
+    /// objects block everything while media let some through
+    fn transmittance(&self, ray: &Ray, t_min: f64, t_max: f64) -> f64 {
+        let mut rec = HitRecord::default();
+        if self.hit(ray, t_min, t_max, &mut rec) {
+            0.0
+        } else {
+            1.0
+        }
+    }
+
+    /// Every stretch of the ray's line spent inside the object, sorted along the ray. Needed by
+    /// CSG, so only closed objects can report them, everything else returns None
+    fn intervals(&self, _ray: &Ray) -> Option<Vec<Interval>> {
+        None
+    }
+}
+
+/// Lets one object be shared, for instance between the world and a light
+impl<T: Hittable + ?Sized> Hittable for Arc<T> {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        (**self).hit(ray, t_min, t_max, rec)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        (**self).bounding_box()
+    }
+
+    fn transmittance(&self, ray: &Ray, t_min: f64, t_max: f64) -> f64 {
+        (**self).transmittance(ray, t_min, t_max)
+    }
+
+    fn intervals(&self, ray: &Ray) -> Option<Vec<Interval>> {
+        (**self).intervals(ray)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hittable::{Cone, Csg, Cylinder, Disk, HitRecord, Hittable, Quad, Sphere, Torus};
+    use crate::renderer::{Ray, Vec3};
+
+    #[test]
+    fn test_shapes_hit_and_miss() {
+        let down_z = |x: f64, y: f64| Ray::new(Vec3::new(x, y, 0.), Vec3::new(0., 0., -1.));
+        // Each shape with a ray that hits it, where and which way it faces, and one that misses
+        let shapes: Vec<(&str, Box<dyn Hittable>, Ray, f64, Vec3, Ray)> = vec![
+            (
+                "sphere",
+                Box::new(Sphere::new(Vec3::new(0., 0., -2.), 1.5)),
+                down_z(0., 0.),
+                0.5,
+                Vec3::new(0., 0., 1.),
+                down_z(2., 0.),
+            ),
+            (
+                "cone",
+                Box::new(Cone::new(
+                    Vec3::new(0., -1., -2.),
+                    Vec3::new(0., 1., 0.),
+                    1.,
+                    2.,
+                )),
+                // Halfway up the cone is half the base radius
+                down_z(0., 0.),
+                1.5,
+                Vec3::new(0., 0.5, 1.).unit_vector(),
+                down_z(1.5, 0.),
+            ),
+            (
+                "cylinder",
+                Box::new(Cylinder::new(
+                    Vec3::new(0., -1., -2.),
+                    Vec3::new(0., 1., 0.),
+                    0.5,
+                    2.,
+                )),
+                down_z(0., 0.),
+                1.5,
+                Vec3::new(0., 0., 1.),
+                down_z(0.6, 0.),
+            ),
+            (
+                "disk",
+                Box::new(Disk::new(Vec3::new(0., 0., -2.), Vec3::new(0., 0., 1.), 1.)),
+                down_z(0.5, 0.),
+                2.,
+                Vec3::new(0., 0., 1.),
+                down_z(1.5, 0.),
+            ),
+            (
+                "quad",
+                Box::new(Quad::new(
+                    Vec3::new(-1., -1., -2.),
+                    Vec3::new(2., 0., 0.),
+                    Vec3::new(0., 2., 0.),
+                )),
+                down_z(0.5, 0.),
+                2.,
+                Vec3::new(0., 0., 1.),
+                down_z(1.5, 0.),
+            ),
+            (
+                "torus",
+                Box::new(Torus::new(
+                    Vec3::new(0., 0., -5.),
+                    Vec3::new(0., 1., 0.),
+                    2.,
+                    0.5,
+                )),
+                down_z(0., 0.),
+                2.5,
+                Vec3::new(0., 0., 1.),
+                down_z(0., 1.),
+            ),
+            (
+                "csg",
+                // A lens from two overlapping spheres
+                Box::new(Csg::intersection(
+                    Box::new(Sphere::new(Vec3::new(0., 0., -1.5), 1.)),
+                    Box::new(Sphere::new(Vec3::new(0., 0., -2.5), 1.)),
+                )),
+                down_z(0., 0.),
+                1.5,
+                Vec3::new(0., 0., 1.),
+                down_z(0.9, 0.),
+            ),
+        ];
+
+        for (name, shape, hit, t, normal, miss) in shapes {
+            let mut rec = HitRecord::default();
+            assert!(shape.hit(&hit, 0., f64::INFINITY, &mut rec), "{name}");
+            assert!((rec.t - t).abs() < 1e-6, "{name} hit at {}", rec.t);
+            assert!((rec.normal - normal).length() < 1e-6, "{name}");
+            assert!(rec.front_face, "{name}");
+            // Nearer than the hit is nothing
+            assert!(!shape.hit(&hit, 0., t - 0.01, &mut rec), "{name}");
+            if let Some(bounds) = shape.bounding_box() {
+                assert!(bounds.hit(&hit, 0., f64::INFINITY).is_some(), "{name}");
+            }
+            assert!(!shape.hit(&miss, 0., f64::INFINITY, &mut rec), "{name}");
+        }
+    }
 }
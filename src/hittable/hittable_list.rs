@@ -1,4 +1,4 @@
-use crate::hittable::{HitRecord, Hittable};
+use crate::hittable::{combine_intervals, Aabb, CsgOp, HitRecord, Hittable, Interval};
 use crate::renderer::Ray;
 
 use std::vec::Vec;
@@ -31,10 +31,39 @@ impl Hittable for HittableList {
             if hittable.hit(ray, t_min, closest_so_far, &mut temp_rec) {
                 hit_anything = true;
                 closest_so_far = temp_rec.t;
-                *rec = temp_rec;
+                *rec = temp_rec.clone();
             }
         }
 
         hit_anything
     }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let mut hittables = self.world.iter();
+        let mut aabb = hittables.next()?.bounding_box()?;
+        for hittable in hittables {
+            aabb = aabb.surrounding(&hittable.bounding_box()?);
+        }
+        Some(aabb)
+    }
+
+    fn transmittance(&self, ray: &Ray, t_min: f64, t_max: f64) -> f64 {
+        let mut transmittance = 1.0;
+        for hittable in &self.world {
+            transmittance *= hittable.transmittance(ray, t_min, t_max);
+            if transmittance <= 0.0 {
+                return 0.0;
+            }
+        }
+        transmittance
+    }
+
+    /// The union of every object, if they are all closed
+    fn intervals(&self, ray: &Ray) -> Option<Vec<Interval>> {
+        let mut intervals = Vec::new();
+        for hittable in &self.world {
+            intervals = combine_intervals(CsgOp::Union, intervals, hittable.intervals(ray)?);
+        }
+        Some(intervals)
+    }
 }
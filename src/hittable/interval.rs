@@ -0,0 +1,45 @@
+use crate::hittable::HitRecord;
+use crate::renderer::Ray;
+
+/// A stretch of a ray spent inside a closed object. The records' normals point out of the
+/// object rather than against the ray
+#[derive(Clone, Debug)]
+pub struct Interval {
+    pub enter: HitRecord,
+    pub exit: HitRecord,
+}
+
+impl Interval {
+    /// Pairs up every crossing of a closed surface along the whole line of a ray, sorted by t.
+    /// The line starts outside so crossings alternate between entering and exiting
+    pub fn from_boundaries(boundaries: Vec<HitRecord>) -> Vec<Interval> {
+        let mut intervals = Vec::with_capacity(boundaries.len() / 2);
+        let mut iter = boundaries.into_iter();
+        while let (Some(enter), Some(exit)) = (iter.next(), iter.next()) {
+            intervals.push(Interval { enter, exit });
+        }
+        intervals
+    }
+}
+
+/// Finds the first surface crossing between `t_min` and `t_max` from a list sorted by t, and
+/// turns its outward normal to face the ray
+pub fn nearest_boundary<'a>(
+    ray: &Ray,
+    boundaries: impl IntoIterator<Item = &'a HitRecord>,
+    t_min: f64,
+    t_max: f64,
+    rec: &mut HitRecord,
+) -> bool {
+    for boundary in boundaries {
+        if boundary.t > t_max {
+            return false;
+        }
+        if boundary.t >= t_min {
+            *rec = boundary.clone();
+            rec.set_face_normal(ray, boundary.normal);
+            return true;
+        }
+    }
+    false
+}
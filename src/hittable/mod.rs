@@ -1,14 +1,39 @@
+mod aabb;
+mod cone;
+mod constant_medium;
+mod csg;
 mod cube;
+mod cylinder;
+mod disk;
+mod heterogeneous_medium;
 mod hit_record;
 mod hittable;
 mod hittable_list;
+mod interval;
 mod plane;
+mod polynomial;
+mod quad;
+mod sampleable;
 mod sphere;
+mod torus;
 mod triangle;
+mod voxel_grid;
 
+pub use aabb::Aabb;
+pub use cone::Cone;
+pub use constant_medium::ConstantMedium;
+pub use csg::{combine_intervals, Csg, CsgOp};
+pub use cylinder::Cylinder;
+pub use disk::Disk;
+pub use heterogeneous_medium::HeterogeneousMedium;
 pub use hit_record::HitRecord;
 pub use hittable::Hittable;
 pub use hittable_list::HittableList;
+pub use interval::{nearest_boundary, Interval};
 pub use plane::Plane;
+pub use quad::Quad;
+pub use sampleable::Sampleable;
 pub use sphere::Sphere;
+pub use torus::Torus;
 pub use triangle::Triangle;
+pub use voxel_grid::VoxelGrid;
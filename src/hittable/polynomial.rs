@@ -0,0 +1,147 @@
+//! Closed form polynomial root finding (after Schwarze, Graphics Gems I), coefficients are
+//! given lowest order first
+
+const EPSILON: f64 = 1e-9;
+
+fn is_zero(x: f64) -> bool {
+    x.abs() < EPSILON
+}
+
+/// Real roots of c[2] x^2 + c[1] x + c[0]
+pub fn solve_quadratic(c: [f64; 3]) -> Vec<f64> {
+    let p = c[1] / (2.0 * c[2]);
+    let q = c[0] / c[2];
+    let discriminant = p * p - q;
+
+    if is_zero(discriminant) {
+        vec![-p]
+    } else if discriminant < 0.0 {
+        Vec::new()
+    } else {
+        let sqrt_d = discriminant.sqrt();
+        vec![sqrt_d - p, -sqrt_d - p]
+    }
+}
+
+/// Real roots of c[3] x^3 + c[2] x^2 + c[1] x + c[0]
+pub fn solve_cubic(c: [f64; 4]) -> Vec<f64> {
+    // Normal form x^3 + Ax^2 + Bx + C = 0
+    let a = c[2] / c[3];
+    let b = c[1] / c[3];
+    let c = c[0] / c[3];
+
+    // Substitute x = y - A/3 to eliminate the quadratic term, y^3 + 3py + 2q = 0
+    let sq_a = a * a;
+    let p = (-sq_a / 3.0 + b) / 3.0;
+    let q = (2.0 / 27.0 * a * sq_a - a * b / 3.0 + c) / 2.0;
+
+    let cb_p = p * p * p;
+    let discriminant = q * q + cb_p;
+
+    let mut roots = if is_zero(discriminant) {
+        if is_zero(q) {
+            vec![0.0]
+        } else {
+            let u = (-q).cbrt();
+            vec![2.0 * u, -u]
+        }
+    } else if discriminant < 0.0 {
+        // Three real roots
+        let phi = (-q / (-cb_p).sqrt()).clamp(-1.0, 1.0).acos() / 3.0;
+        let t = 2.0 * (-p).sqrt();
+        vec![
+            t * phi.cos(),
+            -t * (phi + std::f64::consts::PI / 3.0).cos(),
+            -t * (phi - std::f64::consts::PI / 3.0).cos(),
+        ]
+    } else {
+        let sqrt_d = discriminant.sqrt();
+        vec![(sqrt_d - q).cbrt() - (sqrt_d + q).cbrt()]
+    };
+
+    roots.iter_mut().for_each(|root| *root -= a / 3.0);
+    roots
+}
+
+/// Real roots of c[4] x^4 + c[3] x^3 + c[2] x^2 + c[1] x + c[0], polished with a few Newton
+/// steps as the closed form loses precision
+pub fn solve_quartic(coefficients: [f64; 5]) -> Vec<f64> {
+    let c = coefficients;
+    // Normal form x^4 + Ax^3 + Bx^2 + Cx + D = 0
+    let a = c[3] / c[4];
+    let b = c[2] / c[4];
+    let cc = c[1] / c[4];
+    let d = c[0] / c[4];
+
+    // Substitute x = y - A/4 to eliminate the cubic term, y^4 + py^2 + qy + r = 0
+    let sq_a = a * a;
+    let p = -3.0 / 8.0 * sq_a + b;
+    let q = sq_a * a / 8.0 - a * b / 2.0 + cc;
+    let r = -3.0 / 256.0 * sq_a * sq_a + sq_a * b / 16.0 - a * cc / 4.0 + d;
+
+    let mut roots = if is_zero(r) {
+        // No absolute term, y(y^3 + py + q) = 0
+        let mut roots = solve_cubic([q, p, 0.0, 1.0]);
+        roots.push(0.0);
+        roots
+    } else {
+        // Solve the resolvent cubic and use one of its roots to split into two quadratics
+        let z = solve_cubic([r * p / 2.0 - q * q / 8.0, -r, -p / 2.0, 1.0])[0];
+
+        let u = z * z - r;
+        let v = 2.0 * z - p;
+        let u = if is_zero(u) {
+            0.0
+        } else if u > 0.0 {
+            u.sqrt()
+        } else {
+            return Vec::new();
+        };
+        let v = if is_zero(v) {
+            0.0
+        } else if v > 0.0 {
+            v.sqrt()
+        } else {
+            return Vec::new();
+        };
+
+        let v = if q < 0.0 { -v } else { v };
+        let mut roots = solve_quadratic([z - u, v, 1.0]);
+        roots.extend(solve_quadratic([z + u, -v, 1.0]));
+        roots
+    };
+
+    let eval = |x: f64| (((c[4] * x + c[3]) * x + c[2]) * x + c[1]) * x + c[0];
+    let derivative = |x: f64| ((4.0 * c[4] * x + 3.0 * c[3]) * x + 2.0 * c[2]) * x + c[1];
+    for root in roots.iter_mut() {
+        *root -= a / 4.0;
+        for _ in 0..3 {
+            let slope = derivative(*root);
+            if slope.abs() < EPSILON {
+                break;
+            }
+            *root -= eval(*root) / slope;
+        }
+    }
+    roots
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hittable::polynomial::solve_quartic;
+
+    #[test]
+    fn test_solve_quartic() {
+        // (x - 1)(x - 2)(x + 3)(x - 0.5)
+        let mut roots = solve_quartic([-3., 9.5, -7., -0.5, 1.]);
+        roots.sort_by(f64::total_cmp);
+
+        assert_eq!(roots.len(), 4);
+        for (root, expected) in roots.iter().zip([-3., 0.5, 1., 2.]) {
+            assert!((root - expected).abs() < 1e-9);
+        }
+
+        // x^4 + 1 has no real roots
+        assert!(solve_quartic([1., 0., 0., 0., 1.]).is_empty());
+    }
+}
@@ -0,0 +1,126 @@
+use std::sync::Arc;
+
+use crate::hittable::{Aabb, HitRecord, Hittable, Sampleable};
+use crate::material::Material;
+use crate::renderer::{random_double, Ray, Vec3};
+
+use Vec3 as Point3;
+
+/// A parallelogram with one corner at `q` and sides `u` and `v`
+pub struct Quad {
+    pub q: Point3,
+    pub u: Vec3,
+    pub v: Vec3,
+    pub material: Option<Arc<dyn Material>>,
+    normal: Vec3,
+    d: f64,
+    w: Vec3,
+}
+
+impl Quad {
+    pub fn new(q: Point3, u: Vec3, v: Vec3) -> Self {
+        let n = u.cross(&v);
+        let normal = n.unit_vector();
+        Self {
+            q,
+            u,
+            v,
+            material: None,
+            normal,
+            d: normal.dot(&q),
+            w: n / n.dot(&n),
+        }
+    }
+
+    pub fn with_material(q: Point3, u: Vec3, v: Vec3, material: Arc<dyn Material>) -> Self {
+        Self {
+            material: Some(material),
+            ..Self::new(q, u, v)
+        }
+    }
+
+    pub fn normal(&self) -> Vec3 {
+        self.normal
+    }
+
+    pub fn area(&self) -> f64 {
+        self.u.cross(&self.v).length()
+    }
+}
+
+impl Hittable for Quad {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        let denom = self.normal.dot(&ray.dir);
+        // Parallel to the plane
+        if denom.abs() < 1e-12 {
+            return false;
+        }
+
+        let t = (self.d - self.normal.dot(&ray.orig)) / denom;
+        if t < t_min || t > t_max {
+            return false;
+        }
+
+        // Express the hit in terms of the sides to check it's inside
+        let p = ray.at(t);
+        let planar = p - self.q;
+        let alpha = self.w.dot(&planar.cross(&self.v));
+        let beta = self.w.dot(&self.u.cross(&planar));
+        if !(0.0..=1.0).contains(&alpha) || !(0.0..=1.0).contains(&beta) {
+            return false;
+        }
+
+        rec.t = t;
+        rec.p = p;
+        rec.u = alpha;
+        rec.v = beta;
+        rec.set_face_normal(ray, self.normal);
+        rec.material = self.material.clone();
+
+        true
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(
+            Aabb::from_points(&[
+                self.q,
+                self.q + self.u,
+                self.q + self.v,
+                self.q + self.u + self.v,
+            ])
+            .pad(1e-4),
+        )
+    }
+}
+
+impl Sampleable for Quad {
+    fn area(&self) -> f64 {
+        Quad::area(self)
+    }
+
+    fn sample_surface(&self) -> (Point3, Vec3) {
+        let p = self.q + random_double() * self.u + random_double() * self.v;
+        (p, self.normal)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hittable::{HitRecord, Hittable, Quad};
+    use crate::renderer::{Ray, Vec3};
+
+    #[test]
+    fn test_quad_uv() {
+        let quad = Quad::new(
+            Vec3::new(-1., -1., -2.),
+            Vec3::new(2., 0., 0.),
+            Vec3::new(0., 2., 0.),
+        );
+        let ray = Ray::new(Vec3::new(0.5, 0., 0.), Vec3::new(0., 0., -1.));
+        let mut hit_record = HitRecord::default();
+
+        assert!(quad.hit(&ray, 0., f64::INFINITY, &mut hit_record));
+        assert!((hit_record.u - 0.75).abs() < 1e-9);
+        assert!((hit_record.v - 0.5).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,12 @@
+use crate::hittable::Hittable;
+use crate::renderer::Vec3;
+
+use Vec3 as Point3;
+
+/// A surface that points can be picked on uniformly, needed for area lights
+pub trait Sampleable: Hittable {
+    fn area(&self) -> f64;
+
+    /// A uniformly distributed point on the surface and the outward normal there
+    fn sample_surface(&self) -> (Point3, Vec3);
+}
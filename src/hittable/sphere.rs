@@ -1,14 +1,51 @@
-use crate::hittable::{HitRecord, Hittable};
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use crate::hittable::{Aabb, HitRecord, Hittable, Interval, Sampleable};
+use crate::material::Material;
 use crate::renderer::{Ray, Vec3};
 
 pub struct Sphere {
     pub center: Vec3,
     pub radius: f64,
+    pub material: Option<Arc<dyn Material>>,
 }
 
 impl Sphere {
     pub fn new(center: Vec3, radius: f64) -> Self {
-        Self { center, radius }
+        Self {
+            center,
+            radius,
+            material: None,
+        }
+    }
+
+    pub fn with_material(center: Vec3, radius: f64, material: Arc<dyn Material>) -> Self {
+        Self {
+            center,
+            radius,
+            material: Some(material),
+        }
+    }
+
+    /// The hit record where the ray crosses the surface at `t`, with the outward normal
+    fn boundary(&self, ray: &Ray, t: f64) -> HitRecord {
+        let p = ray.at(t);
+        let normal = (p - self.center) / self.radius;
+
+        // Longitude around the y axis and latitude from the bottom
+        let theta = f64::acos((-normal.y()).clamp(-1.0, 1.0));
+        let phi = f64::atan2(-normal.z(), normal.x()) + PI;
+
+        HitRecord {
+            p,
+            normal,
+            t,
+            u: phi / (2.0 * PI),
+            v: theta / PI,
+            front_face: ray.dir.dot(&normal) < 0.0,
+            material: self.material.clone(),
+        }
     }
 }
 
@@ -24,20 +61,52 @@ impl Hittable for Sphere {
             return false;
         }
         let sqrtd = f64::sqrt(discriminant);
-        let root = (-half_b - sqrtd) / a;
+        let mut root = (-half_b - sqrtd) / a;
         if root < t_min || t_max < root {
-            let root = (-half_b + sqrtd) / a;
+            root = (-half_b + sqrtd) / a;
             if root < t_min || t_max < root {
                 return false;
             };
         }
 
-        rec.t = root;
-        rec.p = ray.at(rec.t);
-        rec.normal = (rec.p - self.center) / self.radius;
+        *rec = self.boundary(ray, root);
+        rec.set_face_normal(ray, rec.normal);
 
         true
     }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let extent = Vec3::new(self.radius, self.radius, self.radius);
+        Some(Aabb::new(self.center - extent, self.center + extent))
+    }
+
+    fn intervals(&self, ray: &Ray) -> Option<Vec<Interval>> {
+        let oc = ray.orig - self.center;
+        let a = ray.dir.length_squared();
+        let half_b = oc.dot(&ray.dir);
+        let c = oc.length_squared() - self.radius * self.radius;
+
+        let discriminant = half_b * half_b - a * c;
+        if discriminant <= 0.0 {
+            return Some(Vec::new());
+        }
+        let sqrtd = f64::sqrt(discriminant);
+        Some(vec![Interval {
+            enter: self.boundary(ray, (-half_b - sqrtd) / a),
+            exit: self.boundary(ray, (-half_b + sqrtd) / a),
+        }])
+    }
+}
+
+impl Sampleable for Sphere {
+    fn area(&self) -> f64 {
+        4.0 * PI * self.radius * self.radius
+    }
+
+    fn sample_surface(&self) -> (Vec3, Vec3) {
+        let normal = Vec3::random_unit_vector();
+        (self.center + self.radius * normal, normal)
+    }
 }
 
 #[cfg(test)]
@@ -50,6 +119,7 @@ mod tests {
         let sphere = Sphere {
             center: Vec3::new(0., 0., -2.),
             radius: 1.5,
+            material: None,
         };
         let ray = Ray::new(Vec3::new(0., 0., 0.), Vec3::new(0., 0., -1.));
         let mut hit_record = HitRecord {
@@ -57,6 +127,6 @@ mod tests {
         };
 
         let hit = sphere.hit(&ray, 0., f64::INFINITY, &mut hit_record);
-        assert_eq!(hit, true);
+        assert!(hit);
     }
 }
@@ -0,0 +1,148 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use crate::hittable::polynomial::solve_quartic;
+use crate::hittable::{nearest_boundary, Aabb, HitRecord, Hittable, Interval};
+use crate::material::Material;
+use crate::renderer::{Frame, Ray, Vec3};
+
+use Vec3 as Point3;
+
+/// A ring doughnut lying flat around `axis`
+pub struct Torus {
+    pub center: Point3,
+    /// Distance from the centre to the middle of the tube
+    pub major_radius: f64,
+    /// Radius of the tube
+    pub minor_radius: f64,
+    pub material: Option<Arc<dyn Material>>,
+    frame: Frame,
+}
+
+impl Torus {
+    pub fn new(center: Point3, axis: Vec3, major_radius: f64, minor_radius: f64) -> Self {
+        Self {
+            center,
+            major_radius,
+            minor_radius,
+            material: None,
+            frame: Frame::from_normal(axis),
+        }
+    }
+
+    pub fn with_material(
+        center: Point3,
+        axis: Vec3,
+        major_radius: f64,
+        minor_radius: f64,
+        material: Arc<dyn Material>,
+    ) -> Self {
+        Self {
+            material: Some(material),
+            ..Self::new(center, axis, major_radius, minor_radius)
+        }
+    }
+
+    pub fn axis(&self) -> Vec3 {
+        self.frame.n
+    }
+
+    /// Every crossing of the surface along the ray's line, sorted by t
+    fn boundaries(&self, ray: &Ray) -> Vec<HitRecord> {
+        let r2 = self.major_radius * self.major_radius;
+        let minor2 = self.minor_radius * self.minor_radius;
+
+        // Work in the torus' frame with a unit direction, the axis is +z
+        let o = self.frame.to_local(ray.orig - self.center);
+        let d = self.frame.to_local(ray.dir);
+        let length = d.length();
+        let d = d / length;
+
+        // The quartic is badly conditioned far from the torus, so start from where the line
+        // enters the bounding sphere
+        let bound = self.major_radius + self.minor_radius;
+        let half_b = o.dot(&d);
+        let discriminant = half_b * half_b - (o.length_squared() - bound * bound);
+        if discriminant < 0.0 {
+            return Vec::new();
+        }
+        let start = -half_b - discriminant.sqrt();
+        let o = o + start * d;
+
+        // (|p|^2 + R^2 - r^2)^2 = 4R^2(x^2 + y^2)
+        let e = o.length_squared() - r2 - minor2;
+        let f = o.dot(&d);
+        let coefficients = [
+            e * e - 4.0 * r2 * (minor2 - o.z() * o.z()),
+            4.0 * f * e + 8.0 * r2 * o.z() * d.z(),
+            2.0 * e + 4.0 * f * f + 4.0 * r2 * d.z() * d.z(),
+            4.0 * f,
+            1.0,
+        ];
+
+        let mut boundaries: Vec<HitRecord> = solve_quartic(coefficients)
+            .into_iter()
+            .map(|s| {
+                let p = o + s * d;
+                let sum = p.length_squared() + r2 - minor2;
+                let local_normal = 4.0 * sum * p - 8.0 * r2 * Vec3::new(p.x(), p.y(), 0.0);
+                let normal = self.frame.to_world(local_normal).unit_vector();
+
+                let ring = f64::sqrt(p.x() * p.x() + p.y() * p.y());
+                let t = (start + s) / length;
+                HitRecord {
+                    p: ray.at(t),
+                    normal,
+                    t,
+                    u: (f64::atan2(p.y(), p.x()) + PI) / (2.0 * PI),
+                    v: (f64::atan2(p.z(), ring - self.major_radius) + PI) / (2.0 * PI),
+                    front_face: ray.dir.dot(&normal) < 0.0,
+                    material: self.material.clone(),
+                }
+            })
+            .collect();
+
+        boundaries.sort_by(|a, b| a.t.total_cmp(&b.t));
+        // Tangent rays give a repeated root, which would throw off the pairing into intervals
+        boundaries.dedup_by(|a, b| (a.t - b.t).abs() < 1e-9);
+        if boundaries.len() % 2 == 1 {
+            return Vec::new();
+        }
+        boundaries
+    }
+}
+
+impl Hittable for Torus {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        nearest_boundary(ray, &self.boundaries(ray), t_min, t_max, rec)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(Aabb::disk(self.center, self.axis(), self.major_radius).pad(self.minor_radius))
+    }
+
+    fn intervals(&self, ray: &Ray) -> Option<Vec<Interval>> {
+        Some(Interval::from_boundaries(self.boundaries(ray)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hittable::{HitRecord, Hittable, Torus};
+    use crate::renderer::{Ray, Vec3};
+
+    #[test]
+    fn test_torus_hole() {
+        let torus = Torus::new(Vec3::new(0., 0., -5.), Vec3::new(0., 1., 0.), 2., 0.5);
+        let mut hit_record = HitRecord::default();
+
+        // Straight down through the hole
+        let ray = Ray::new(Vec3::new(0., 3., -5.), Vec3::new(0., -1., 0.));
+        assert!(!torus.hit(&ray, 0., f64::INFINITY, &mut hit_record));
+        assert_eq!(torus.intervals(&ray).unwrap().len(), 0);
+
+        // Along the axis of the tube passes through both sides
+        let ray = Ray::new(Vec3::new(-5., 0., -5.), Vec3::new(1., 0., 0.));
+        assert_eq!(torus.intervals(&ray).unwrap().len(), 2);
+    }
+}
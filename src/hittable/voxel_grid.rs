@@ -0,0 +1,196 @@
+use std::fs;
+
+use crate::hittable::Aabb;
+use crate::renderer::Vec3;
+
+use Vec3 as Point3;
+
+/// A dense grid of scalar values (density, temperature...) stretched over a bounding box
+#[derive(Clone, Debug)]
+pub struct VoxelGrid {
+    pub resolution: [usize; 3],
+    pub bounds: Aabb,
+    data: Vec<f32>,
+}
+
+impl VoxelGrid {
+    /// Creates a grid from values laid out with x varying fastest, then y, then z
+    pub fn new(resolution: [usize; 3], bounds: Aabb, data: Vec<f32>) -> Self {
+        assert_eq!(
+            data.len(),
+            resolution[0] * resolution[1] * resolution[2],
+            "Voxel data does not match the grid resolution"
+        );
+        Self {
+            resolution,
+            bounds,
+            data,
+        }
+    }
+
+    /// Loads a Mitsuba style `.vol` file, only the first channel is kept
+    ///
+    /// The header is `VOL`, a version byte of 3, then little endian i32s for the encoding
+    /// (1 = f32, 3 = u8), the x, y and z resolution and the channel count, followed by the
+    /// bounding box as six f32s (min xyz, max xyz)
+    pub fn from_vol_file(filename: &str) -> Result<Self, String> {
+        let bytes = fs::read(filename).map_err(|e| format!("Failed to read {filename}: {e}"))?;
+        Self::from_vol_bytes(&bytes)
+    }
+
+    pub fn from_vol_bytes(bytes: &[u8]) -> Result<Self, String> {
+        if bytes.len() < 48 || &bytes[0..3] != b"VOL" {
+            return Err("Not a .vol file".to_string());
+        }
+        if bytes[3] != 3 {
+            return Err(format!("Unsupported .vol version {}", bytes[3]));
+        }
+
+        let read_i32 =
+            |offset: usize| i32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap());
+        let read_f32 =
+            |offset: usize| f32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap());
+
+        let encoding = read_i32(4);
+        let dims = [read_i32(8), read_i32(12), read_i32(16)];
+        let channels = read_i32(20);
+        if dims.iter().any(|&d| d <= 0) || channels <= 0 {
+            return Err("Invalid .vol dimensions".to_string());
+        }
+        let resolution = dims.map(|d| d as usize);
+        let channels = channels as usize;
+
+        let bounds = Aabb::new(
+            Point3::new(
+                read_f32(24) as f64,
+                read_f32(28) as f64,
+                read_f32(32) as f64,
+            ),
+            Point3::new(
+                read_f32(36) as f64,
+                read_f32(40) as f64,
+                read_f32(44) as f64,
+            ),
+        );
+
+        let count = resolution[0] * resolution[1] * resolution[2];
+        let payload = &bytes[48..];
+        let data: Vec<f32> = match encoding {
+            1 => {
+                if payload.len() < count * channels * 4 {
+                    return Err("Truncated .vol data".to_string());
+                }
+                (0..count)
+                    .map(|i| {
+                        let offset = i * channels * 4;
+                        f32::from_le_bytes(payload[offset..offset + 4].try_into().unwrap())
+                    })
+                    .collect()
+            }
+            3 => {
+                if payload.len() < count * channels {
+                    return Err("Truncated .vol data".to_string());
+                }
+                (0..count)
+                    .map(|i| payload[i * channels] as f32 / 255.0)
+                    .collect()
+            }
+            _ => return Err(format!("Unsupported .vol encoding {encoding}")),
+        };
+
+        Ok(Self::new(resolution, bounds, data))
+    }
+
+    /// Loads headerless little endian f32 values, x varying fastest
+    pub fn from_raw_file(
+        filename: &str,
+        resolution: [usize; 3],
+        bounds: Aabb,
+    ) -> Result<Self, String> {
+        let bytes = fs::read(filename).map_err(|e| format!("Failed to read {filename}: {e}"))?;
+        let count = resolution[0] * resolution[1] * resolution[2];
+        if bytes.len() != count * 4 {
+            return Err(format!(
+                "Expected {} bytes of voxel data in {filename}, found {}",
+                count * 4,
+                bytes.len()
+            ));
+        }
+        let data = bytes
+            .chunks_exact(4)
+            .map(|chunk| f32::from_le_bytes(chunk.try_into().unwrap()))
+            .collect();
+        Ok(Self::new(resolution, bounds, data))
+    }
+
+    pub fn voxel(&self, x: usize, y: usize, z: usize) -> f64 {
+        self.data[x + self.resolution[0] * (y + self.resolution[1] * z)] as f64
+    }
+
+    /// The largest value in the grid
+    pub fn max_value(&self) -> f64 {
+        self.data.iter().fold(0.0f32, |a, &b| a.max(b)) as f64
+    }
+
+    /// Trilinearly interpolates the grid at a world space point, zero outside the bounds
+    pub fn sample(&self, p: Point3) -> f64 {
+        let local = p - self.bounds.min;
+        let size = self.bounds.size();
+        let mut index = [0usize; 3];
+        let mut frac = [0.0; 3];
+        for axis in 0..3 {
+            let u = local.0[axis] / size.0[axis];
+            if !(0.0..=1.0).contains(&u) {
+                return 0.0;
+            }
+            // Voxel values sit at the voxel centres
+            let res = self.resolution[axis];
+            let x = (u * res as f64 - 0.5).clamp(0.0, (res - 1) as f64);
+            let i = (x.floor() as usize).min(res.saturating_sub(2));
+            index[axis] = i;
+            frac[axis] = if res > 1 { x - i as f64 } else { 0.0 };
+        }
+
+        let next = |axis: usize| (index[axis] + 1).min(self.resolution[axis] - 1);
+        let (x0, y0, z0) = (index[0], index[1], index[2]);
+        let (x1, y1, z1) = (next(0), next(1), next(2));
+        let lerp = |a: f64, b: f64, t: f64| a + (b - a) * t;
+
+        let c00 = lerp(self.voxel(x0, y0, z0), self.voxel(x1, y0, z0), frac[0]);
+        let c10 = lerp(self.voxel(x0, y1, z0), self.voxel(x1, y1, z0), frac[0]);
+        let c01 = lerp(self.voxel(x0, y0, z1), self.voxel(x1, y0, z1), frac[0]);
+        let c11 = lerp(self.voxel(x0, y1, z1), self.voxel(x1, y1, z1), frac[0]);
+        let c0 = lerp(c00, c10, frac[1]);
+        let c1 = lerp(c01, c11, frac[1]);
+        lerp(c0, c1, frac[2])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hittable::VoxelGrid;
+    use crate::renderer::Vec3;
+
+    #[test]
+    fn test_vol_parse() {
+        let mut bytes = b"VOL".to_vec();
+        bytes.push(3);
+        for value in [1i32, 2, 2, 2, 1] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        for value in [0f32, 0., 0., 1., 1., 1.] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        for value in [0f32, 1., 2., 3., 4., 5., 6., 7.] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+
+        let grid = VoxelGrid::from_vol_bytes(&bytes).unwrap();
+        assert_eq!(grid.resolution, [2, 2, 2]);
+        assert_eq!(grid.voxel(1, 1, 1), 7.);
+        assert_eq!(grid.max_value(), 7.);
+        // The centre of the grid is the average of all the voxels
+        assert!((grid.sample(Vec3::new(0.5, 0.5, 0.5)) - 3.5).abs() < 1e-9);
+        assert_eq!(grid.sample(Vec3::new(2., 0.5, 0.5)), 0.);
+    }
+}
@@ -0,0 +1,56 @@
+use crate::hittable::{HitRecord, Hittable};
+use crate::integrator::{Integrator, IntegratorKind, Splat};
+use crate::renderer::{Ray, Scene, Vec3};
+
+/// Shades the first hit by how much of the hemisphere above it is open
+pub struct AmbientOcclusionIntegrator {
+    /// Occluders further away than this don't count
+    pub distance: f64,
+    /// Rays cast per camera sample
+    pub samples: u32,
+}
+
+impl Default for AmbientOcclusionIntegrator {
+    fn default() -> Self {
+        Self {
+            distance: 1.0,
+            samples: 1,
+        }
+    }
+}
+
+impl Integrator for AmbientOcclusionIntegrator {
+    fn li(&self, ray: &Ray, scene: &Scene, _splats: &mut Vec<Splat>) -> Vec3 {
+        let mut rec = HitRecord {
+            ..Default::default()
+        };
+
+        if !scene.world.hit(ray, 0.0, f64::INFINITY, &mut rec) {
+            return Vec3::new(1.0, 1.0, 1.0);
+        }
+
+        // Cosine weighted directions, so the cosine and pdf cancel
+        let mut unoccluded = 0;
+        for _ in 0..self.samples {
+            let direction = rec.normal + Vec3::random_unit_vector();
+            if direction.length_squared() < 1e-12 {
+                continue;
+            }
+            let occlusion_ray = Ray::new(rec.p, direction.unit_vector());
+            let mut occluder = HitRecord::default();
+            if !scene
+                .world
+                .hit(&occlusion_ray, 0.001, self.distance, &mut occluder)
+            {
+                unoccluded += 1;
+            }
+        }
+
+        let visibility = unoccluded as f64 / self.samples as f64;
+        Vec3::new(visibility, visibility, visibility)
+    }
+
+    fn kind(&self) -> IntegratorKind {
+        IntegratorKind::AmbientOcclusion
+    }
+}
@@ -0,0 +1,622 @@
+use std::sync::Arc;
+
+use crate::hittable::HitRecord;
+use crate::integrator::{Integrator, IntegratorKind, Splat};
+use crate::light::Light;
+use crate::material::Material;
+use crate::renderer::{Ray, Scene, Vec3};
+
+use Vec3 as Point3;
+
+/// Bidirectional path tracing, traces a path from the camera and one from a light and joins
+/// every pair of their vertices. Each way of building a path is weighted against the others
+/// with the balance heuristic, so caustics and small lights both come out cleanly
+pub struct BdptIntegrator {
+    /// The maximum number of bounces in a full path
+    pub max_depth: usize,
+}
+
+impl Default for BdptIntegrator {
+    fn default() -> Self {
+        Self { max_depth: 8 }
+    }
+}
+
+#[derive(Clone)]
+enum VertexKind {
+    Camera,
+    Light(Arc<dyn Light>),
+    Surface(HitRecord),
+}
+
+#[derive(Clone)]
+struct Vertex {
+    kind: VertexKind,
+    p: Point3,
+    /// Surface normal, or the view direction for the camera
+    n: Vec3,
+    /// Unit direction back towards the previous vertex of the path
+    wo: Vec3,
+    /// Throughput of the path up to and including this vertex
+    beta: Vec3,
+    /// Area densities of reaching this vertex from the previous one, and from the next one
+    pdf_fwd: f64,
+    pdf_rev: f64,
+    /// The path scattered off a delta lobe here
+    delta: bool,
+}
+
+impl Vertex {
+    fn camera(scene: &Scene, beta: Vec3) -> Self {
+        Self {
+            kind: VertexKind::Camera,
+            p: scene.camera.origin,
+            n: scene.camera.direction(),
+            wo: Vec3::new(0.0, 0.0, 0.0),
+            beta,
+            pdf_fwd: 0.0,
+            pdf_rev: 0.0,
+            delta: false,
+        }
+    }
+
+    fn light(light: Arc<dyn Light>, p: Point3, n: Vec3, beta: Vec3, pdf_fwd: f64) -> Self {
+        Self {
+            kind: VertexKind::Light(light),
+            p,
+            n,
+            wo: Vec3::new(0.0, 0.0, 0.0),
+            beta,
+            pdf_fwd,
+            pdf_rev: 0.0,
+            delta: false,
+        }
+    }
+
+    fn surface(rec: HitRecord, wo: Vec3, beta: Vec3) -> Self {
+        Self {
+            p: rec.p,
+            n: rec.normal,
+            kind: VertexKind::Surface(rec),
+            wo,
+            beta,
+            pdf_fwd: 0.0,
+            pdf_rev: 0.0,
+            delta: false,
+        }
+    }
+
+    fn material(&self) -> Option<(&Arc<dyn Material>, &HitRecord)> {
+        match &self.kind {
+            VertexKind::Surface(rec) => rec.material.as_ref().map(|material| (material, rec)),
+            _ => None,
+        }
+    }
+
+    /// Densities at points on surfaces depend on the angle they're reached at, unlike the
+    /// pinhole, point lights and scattering inside media
+    fn on_surface(&self) -> bool {
+        match &self.kind {
+            VertexKind::Camera => false,
+            VertexKind::Light(light) => !light.is_delta(),
+            VertexKind::Surface(rec) => !rec
+                .material
+                .as_ref()
+                .is_some_and(|material| material.is_medium()),
+        }
+    }
+
+    fn is_connectible(&self) -> bool {
+        match &self.kind {
+            VertexKind::Camera | VertexKind::Light(_) => true,
+            VertexKind::Surface(rec) => rec.material.is_some(),
+        }
+    }
+
+    fn is_delta_light(&self) -> bool {
+        matches!(&self.kind, VertexKind::Light(light) if light.is_delta())
+    }
+
+    fn is_infinite_light(&self) -> bool {
+        matches!(&self.kind, VertexKind::Light(light) if light.is_infinite())
+    }
+
+    /// The light this vertex lies on, either as the start of a light path or a camera path
+    /// hitting an emitter
+    fn find_light<'a>(&'a self, scene: &'a Scene) -> Option<&'a Arc<dyn Light>> {
+        match &self.kind {
+            VertexKind::Light(light) => Some(light),
+            VertexKind::Surface(rec) => scene.light_for_hit(rec),
+            VertexKind::Camera => None,
+        }
+    }
+
+    /// Scattering from the previous vertex towards `next`, including the cosine term
+    fn f(&self, next: &Vertex) -> Vec3 {
+        match self.material() {
+            Some((material, rec)) => material.eval(self.wo, (next.p - self.p).unit_vector(), rec),
+            None => Vec3::new(0.0, 0.0, 0.0),
+        }
+    }
+
+    /// Turns a solid angle density of heading towards `next` into an area density there
+    fn convert_density(&self, pdf: f64, next: &Vertex) -> f64 {
+        // Distant lights are reached by direction alone
+        if next.is_infinite_light() {
+            return pdf;
+        }
+        let w = next.p - self.p;
+        let dist2 = w.length_squared();
+        if dist2 == 0.0 {
+            return 0.0;
+        }
+        let mut pdf = pdf / dist2;
+        if next.on_surface() {
+            pdf *= next.n.dot(&w).abs() / dist2.sqrt();
+        }
+        pdf
+    }
+
+    /// Area density at `next` of a path arriving from `prev` and scattering here
+    fn pdf(&self, scene: &Scene, prev: Option<&Vertex>, next: &Vertex) -> f64 {
+        let wn = (next.p - self.p).unit_vector();
+        let pdf = match &self.kind {
+            VertexKind::Light(_) => return self.pdf_light(scene, next),
+            VertexKind::Camera => scene.camera.pdf_dir(wn),
+            VertexKind::Surface(rec) => match (prev, &rec.material) {
+                (Some(prev), Some(material)) => {
+                    material.pdf((prev.p - self.p).unit_vector(), wn, rec)
+                }
+                _ => 0.0,
+            },
+        };
+        self.convert_density(pdf, next)
+    }
+
+    /// Area density at `next` of a light path leaving from this point
+    fn pdf_light(&self, scene: &Scene, next: &Vertex) -> f64 {
+        match self.find_light(scene) {
+            // Light paths from distant lights start anywhere on a disk facing the scene
+            Some(light) if light.is_infinite() => {
+                let w = (next.p - self.p).unit_vector();
+                let (pdf_pos, _) = light.pdf_le(scene, self.n, w);
+                if next.on_surface() {
+                    pdf_pos * next.n.dot(&w).abs()
+                } else {
+                    pdf_pos
+                }
+            }
+            Some(light) => {
+                let (_, pdf_dir) = light.pdf_le(scene, self.n, next.p - self.p);
+                self.convert_density(pdf_dir, next)
+            }
+            None => 0.0,
+        }
+    }
+
+    /// Area density of a light path starting at this point, heading towards `next`
+    fn pdf_light_origin(&self, scene: &Scene, next: &Vertex) -> f64 {
+        match self.find_light(scene) {
+            // Only the direction matters for distant lights
+            Some(light) if light.is_infinite() => {
+                let (_, pdf_dir) = light.pdf_le(scene, self.n, next.p - self.p);
+                pdf_dir * scene.light_pdf()
+            }
+            Some(light) => {
+                let (pdf_pos, _) = light.pdf_le(scene, self.n, next.p - self.p);
+                pdf_pos * scene.light_pdf()
+            }
+            None => 0.0,
+        }
+    }
+}
+
+impl BdptIntegrator {
+    /// Extends `path` by scattering around the scene. Returns light only the camera can find
+    /// with weight one: the sky, and objects without a material which are shaded by normal
+    fn random_walk(
+        &self,
+        scene: &Scene,
+        mut ray: Ray,
+        mut beta: Vec3,
+        pdf: f64,
+        max_vertices: usize,
+        path: &mut Vec<Vertex>,
+    ) -> Vec3 {
+        let mut pdf_fwd = pdf;
+
+        while path.len() < max_vertices {
+            let mut rec = HitRecord {
+                ..Default::default()
+            };
+            if !scene.hit(&ray, 0.001, f64::INFINITY, &mut rec) {
+                return beta * scene.background(&ray);
+            }
+
+            let wo = -ray.dir.unit_vector();
+            let mut vertex = Vertex::surface(rec.clone(), wo, beta);
+            vertex.pdf_fwd = path.last().unwrap().convert_density(pdf_fwd, &vertex);
+            path.push(vertex);
+
+            let material = match &rec.material {
+                Some(material) => material,
+                None => return beta * 0.5 * (rec.normal + Vec3::new(1.0, 1.0, 1.0)),
+            };
+            if path.len() == max_vertices {
+                break;
+            }
+            let scatter = match material.scatter(&ray, &rec) {
+                Some(scatter) => scatter,
+                None => break,
+            };
+            beta = beta * scatter.attenuation;
+            if beta.is_black() {
+                break;
+            }
+
+            // Delta lobes can't be reached by any other strategy
+            let last = path.len() - 1;
+            let pdf_rev = if scatter.lobe.is_delta() {
+                path[last].delta = true;
+                pdf_fwd = 0.0;
+                0.0
+            } else {
+                pdf_fwd = scatter.pdf;
+                material.pdf(scatter.ray.dir.unit_vector(), wo, &rec)
+            };
+            path[last - 1].pdf_rev = path[last].convert_density(pdf_rev, &path[last - 1]);
+
+            ray = scatter.ray;
+        }
+        Vec3::new(0.0, 0.0, 0.0)
+    }
+
+    fn light_subpath(&self, scene: &Scene) -> Vec<Vertex> {
+        let mut path = Vec::new();
+        let (light, light_pdf) = match scene.pick_light() {
+            Some(picked) => picked,
+            None => return path,
+        };
+        let emission = match light.sample_le(scene) {
+            Some(emission) => emission,
+            None => return path,
+        };
+        if emission.pdf_pos == 0.0 || emission.pdf_dir == 0.0 || emission.radiance.is_black() {
+            return path;
+        }
+
+        path.push(Vertex::light(
+            light.clone(),
+            emission.ray.orig,
+            emission.normal,
+            emission.radiance,
+            emission.pdf_pos * light_pdf,
+        ));
+        let cos_theta = emission.normal.dot(&emission.ray.dir.unit_vector()).abs();
+        let beta =
+            emission.radiance * (cos_theta / (light_pdf * emission.pdf_pos * emission.pdf_dir));
+        self.random_walk(
+            scene,
+            emission.ray,
+            beta,
+            emission.pdf_dir,
+            self.max_depth + 1,
+            &mut path,
+        );
+        path
+    }
+
+    /// The unweighted contribution of joining the first `s` light vertices with the first `t`
+    /// camera vertices. Light tracing strategies also return where on the image they landed
+    fn connect(
+        &self,
+        scene: &Scene,
+        light_path: &[Vertex],
+        camera_path: &[Vertex],
+        s: usize,
+        t: usize,
+    ) -> Option<(Vec3, Option<(f64, f64)>)> {
+        let mut l = Vec3::new(0.0, 0.0, 0.0);
+        let mut sampled = None;
+        let mut raster = None;
+
+        if s == 0 {
+            // The camera path found an emitter by itself
+            let pt = &camera_path[t - 1];
+            if let Some((material, rec)) = pt.material() {
+                l = pt.beta * material.emitted(rec);
+            }
+        } else if t == 1 {
+            // Project the light path onto the image
+            let qs = &light_path[s - 1];
+            if qs.is_connectible() {
+                if let Some(sample) = scene.camera.sample_wi(qs.p) {
+                    let weight = sample.importance / sample.pdf;
+                    let camera = Vertex::camera(scene, Vec3::new(weight, weight, weight));
+                    l = qs.beta * qs.f(&camera) * camera.beta;
+                    if !l.is_black() {
+                        l = l * scene.visibility(qs.p, camera.p);
+                    }
+                    raster = Some((sample.u, sample.v));
+                    sampled = Some(camera);
+                }
+            }
+        } else if s == 1 {
+            // Direct lighting with a fresh point on a light
+            let pt = &camera_path[t - 1];
+            if pt.is_connectible() {
+                if let Some((light, light_pdf)) = scene.pick_light() {
+                    if let Some(sample) = light.sample_li(pt.p) {
+                        let mut vertex = Vertex::light(
+                            light.clone(),
+                            sample.point,
+                            sample.normal,
+                            sample.radiance / (sample.pdf * light_pdf),
+                            0.0,
+                        );
+                        vertex.pdf_fwd = vertex.pdf_light_origin(scene, pt);
+                        l = pt.beta * pt.f(&vertex) * vertex.beta;
+                        if !l.is_black() {
+                            l = l * scene.visibility(pt.p, vertex.p);
+                        }
+                        sampled = Some(vertex);
+                    }
+                }
+            }
+        } else {
+            let qs = &light_path[s - 1];
+            let pt = &camera_path[t - 1];
+            if qs.is_connectible() && pt.is_connectible() {
+                l = qs.beta * qs.f(pt) * pt.f(qs) * pt.beta;
+                if !l.is_black() {
+                    // The cosines are already part of `f`
+                    let dist2 = (qs.p - pt.p).length_squared();
+                    l = l * (scene.visibility(qs.p, pt.p) / dist2);
+                }
+            }
+        }
+
+        if l.is_black() {
+            return None;
+        }
+        let weight = self.mis_weight(scene, light_path, camera_path, sampled, s, t);
+        Some((weight * l, raster))
+    }
+
+    /// Balance heuristic weight of the strategy, found by walking along the path and working
+    /// out the relative density each other strategy would have produced it with
+    fn mis_weight(
+        &self,
+        scene: &Scene,
+        light_path: &[Vertex],
+        camera_path: &[Vertex],
+        sampled: Option<Vertex>,
+        s: usize,
+        t: usize,
+    ) -> f64 {
+        if s + t == 2 {
+            return 1.0;
+        }
+        // Emitters that aren't lights can only ever be found from the camera
+        if s == 0 && camera_path[t - 1].find_light(scene).is_none() {
+            return 1.0;
+        }
+
+        // Work on copies holding the densities this strategy would have
+        let mut camera = camera_path[..t].to_vec();
+        let mut light = light_path[..s].to_vec();
+        if let Some(vertex) = sampled {
+            if s == 1 {
+                light[0] = vertex;
+            } else if t == 1 {
+                camera[0] = vertex;
+            }
+        }
+
+        // The connection itself is never a delta scattering
+        camera[t - 1].delta = false;
+        if s > 0 {
+            light[s - 1].delta = false;
+        }
+
+        let pt_rev = if s > 0 {
+            let qs_minus = if s > 1 { Some(&light[s - 2]) } else { None };
+            light[s - 1].pdf(scene, qs_minus, &camera[t - 1])
+        } else {
+            camera[t - 1].pdf_light_origin(scene, &camera[t - 2])
+        };
+        let pt_minus_rev = if t > 1 {
+            Some(if s > 0 {
+                camera[t - 1].pdf(scene, Some(&light[s - 1]), &camera[t - 2])
+            } else {
+                camera[t - 1].pdf_light(scene, &camera[t - 2])
+            })
+        } else {
+            None
+        };
+        let qs_rev = if s > 0 {
+            let pt_minus = if t > 1 { Some(&camera[t - 2]) } else { None };
+            Some(camera[t - 1].pdf(scene, pt_minus, &light[s - 1]))
+        } else {
+            None
+        };
+        let qs_minus_rev = if s > 1 {
+            Some(light[s - 1].pdf(scene, Some(&camera[t - 1]), &light[s - 2]))
+        } else {
+            None
+        };
+
+        camera[t - 1].pdf_rev = pt_rev;
+        if let Some(pdf) = pt_minus_rev {
+            camera[t - 2].pdf_rev = pdf;
+        }
+        if let Some(pdf) = qs_rev {
+            light[s - 1].pdf_rev = pdf;
+        }
+        if let Some(pdf) = qs_minus_rev {
+            light[s - 2].pdf_rev = pdf;
+        }
+
+        // Delta densities are stored as zero, treat them as one so the ratios stay finite
+        let remap = |pdf: f64| if pdf == 0.0 { 1.0 } else { pdf };
+
+        let mut sum = 0.0;
+        let mut ratio = 1.0;
+        for i in (1..t).rev() {
+            ratio *= remap(camera[i].pdf_rev) / remap(camera[i].pdf_fwd);
+            if !camera[i].delta && !camera[i - 1].delta {
+                sum += ratio;
+            }
+        }
+
+        ratio = 1.0;
+        for i in (0..s).rev() {
+            ratio *= remap(light[i].pdf_rev) / remap(light[i].pdf_fwd);
+            let delta_before = if i > 0 {
+                light[i - 1].delta
+            } else {
+                light[0].is_delta_light()
+            };
+            if !light[i].delta && !delta_before {
+                sum += ratio;
+            }
+        }
+
+        1.0 / (1.0 + sum)
+    }
+}
+
+impl Integrator for BdptIntegrator {
+    fn li(&self, ray: &Ray, scene: &Scene, splats: &mut Vec<Splat>) -> Vec3 {
+        let mut camera_path = vec![Vertex::camera(scene, Vec3::new(1.0, 1.0, 1.0))];
+        let mut l = self.random_walk(
+            scene,
+            *ray,
+            Vec3::new(1.0, 1.0, 1.0),
+            scene.camera.pdf_dir(ray.dir),
+            self.max_depth + 2,
+            &mut camera_path,
+        );
+        let light_path = self.light_subpath(scene);
+
+        for t in 1..=camera_path.len() {
+            for s in 0..=light_path.len() {
+                // Looking straight at the light is covered by the camera path hitting it
+                if s + t < 2 || (s == 1 && t == 1) || s + t - 2 > self.max_depth {
+                    continue;
+                }
+                match self.connect(scene, &light_path, &camera_path, s, t) {
+                    Some((value, Some((u, v)))) => splats.push(Splat { u, v, value }),
+                    Some((value, None)) => l += value,
+                    None => {}
+                }
+            }
+        }
+        l
+    }
+
+    fn set_max_depth(&mut self, depth: u32) {
+        self.max_depth = depth as usize;
+    }
+
+    fn kind(&self) -> IntegratorKind {
+        IntegratorKind::Bidirectional
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::hittable::{HittableList, Quad};
+    use crate::integrator::{BdptIntegrator, Integrator, PathIntegrator};
+    use crate::material::{DiffuseLight, Lambertian};
+    use crate::renderer::{render_image, Camera, Scene, Vec3};
+
+    #[test]
+    fn test_bdpt_splats() {
+        let mut world = HittableList::new();
+        world.add_hittable(Box::new(Quad::with_material(
+            Vec3::new(-2.0, -0.5, -3.0),
+            Vec3::new(0.0, 0.0, 3.0),
+            Vec3::new(4.0, 0.0, 0.0),
+            Arc::new(Lambertian::new(Vec3::new(0.5, 0.5, 0.5))),
+        )));
+        let mut scene = Scene::new(world);
+        let emission = Arc::new(DiffuseLight::new(Vec3::new(5.0, 5.0, 5.0)));
+        scene.add_area_light(
+            Arc::new(Quad::with_material(
+                Vec3::new(-0.5, 1.0, -1.5),
+                Vec3::new(1.0, 0.0, 0.0),
+                Vec3::new(0.0, 0.0, 1.0),
+                emission.clone(),
+            )),
+            emission,
+        );
+
+        let integrator = BdptIntegrator::default();
+        let ray = scene.camera.get_ray(0.5, 0.3);
+        let mut splats = Vec::new();
+        let mut total = Vec3::new(0.0, 0.0, 0.0);
+        for _ in 0..100 {
+            total += integrator.li(&ray, &scene, &mut splats);
+        }
+
+        // The floor is lit, and the light tracing lands on the image
+        assert!(total.x() > 0.0);
+        assert!(!splats.is_empty());
+        for splat in splats {
+            assert!((0.0..=1.0).contains(&splat.u) && (0.0..=1.0).contains(&splat.v));
+            assert!(splat.value.x() >= 0.0 && splat.value.x().is_finite());
+        }
+    }
+
+    #[test]
+    fn test_bdpt_matches_path() {
+        // A small bright lamp over a floor, seen from above so the floor fills the image
+        let mut world = HittableList::new();
+        world.add_hittable(Box::new(Quad::with_material(
+            Vec3::new(-50.0, 0.0, -50.0),
+            Vec3::new(0.0, 0.0, 100.0),
+            Vec3::new(100.0, 0.0, 0.0),
+            Arc::new(Lambertian::new(Vec3::new(0.5, 0.5, 0.5))),
+        )));
+        let mut scene = Scene::new(world);
+        let emission = Arc::new(DiffuseLight::new(Vec3::new(400.0, 400.0, 400.0)));
+        scene.add_area_light(
+            Arc::new(Quad::with_material(
+                Vec3::new(-0.1, 0.6, -1.1),
+                Vec3::new(0.2, 0.0, 0.0),
+                Vec3::new(0.0, 0.0, 0.2),
+                emission.clone(),
+            )),
+            emission,
+        );
+        scene.camera = Camera::look_at(
+            Vec3::new(0.0, 1.2, 1.0),
+            Vec3::new(0.0, 0.0, -1.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            40.0,
+            1.5,
+        );
+
+        let brightness = |integrator: &mut dyn Integrator, samples: u32| {
+            let image = render_image(&scene, integrator, (24, 16), samples, 1, 4, |_, _| {});
+            let mut total = 0.0;
+            for y in 0..image.height {
+                for x in 0..image.width {
+                    total += Vec3(image.get_colour(x, y)).luminance();
+                }
+            }
+            total / (image.width * image.height) as f64
+        };
+        let bdpt = brightness(&mut BdptIntegrator::default(), 8);
+        let path = brightness(&mut PathIntegrator::default(), 64);
+        // Both are unbiased, so apart from noise they agree. Splats landing a row out or
+        // scaled for the wrong pixel size were off by 5%
+        assert!(
+            (bdpt - path).abs() < 0.03 * path,
+            "bdpt {bdpt}, path {path}"
+        );
+    }
+}
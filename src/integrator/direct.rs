@@ -0,0 +1,57 @@
+use crate::hittable::HitRecord;
+use crate::material::Material;
+use crate::renderer::{Scene, Vec3};
+
+use Vec3 as Point3;
+
+/// Weights two sampling strategies by the square of their densities
+pub fn power_heuristic(pdf: f64, other_pdf: f64) -> f64 {
+    let (a, b) = (pdf * pdf, other_pdf * other_pdf);
+    if a + b == 0.0 {
+        0.0
+    } else {
+        a / (a + b)
+    }
+}
+
+/// Light arriving at the hit point straight from one randomly picked light, scattered towards
+/// `wo`. Lights which could also be found by scattering are weighted against it
+pub fn sample_direct(scene: &Scene, rec: &HitRecord, material: &dyn Material, wo: Vec3) -> Vec3 {
+    let none = Vec3::new(0.0, 0.0, 0.0);
+    let Some((light, light_pdf)) = scene.pick_light() else {
+        return none;
+    };
+    let Some(sample) = light.sample_li(rec.p) else {
+        return none;
+    };
+    if sample.pdf <= 0.0 || sample.radiance.is_black() {
+        return none;
+    }
+
+    let f = material.eval(wo, sample.wi, rec);
+    if f.is_black() {
+        return none;
+    }
+    let visibility = scene.visibility(rec.p, sample.point);
+    if visibility <= 0.0 {
+        return none;
+    }
+
+    let pdf = light_pdf * sample.pdf;
+    let weight = if light.is_delta() {
+        1.0
+    } else {
+        power_heuristic(pdf, material.pdf(wo, sample.wi, rec))
+    };
+    (weight * visibility / pdf) * f * sample.radiance
+}
+
+/// The weight of light found by scattering from `from` with solid angle density `pdf` and
+/// hitting `rec`, given `sample_direct` might have picked the same light
+pub fn emission_weight(scene: &Scene, from: Point3, pdf: f64, rec: &HitRecord) -> f64 {
+    match scene.light_for_hit(rec) {
+        Some(light) => power_heuristic(pdf, scene.light_pdf() * light.pdf_li(from, rec)),
+        // Glowing things that aren't lights can only be found by scattering
+        None => 1.0,
+    }
+}
@@ -0,0 +1,19 @@
+mod ambient_occlusion;
+mod bdpt;
+mod direct;
+mod normals;
+mod path;
+mod photon_map;
+mod photon_mapping;
+mod traits;
+mod whitted;
+
+pub use ambient_occlusion::AmbientOcclusionIntegrator;
+pub use bdpt::BdptIntegrator;
+pub use direct::{emission_weight, sample_direct};
+pub use normals::NormalsIntegrator;
+pub use path::PathIntegrator;
+pub use photon_map::{Photon, PhotonMap};
+pub use photon_mapping::PhotonMapIntegrator;
+pub use traits::{Integrator, IntegratorKind, Splat};
+pub use whitted::WhittedIntegrator;
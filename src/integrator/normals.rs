@@ -0,0 +1,24 @@
+use crate::hittable::{HitRecord, Hittable};
+use crate::integrator::{Integrator, IntegratorKind, Splat};
+use crate::renderer::{Ray, Scene, Vec3};
+
+/// Debug view, colours the first hit by its normal
+pub struct NormalsIntegrator;
+
+impl Integrator for NormalsIntegrator {
+    fn li(&self, ray: &Ray, scene: &Scene, _splats: &mut Vec<Splat>) -> Vec3 {
+        let mut rec = HitRecord {
+            ..Default::default()
+        };
+
+        if scene.world.hit(ray, 0.0, f64::INFINITY, &mut rec) {
+            return 0.5 * (rec.normal + Vec3::new(1.0, 1.0, 1.0));
+        }
+
+        scene.background(ray)
+    }
+
+    fn kind(&self) -> IntegratorKind {
+        IntegratorKind::Normals
+    }
+}
@@ -0,0 +1,211 @@
+use crate::hittable::HitRecord;
+use crate::integrator::{emission_weight, sample_direct, Integrator, IntegratorKind, Splat};
+use crate::material::Lobe;
+use crate::renderer::{
+    random_double, rgb_to_spectrum, sample_wavelengths, spectrum_to_rgb, Ray, Scene, Vec3,
+};
+
+/// Unidirectional path tracing, follows a ray as it scatters around the scene
+pub struct PathIntegrator {
+    /// The maximum number of times a ray can scatter before we give up on it
+    pub max_depth: u32,
+    /// Limits on each kind of bounce, scattering in a medium counts as diffuse
+    pub max_diffuse_depth: u32,
+    pub max_specular_depth: u32,
+    pub max_transmission_depth: u32,
+    /// Bounces before Russian roulette can start ending paths which carry little light
+    pub rr_min_depth: u32,
+    /// Trace a hero wavelength and two companions instead of RGB, so dispersive glass can
+    /// split light up
+    pub spectral: bool,
+}
+
+impl Default for PathIntegrator {
+    fn default() -> Self {
+        Self {
+            max_depth: 50,
+            max_diffuse_depth: 16,
+            max_specular_depth: 32,
+            max_transmission_depth: 32,
+            rr_min_depth: 3,
+            spectral: false,
+        }
+    }
+}
+
+impl PathIntegrator {
+    pub fn spectral() -> Self {
+        Self {
+            spectral: true,
+            ..Default::default()
+        }
+    }
+}
+
+impl Integrator for PathIntegrator {
+    fn li(&self, ray: &Ray, scene: &Scene, _splats: &mut Vec<Splat>) -> Vec3 {
+        // When spectral the channels hold values at each wavelength rather than RGB, colours
+        // from the scene are upsampled to spectra as they're used
+        let wavelengths = if self.spectral {
+            Some(sample_wavelengths())
+        } else {
+            None
+        };
+        let convert = |rgb: Vec3| match wavelengths {
+            Some(wavelengths) => rgb_to_spectrum(rgb, wavelengths),
+            None => rgb,
+        };
+
+        let mut l = Vec3::new(0.0, 0.0, 0.0);
+        let mut beta = Vec3::new(1.0, 1.0, 1.0);
+        let mut ray = *ray;
+        ray.wavelength = wavelengths.map(|wavelengths| wavelengths.x());
+        let mut dispersed = false;
+        let (mut diffuse, mut specular, mut transmission) = (0, 0, 0);
+        // Where the last non-delta bounce was and its density, lights found by scattering from
+        // there could also have been sampled directly
+        let mut previous = None;
+
+        for depth in 0..self.max_depth {
+            let mut rec = HitRecord {
+                ..Default::default()
+            };
+
+            if !scene.hit(&ray, 0.001, f64::INFINITY, &mut rec) {
+                l += beta * convert(scene.background(&ray));
+                break;
+            }
+
+            let material = match &rec.material {
+                Some(material) => material,
+                // Objects without a material are shaded by their normal
+                None => {
+                    l += beta * convert(0.5 * (rec.normal + Vec3::new(1.0, 1.0, 1.0)));
+                    break;
+                }
+            };
+            let emitted = material.emitted(&rec);
+            if !emitted.is_black() {
+                let weight = match previous {
+                    Some((from, pdf)) => emission_weight(scene, from, pdf, &rec),
+                    None => 1.0,
+                };
+                l += weight * beta * convert(emitted);
+            }
+
+            if !material.is_delta() {
+                let direct = sample_direct(scene, &rec, material.as_ref(), -ray.dir.unit_vector());
+                l += beta * convert(direct);
+            }
+
+            let scatter = match material.scatter(&ray, &rec) {
+                Some(scatter) => scatter,
+                None => break,
+            };
+
+            let (bounces, max_bounces) = match scatter.lobe {
+                lobe if lobe.is_transmission() => (&mut transmission, self.max_transmission_depth),
+                Lobe::Specular | Lobe::Glossy => (&mut specular, self.max_specular_depth),
+                _ => (&mut diffuse, self.max_diffuse_depth),
+            };
+            if *bounces >= max_bounces {
+                break;
+            }
+            *bounces += 1;
+
+            // Each wavelength would have bent differently, so only the hero carries on and
+            // takes the weight of the others
+            if wavelengths.is_some() && !dispersed && material.is_dispersive() {
+                beta = beta * Vec3::new(3.0, 0.0, 0.0);
+                dispersed = true;
+            }
+
+            previous = if scatter.lobe.is_delta() {
+                None
+            } else {
+                Some((rec.p, scatter.pdf))
+            };
+            beta = beta * convert(scatter.attenuation);
+            ray = Ray {
+                wavelength: ray.wavelength,
+                ..scatter.ray
+            };
+
+            // Paths carrying little light are ended at random, the survivors are boosted to make
+            // up for the ones that were lost
+            if depth + 1 >= self.rr_min_depth {
+                let q = (1.0 - beta.max_component()).max(0.05);
+                if random_double() < q {
+                    break;
+                }
+                beta = beta / (1.0 - q);
+            }
+        }
+
+        match wavelengths {
+            Some(wavelengths) => spectrum_to_rgb(l, wavelengths),
+            None => l,
+        }
+    }
+
+    fn set_max_depth(&mut self, depth: u32) {
+        self.max_depth = depth;
+    }
+
+    fn kind(&self) -> IntegratorKind {
+        if self.spectral {
+            IntegratorKind::SpectralPath
+        } else {
+            IntegratorKind::Path
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::hittable::{HittableList, Sphere};
+    use crate::integrator::{Integrator, PathIntegrator};
+    use crate::material::Lambertian;
+    use crate::renderer::{Ray, Scene, Vec3};
+
+    #[test]
+    fn test_path_depth_and_roulette() {
+        let mut world = HittableList::new();
+        world.add_hittable(Box::new(Sphere::with_material(
+            Vec3::new(0.0, -100.5, -1.0),
+            100.0,
+            Arc::new(Lambertian::new(Vec3::new(0.5, 0.5, 0.5))),
+        )));
+        let scene = Scene::new(world);
+        let ray = Ray::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, -1.0, -1.0));
+        let mut splats = Vec::new();
+
+        // Not allowed to bounce off the ground, so no light gets back
+        let no_bounces = PathIntegrator {
+            max_diffuse_depth: 0,
+            ..Default::default()
+        };
+        assert!(no_bounces.li(&ray, &scene, &mut splats).is_black());
+
+        // Russian roulette from the first bounce shouldn't change the average
+        let runs = 50_000;
+        let average = |integrator: &PathIntegrator| {
+            let mut splats = Vec::new();
+            (0..runs)
+                .map(|_| integrator.li(&ray, &scene, &mut splats).luminance())
+                .sum::<f64>()
+                / runs as f64
+        };
+        let without = average(&PathIntegrator {
+            rr_min_depth: 1000,
+            ..Default::default()
+        });
+        let with = average(&PathIntegrator {
+            rr_min_depth: 0,
+            ..Default::default()
+        });
+        assert!((with - without).abs() < 0.05 * without);
+    }
+}
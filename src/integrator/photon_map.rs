@@ -0,0 +1,133 @@
+use crate::renderer::Vec3;
+
+use Vec3 as Point3;
+
+/// A packet of light flux, stored where it landed
+#[derive(Copy, Clone, Debug)]
+pub struct Photon {
+    pub p: Point3,
+    /// Unit direction back towards where the photon came from
+    pub wi: Vec3,
+    pub power: Vec3,
+}
+
+/// Photons in a balanced kd-tree. The tree lives in the array itself, each node sits in the
+/// middle of its range with its children in the halves either side
+pub struct PhotonMap {
+    photons: Vec<Photon>,
+    axes: Vec<usize>,
+}
+
+impl PhotonMap {
+    pub fn new(mut photons: Vec<Photon>) -> Self {
+        let mut axes = vec![0; photons.len()];
+        let len = photons.len();
+        Self::build(&mut photons, &mut axes, 0, len);
+        Self { photons, axes }
+    }
+
+    fn build(photons: &mut [Photon], axes: &mut [usize], start: usize, end: usize) {
+        if end - start <= 1 {
+            return;
+        }
+
+        // Split along the longest side of the photons' bounds
+        let mut min = photons[start].p;
+        let mut max = photons[start].p;
+        for photon in &photons[start..end] {
+            for axis in 0..3 {
+                min.0[axis] = min.0[axis].min(photon.p.0[axis]);
+                max.0[axis] = max.0[axis].max(photon.p.0[axis]);
+            }
+        }
+        let extent = max - min;
+        let axis = if extent.x() > extent.y() && extent.x() > extent.z() {
+            0
+        } else if extent.y() > extent.z() {
+            1
+        } else {
+            2
+        };
+
+        let mid = (start + end) / 2;
+        photons[start..end]
+            .select_nth_unstable_by(mid - start, |a, b| a.p.0[axis].total_cmp(&b.p.0[axis]));
+        axes[mid] = axis;
+
+        Self::build(photons, axes, start, mid);
+        Self::build(photons, axes, mid + 1, end);
+    }
+
+    pub fn len(&self) -> usize {
+        self.photons.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.photons.is_empty()
+    }
+
+    /// Calls `f` with every photon within `radius` of `p`
+    pub fn for_each_within<F: FnMut(&Photon)>(&self, p: Point3, radius: f64, mut f: F) {
+        self.search(p, radius * radius, 0, self.photons.len(), &mut f);
+    }
+
+    fn search<F: FnMut(&Photon)>(
+        &self,
+        p: Point3,
+        radius2: f64,
+        start: usize,
+        end: usize,
+        f: &mut F,
+    ) {
+        if start >= end {
+            return;
+        }
+        let mid = (start + end) / 2;
+        let photon = &self.photons[mid];
+        if (photon.p - p).length_squared() <= radius2 {
+            f(photon);
+        }
+
+        // Look on our side of the split first, the far side only if the sphere crosses it
+        let axis = self.axes[mid];
+        let offset = p.0[axis] - photon.p.0[axis];
+        let (near, far) = if offset < 0.0 {
+            ((start, mid), (mid + 1, end))
+        } else {
+            ((mid + 1, end), (start, mid))
+        };
+        self.search(p, radius2, near.0, near.1, f);
+        if offset * offset <= radius2 {
+            self.search(p, radius2, far.0, far.1, f);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::integrator::{Photon, PhotonMap};
+    use crate::renderer::{random_double, Vec3};
+
+    #[test]
+    fn test_photon_map_search() {
+        let photons: Vec<_> = (0..1000)
+            .map(|_| Photon {
+                p: Vec3::new(random_double(), random_double(), random_double()),
+                wi: Vec3::new(0.0, 1.0, 0.0),
+                power: Vec3::new(1.0, 1.0, 1.0),
+            })
+            .collect();
+        let centre = Vec3::new(0.5, 0.5, 0.5);
+        let radius = 0.2;
+        let expected = photons
+            .iter()
+            .filter(|photon| (photon.p - centre).length() <= radius)
+            .count();
+
+        let map = PhotonMap::new(photons);
+        let mut found = 0;
+        map.for_each_within(centre, radius, |_| found += 1);
+        assert_eq!(map.len(), 1000);
+        assert_eq!(found, expected);
+    }
+}
@@ -0,0 +1,347 @@
+use std::f64::consts::PI;
+
+use crate::hittable::HitRecord;
+use crate::integrator::{
+    emission_weight, sample_direct, Integrator, IntegratorKind, Photon, PhotonMap, Splat,
+};
+use crate::material::Material;
+use crate::renderer::{Ray, Scene, Vec3};
+
+/// How the camera path got to where it is, so light already carried by photons isn't
+/// counted again when the path happens to hit the light itself
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum Bounce {
+    Other,
+    /// Scattered off something the photon map is gathered at
+    Diffuse,
+    /// A diffuse bounce followed by nothing but mirrors and glass
+    Caustic,
+}
+
+/// Path tracing which takes caustics, light reaching a surface through mirrors and glass, from
+/// a photon map instead of waiting for paths to stumble onto the light through them.
+///
+/// The progressive variant traces a fresh map before every pass and shrinks the gather radius
+/// as it goes, following Knaus and Zwicker's probabilistic take on stochastic progressive
+/// photon mapping, so the blur of the density estimate converges away
+pub struct PhotonMapIntegrator {
+    /// Photons emitted each time the map is built
+    pub photon_count: usize,
+    /// The starting gather radius
+    pub radius: f64,
+    pub max_depth: u32,
+    pub progressive: bool,
+    /// How much of the radius is kept each pass, between zero and one
+    pub alpha: f64,
+    current_radius: f64,
+    pass: u32,
+    map: Option<PhotonMap>,
+}
+
+impl Default for PhotonMapIntegrator {
+    fn default() -> Self {
+        Self {
+            photon_count: 200_000,
+            radius: 0.02,
+            max_depth: 50,
+            progressive: false,
+            alpha: 2.0 / 3.0,
+            current_radius: 0.02,
+            pass: 0,
+            map: None,
+        }
+    }
+}
+
+impl PhotonMapIntegrator {
+    pub fn progressive() -> Self {
+        Self {
+            photon_count: 20_000,
+            radius: 0.05,
+            current_radius: 0.05,
+            progressive: true,
+            ..Default::default()
+        }
+    }
+
+    /// Follows photons from the lights through mirrors and glass, keeping them where they land
+    /// on anything else
+    fn trace_photons(&self, scene: &Scene) -> PhotonMap {
+        let mut photons = Vec::new();
+
+        for _ in 0..self.photon_count {
+            let (light, light_pdf) = match scene.pick_light() {
+                Some(picked) => picked,
+                None => break,
+            };
+            let emission = match light.sample_le(scene) {
+                Some(emission) if emission.pdf_pos > 0.0 && emission.pdf_dir > 0.0 => emission,
+                _ => continue,
+            };
+            let cos_theta = emission.normal.dot(&emission.ray.dir.unit_vector()).abs();
+            let mut power =
+                emission.radiance * (cos_theta / (light_pdf * emission.pdf_pos * emission.pdf_dir));
+            let mut ray = emission.ray;
+            let mut specular = false;
+
+            for _ in 0..self.max_depth {
+                let mut rec = HitRecord {
+                    ..Default::default()
+                };
+                if !scene.hit(&ray, 0.001, f64::INFINITY, &mut rec) {
+                    break;
+                }
+                let material = match &rec.material {
+                    Some(material) if !material.is_medium() => material,
+                    _ => break,
+                };
+
+                if !material.is_delta() {
+                    // Only light which came through a mirror or glass is a caustic
+                    if specular {
+                        photons.push(Photon {
+                            p: rec.p,
+                            wi: -ray.dir.unit_vector(),
+                            power,
+                        });
+                    }
+                    break;
+                }
+
+                match material.scatter(&ray, &rec) {
+                    Some(scatter) => {
+                        power = power * scatter.attenuation;
+                        ray = scatter.ray;
+                        specular = true;
+                    }
+                    None => break,
+                }
+            }
+        }
+
+        PhotonMap::new(photons)
+    }
+
+    /// Density estimate of the caustic light leaving the hit point towards `wo`
+    fn caustics(&self, rec: &HitRecord, material: &dyn Material, wo: Vec3) -> Vec3 {
+        let mut sum = Vec3::new(0.0, 0.0, 0.0);
+        let map = match &self.map {
+            Some(map) => map,
+            None => return sum,
+        };
+
+        map.for_each_within(rec.p, self.current_radius, |photon| {
+            // Skip photons on the other side of the surface, `eval` includes the cosine
+            let cos_theta = rec.normal.dot(&photon.wi);
+            if cos_theta > 0.0 {
+                sum += material.eval(wo, photon.wi, rec) / cos_theta * photon.power;
+            }
+        });
+        let area = PI * self.current_radius * self.current_radius;
+        sum / (area * self.photon_count as f64)
+    }
+}
+
+impl Integrator for PhotonMapIntegrator {
+    fn li(&self, ray: &Ray, scene: &Scene, _splats: &mut Vec<Splat>) -> Vec3 {
+        let mut l = Vec3::new(0.0, 0.0, 0.0);
+        let mut beta = Vec3::new(1.0, 1.0, 1.0);
+        let mut ray = *ray;
+        let mut bounce = Bounce::Other;
+        let mut previous = None;
+
+        for _ in 0..self.max_depth {
+            let mut rec = HitRecord {
+                ..Default::default()
+            };
+            if !scene.hit(&ray, 0.001, f64::INFINITY, &mut rec) {
+                l += beta * scene.background(&ray);
+                break;
+            }
+            let material = match &rec.material {
+                Some(material) => material.clone(),
+                // Objects without a material are shaded by their normal
+                None => {
+                    l += beta * 0.5 * (rec.normal + Vec3::new(1.0, 1.0, 1.0));
+                    break;
+                }
+            };
+
+            let emitted = material.emitted(&rec);
+            if !emitted.is_black()
+                && (bounce != Bounce::Caustic || scene.light_for_hit(&rec).is_none())
+            {
+                let weight = match previous {
+                    Some((from, pdf)) => emission_weight(scene, from, pdf, &rec),
+                    None => 1.0,
+                };
+                l += weight * beta * emitted;
+            }
+
+            let wo = -ray.dir.unit_vector();
+            if !material.is_delta() {
+                l += beta * sample_direct(scene, &rec, material.as_ref(), wo);
+            }
+            let gathered = !material.is_medium() && !material.is_delta();
+            if gathered {
+                l += beta * self.caustics(&rec, material.as_ref(), wo);
+            }
+
+            let scatter = match material.scatter(&ray, &rec) {
+                Some(scatter) => scatter,
+                None => break,
+            };
+            bounce = if gathered && !scatter.lobe.is_delta() {
+                Bounce::Diffuse
+            } else if material.is_delta() && bounce != Bounce::Other {
+                Bounce::Caustic
+            } else {
+                Bounce::Other
+            };
+            previous = if scatter.lobe.is_delta() {
+                None
+            } else {
+                Some((rec.p, scatter.pdf))
+            };
+            beta = beta * scatter.attenuation;
+            ray = scatter.ray;
+        }
+        l
+    }
+
+    fn set_max_depth(&mut self, depth: u32) {
+        self.max_depth = depth;
+    }
+
+    fn kind(&self) -> IntegratorKind {
+        if self.progressive {
+            IntegratorKind::ProgressivePhotonMap
+        } else {
+            IntegratorKind::PhotonMap
+        }
+    }
+
+    fn prepare(&mut self, scene: &Scene) {
+        if self.progressive {
+            if self.pass > 0 {
+                let pass = self.pass as f64;
+                self.current_radius *= ((pass + self.alpha) / (pass + 1.0)).sqrt();
+            }
+            self.pass += 1;
+            self.map = Some(self.trace_photons(scene));
+        } else if self.map.is_none() {
+            self.map = Some(self.trace_photons(scene));
+        }
+    }
+
+    fn reset(&mut self) {
+        self.current_radius = self.radius;
+        self.pass = 0;
+        self.map = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::hittable::{HittableList, Quad, Sphere};
+    use crate::integrator::{Integrator, PhotonMapIntegrator};
+    use crate::material::{Dielectric, DiffuseLight, Lambertian};
+    use crate::renderer::{render_image, Ray, Scene, Vec3};
+
+    /// A floor lit by a small lamp, with a glass ball hanging between them
+    fn caustic_scene(with_ball: bool) -> Scene {
+        let mut world = HittableList::new();
+        world.add_hittable(Box::new(Quad::with_material(
+            Vec3::new(-2.0, -0.5, -3.0),
+            Vec3::new(0.0, 0.0, 3.0),
+            Vec3::new(4.0, 0.0, 0.0),
+            Arc::new(Lambertian::new(Vec3::new(0.5, 0.5, 0.5))),
+        )));
+        if with_ball {
+            world.add_hittable(Box::new(Sphere::with_material(
+                Vec3::new(0.0, 0.3, -1.5),
+                0.3,
+                Arc::new(Dielectric::new(1.5)),
+            )));
+        }
+        let mut scene = Scene::new(world);
+        let emission = Arc::new(DiffuseLight::new(Vec3::new(10.0, 10.0, 10.0)));
+        scene.add_area_light(
+            Arc::new(Quad::with_material(
+                Vec3::new(-0.1, 1.5, -1.6),
+                Vec3::new(0.2, 0.0, 0.0),
+                Vec3::new(0.0, 0.0, 0.2),
+                emission.clone(),
+            )),
+            emission,
+        );
+        scene
+    }
+
+    /// Average brightness of the floor at `x`, looking in from the side so the ball isn't in
+    /// the way
+    fn floor_brightness(integrator: &PhotonMapIntegrator, scene: &Scene, x: f64) -> f64 {
+        let target = Vec3::new(x, -0.5, -1.5);
+        let origin = Vec3::new(x, -0.4, 0.0);
+        let ray = Ray::new(origin, target - origin);
+        let mut total = 0.0;
+        for _ in 0..200 {
+            total += integrator.li(&ray, scene, &mut Vec::new()).y();
+        }
+        total / 200.0
+    }
+
+    #[test]
+    fn test_progressive_passes() {
+        let scene = caustic_scene(true);
+        let render = |integrator: &mut PhotonMapIntegrator| {
+            integrator.photon_count = 1000;
+            render_image(&scene, integrator, (8, 6), 3, 0, 2, |_, _| {});
+        };
+
+        // Every sample is a pass with its own photons and a smaller radius
+        let mut progressive = PhotonMapIntegrator::progressive();
+        render(&mut progressive);
+        assert_eq!(progressive.pass, 3);
+        let shrunk = progressive.radius * ((1.0 + progressive.alpha) / 2.0).sqrt();
+        assert!(progressive.current_radius < shrunk);
+
+        // Without progressive passes the one map is kept
+        let mut fixed = PhotonMapIntegrator::default();
+        render(&mut fixed);
+        assert_eq!((fixed.pass, fixed.current_radius), (0, fixed.radius));
+    }
+
+    #[test]
+    fn test_glass_caustic() {
+        let new_integrator = || PhotonMapIntegrator {
+            photon_count: 20_000,
+            radius: 0.05,
+            ..PhotonMapIntegrator::progressive()
+        };
+        let scene = caustic_scene(true);
+        let mut integrator = new_integrator();
+        integrator.reset();
+        integrator.prepare(&scene);
+        let first = integrator.current_radius;
+        integrator.prepare(&scene);
+        assert!(integrator.current_radius < first);
+
+        // The glass ball focuses light onto the floor beneath it, brighter than the open floor
+        // beside it and brighter than the same spot with nothing in the way
+        let under = floor_brightness(&integrator, &scene, 0.0);
+        let beside = floor_brightness(&integrator, &scene, 0.6);
+        assert!(under > beside, "{under} under the ball, {beside} beside it");
+
+        let open = caustic_scene(false);
+        let mut without = new_integrator();
+        without.prepare(&open);
+        let unfocused = floor_brightness(&without, &open, 0.0);
+        assert!(
+            under > unfocused,
+            "{under} under the ball, {unfocused} without it"
+        );
+    }
+}
@@ -0,0 +1,137 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::integrator::{
+    AmbientOcclusionIntegrator, BdptIntegrator, NormalsIntegrator, PathIntegrator,
+    PhotonMapIntegrator, WhittedIntegrator,
+};
+use crate::renderer::{Ray, Scene, Vec3};
+
+/// Light landing on the image somewhere other than the pixel being sampled, at a position
+/// given the same way as `Camera::get_ray`
+pub struct Splat {
+    pub u: f64,
+    pub v: f64,
+    pub value: Vec3,
+}
+
+/// Works out how much light travels back along a camera ray
+pub trait Integrator: Send + Sync {
+    /// Incoming radiance along `ray`. Integrators tracing paths from the lights push what
+    /// they find for other pixels to `splats`
+    fn li(&self, ray: &Ray, scene: &Scene, splats: &mut Vec<Splat>) -> Vec3;
+
+    fn kind(&self) -> IntegratorKind;
+
+    /// Called before each pass over the image, for integrators which precompute from the scene.
+    /// Ones that aren't progressive should keep what they worked out until `reset`
+    fn prepare(&mut self, _scene: &Scene) {}
+
+    /// The scene has changed, so anything precomputed from it is out of date
+    fn reset(&mut self) {}
+
+    /// Limits how many bounces a path can take, for integrators which bounce at all
+    fn set_max_depth(&mut self, _depth: u32) {}
+}
+
+/// The integrators that can be picked at runtime
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum IntegratorKind {
+    Normals,
+    AmbientOcclusion,
+    Path,
+    Whitted,
+    Bidirectional,
+    PhotonMap,
+    ProgressivePhotonMap,
+    SpectralPath,
+}
+
+impl IntegratorKind {
+    pub const ALL: [IntegratorKind; 8] = [
+        IntegratorKind::Normals,
+        IntegratorKind::AmbientOcclusion,
+        IntegratorKind::Path,
+        IntegratorKind::Whitted,
+        IntegratorKind::Bidirectional,
+        IntegratorKind::PhotonMap,
+        IntegratorKind::ProgressivePhotonMap,
+        IntegratorKind::SpectralPath,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            IntegratorKind::Normals => "normals",
+            IntegratorKind::AmbientOcclusion => "ao",
+            IntegratorKind::Path => "path",
+            IntegratorKind::Whitted => "whitted",
+            IntegratorKind::Bidirectional => "bdpt",
+            IntegratorKind::PhotonMap => "pm",
+            IntegratorKind::ProgressivePhotonMap => "sppm",
+            IntegratorKind::SpectralPath => "spectral",
+        }
+    }
+
+    /// The next integrator along, for cycling through them in the viewer
+    pub fn next(&self) -> IntegratorKind {
+        let index = Self::ALL.iter().position(|kind| kind == self).unwrap();
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    /// Works something out again before every pass, so passes should be one sample per pixel
+    pub fn is_progressive(&self) -> bool {
+        matches!(self, IntegratorKind::ProgressivePhotonMap)
+    }
+
+    /// Creates the integrator with its default settings
+    pub fn create(&self) -> Box<dyn Integrator> {
+        match self {
+            IntegratorKind::Normals => Box::new(NormalsIntegrator),
+            IntegratorKind::AmbientOcclusion => Box::new(AmbientOcclusionIntegrator::default()),
+            IntegratorKind::Path => Box::new(PathIntegrator::default()),
+            IntegratorKind::Whitted => Box::new(WhittedIntegrator::default()),
+            IntegratorKind::Bidirectional => Box::new(BdptIntegrator::default()),
+            IntegratorKind::PhotonMap => Box::new(PhotonMapIntegrator::default()),
+            IntegratorKind::ProgressivePhotonMap => Box::new(PhotonMapIntegrator::progressive()),
+            IntegratorKind::SpectralPath => Box::new(PathIntegrator::spectral()),
+        }
+    }
+}
+
+impl fmt::Display for IntegratorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for IntegratorKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .iter()
+            .find(|kind| kind.name() == s)
+            .copied()
+            .ok_or_else(|| {
+                let names: Vec<_> = Self::ALL.iter().map(|kind| kind.name()).collect();
+                format!(
+                    "Unknown integrator '{s}', expected one of {}",
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::integrator::IntegratorKind;
+
+    #[test]
+    fn test_integrator_names() {
+        for kind in IntegratorKind::ALL {
+            assert_eq!(kind.name().parse::<IntegratorKind>(), Ok(kind));
+            assert_eq!(kind.create().kind(), kind);
+        }
+        assert!("nonsense".parse::<IntegratorKind>().is_err());
+    }
+}
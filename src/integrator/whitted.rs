@@ -0,0 +1,83 @@
+use std::f64::consts::PI;
+
+use crate::hittable::HitRecord;
+use crate::integrator::{Integrator, IntegratorKind, Splat};
+use crate::renderer::{Ray, Scene, Vec3};
+
+/// Classic recursive ray tracing, mirrors and glass are followed exactly while everything else
+/// only sees the lights and the sky straight above its surface
+pub struct WhittedIntegrator {
+    pub max_depth: u32,
+}
+
+impl Default for WhittedIntegrator {
+    fn default() -> Self {
+        Self { max_depth: 10 }
+    }
+}
+
+impl WhittedIntegrator {
+    fn ray_colour(&self, ray: &Ray, scene: &Scene, depth: u32) -> Vec3 {
+        if depth == 0 {
+            return Vec3::new(0.0, 0.0, 0.0);
+        }
+
+        let mut rec = HitRecord {
+            ..Default::default()
+        };
+
+        if !scene.hit(ray, 0.001, f64::INFINITY, &mut rec) {
+            return scene.background(ray);
+        }
+
+        let Some(material) = &rec.material else {
+            return 0.5 * (rec.normal + Vec3::new(1.0, 1.0, 1.0));
+        };
+
+        let mut colour = material.emitted(&rec);
+        if let Some(scatter) = material.scatter(ray, &rec) {
+            if scatter.lobe.is_delta() {
+                return colour
+                    + scatter.attenuation * self.ray_colour(&scatter.ray, scene, depth - 1);
+            }
+        }
+
+        // One sample of each light
+        let wo = -ray.dir.unit_vector();
+        for light in &scene.lights {
+            let Some(sample) = light.sample_li(rec.p) else {
+                continue;
+            };
+            let f = material.eval(wo, sample.wi, &rec);
+            if sample.pdf > 0.0 && !f.is_black() {
+                let visibility = scene.visibility(rec.p, sample.point);
+                colour += (visibility / sample.pdf) * f * sample.radiance;
+            }
+        }
+
+        // Treat the sky along the normal as if it lit the whole hemisphere, which is exact for
+        // a uniform sky and a diffuse surface
+        let sky_ray = Ray::new(rec.p, rec.normal);
+        let visibility = scene.transmittance(&sky_ray, 0.001, f64::INFINITY);
+        if visibility > 0.0 {
+            colour += (PI * visibility)
+                * material.eval(wo, rec.normal, &rec)
+                * scene.background(&sky_ray);
+        }
+        colour
+    }
+}
+
+impl Integrator for WhittedIntegrator {
+    fn li(&self, ray: &Ray, scene: &Scene, _splats: &mut Vec<Splat>) -> Vec3 {
+        self.ray_colour(ray, scene, self.max_depth)
+    }
+
+    fn set_max_depth(&mut self, depth: u32) {
+        self.max_depth = depth;
+    }
+
+    fn kind(&self) -> IntegratorKind {
+        IntegratorKind::Whitted
+    }
+}
@@ -0,0 +1,10 @@
+#![cfg_attr(feature = "simd", feature(portable_simd))]
+
+pub mod cli;
+pub mod commands;
+pub mod hittable;
+pub mod integrator;
+pub mod light;
+pub mod material;
+pub mod net;
+pub mod renderer;
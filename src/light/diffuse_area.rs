@@ -0,0 +1,132 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use crate::hittable::{HitRecord, Sampleable};
+use crate::light::{Light, LightEmission, LightSample};
+use crate::material::DiffuseLight;
+use crate::renderer::{random_double, Frame, Ray, Scene, Vec3};
+
+use Vec3 as Point3;
+
+/// A shape which emits evenly from the outside of its surface. The shape should be given
+/// the same `DiffuseLight` material so the light can recognise hits on it
+pub struct DiffuseAreaLight {
+    pub shape: Arc<dyn Sampleable>,
+    pub material: Arc<DiffuseLight>,
+}
+
+impl DiffuseAreaLight {
+    pub fn new(shape: Arc<dyn Sampleable>, material: Arc<DiffuseLight>) -> Self {
+        Self { shape, material }
+    }
+}
+
+impl Light for DiffuseAreaLight {
+    fn sample_li(&self, p: Point3) -> Option<LightSample> {
+        let (point, normal) = self.shape.sample_surface();
+        let to_light = point - p;
+        let distance = to_light.length();
+        if distance <= 0.0 {
+            return None;
+        }
+        let wi = to_light / distance;
+
+        // Convert the uniform area density into solid angle around `p`
+        let cos_light = normal.dot(&-wi);
+        if cos_light <= 0.0 {
+            return None;
+        }
+        let pdf = distance * distance / (cos_light * self.shape.area());
+
+        Some(LightSample {
+            point,
+            normal,
+            wi,
+            distance,
+            radiance: self.material.emit,
+            pdf,
+        })
+    }
+
+    fn pdf_li(&self, p: Point3, rec: &HitRecord) -> f64 {
+        let to_light = rec.p - p;
+        let cos_light = rec.normal.dot(&to_light.unit_vector()).abs();
+        if cos_light <= 0.0 {
+            return 0.0;
+        }
+        to_light.length_squared() / (cos_light * self.shape.area())
+    }
+
+    fn sample_le(&self, scene: &Scene) -> Option<LightEmission> {
+        let (point, normal) = self.shape.sample_surface();
+
+        // Cosine weighted about the normal
+        let r = random_double().sqrt();
+        let phi = 2.0 * PI * random_double();
+        let local = Vec3::new(r * phi.cos(), r * phi.sin(), (1.0 - r * r).max(0.0).sqrt());
+        let dir = Frame::from_normal(normal).to_world(local);
+
+        let (pdf_pos, pdf_dir) = self.pdf_le(scene, normal, dir);
+        if pdf_dir <= 0.0 {
+            return None;
+        }
+
+        Some(LightEmission {
+            ray: Ray::new(point, dir),
+            normal,
+            radiance: self.material.emit,
+            pdf_pos,
+            pdf_dir,
+        })
+    }
+
+    fn pdf_le(&self, _scene: &Scene, normal: Vec3, dir: Vec3) -> (f64, f64) {
+        let cos_theta = normal.dot(&dir.unit_vector());
+        (1.0 / self.shape.area(), cos_theta.max(0.0) / PI)
+    }
+
+    fn emits_from(&self, rec: &HitRecord) -> bool {
+        rec.material.as_ref().is_some_and(|material| {
+            Arc::as_ptr(material) as *const () == Arc::as_ptr(&self.material) as *const ()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::hittable::{HitRecord, Hittable, Quad};
+    use crate::light::{DiffuseAreaLight, Light};
+    use crate::material::DiffuseLight;
+    use crate::renderer::{Ray, Vec3};
+
+    #[test]
+    fn test_area_light_sample() {
+        let material = Arc::new(DiffuseLight::new(Vec3::new(4.0, 4.0, 4.0)));
+        let quad = Arc::new(Quad::with_material(
+            Vec3::new(-0.5, 1.0, -0.5),
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(0.0, 0.0, 1.0),
+            material.clone(),
+        ));
+        let light = DiffuseAreaLight::new(quad.clone(), material);
+
+        // Below the light, facing down at us
+        let sample = light.sample_li(Vec3::new(0.0, 0.0, 0.0)).unwrap();
+        assert!(sample.wi.y() > 0.0);
+        assert!(sample.pdf > 0.0);
+        assert_eq!(sample.radiance.x(), 4.0);
+
+        // Above it sees the unlit back
+        assert!(light.sample_li(Vec3::new(0.0, 2.0, 0.0)).is_none());
+
+        let mut rec = HitRecord {
+            ..Default::default()
+        };
+        let ray = Ray::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+        let hit = quad.hit(&ray, 0.0, f64::INFINITY, &mut rec);
+        assert!(hit);
+        assert!(light.emits_from(&rec));
+    }
+}
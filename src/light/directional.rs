@@ -0,0 +1,151 @@
+use std::f64::consts::PI;
+
+use crate::light::{Light, LightEmission, LightSample};
+use crate::renderer::{random_double, Frame, Ray, Scene, Vec3};
+
+use Vec3 as Point3;
+
+/// Light from very far away arriving from one direction, like the sun. Giving it an angular
+/// diameter spreads it over a small disk of the sky for softer shadows
+pub struct DirectionalLight {
+    /// Irradiance on a surface facing the light
+    pub irradiance: Vec3,
+    /// The way the light travels, the frame's normal
+    frame: Frame,
+    cos_max: f64,
+}
+
+impl DirectionalLight {
+    /// `angular_diameter` is in degrees, zero for perfectly sharp shadows
+    pub fn new(direction: Vec3, irradiance: Vec3, angular_diameter: f64) -> Self {
+        Self {
+            irradiance,
+            frame: Frame::from_normal(direction.unit_vector()),
+            cos_max: (angular_diameter / 2.0).to_radians().cos(),
+        }
+    }
+
+    fn is_sharp(&self) -> bool {
+        self.cos_max >= 1.0
+    }
+
+    /// Solid angle density of picking a direction inside the disk
+    fn cone_pdf(&self) -> f64 {
+        1.0 / (2.0 * PI * (1.0 - self.cos_max))
+    }
+
+    /// A direction of travel from somewhere on the disk
+    fn sample_direction(&self) -> Vec3 {
+        if self.is_sharp() {
+            return self.frame.n;
+        }
+        let cos_theta = 1.0 - random_double() * (1.0 - self.cos_max);
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi = 2.0 * PI * random_double();
+        self.frame.to_world(Vec3::new(
+            sin_theta * phi.cos(),
+            sin_theta * phi.sin(),
+            cos_theta,
+        ))
+    }
+
+    /// Radiance of the disk, spread out so it adds up to the irradiance
+    fn radiance(&self) -> Vec3 {
+        if self.is_sharp() {
+            self.irradiance
+        } else {
+            self.irradiance * self.cone_pdf()
+        }
+    }
+
+    fn direction_pdf(&self, dir: Vec3) -> f64 {
+        if self.is_sharp() || dir.unit_vector().dot(&self.frame.n) < self.cos_max {
+            0.0
+        } else {
+            self.cone_pdf()
+        }
+    }
+}
+
+impl Light for DirectionalLight {
+    fn sample_li(&self, p: Point3) -> Option<LightSample> {
+        let wi = -self.sample_direction();
+        let distance = 1e6;
+        Some(LightSample {
+            point: p + distance * wi,
+            normal: -wi,
+            wi,
+            distance,
+            radiance: self.radiance(),
+            pdf: if self.is_sharp() {
+                1.0
+            } else {
+                self.cone_pdf()
+            },
+        })
+    }
+
+    fn sample_le(&self, scene: &Scene) -> Option<LightEmission> {
+        let (centre, radius) = scene.bounding_sphere()?;
+        let dir = self.sample_direction();
+
+        // Start on a disk covering the scene, just outside it
+        let r = radius * random_double().sqrt();
+        let phi = 2.0 * PI * random_double();
+        let frame = Frame::from_normal(dir);
+        let origin =
+            centre - radius * dir + frame.to_world(Vec3::new(r * phi.cos(), r * phi.sin(), 0.0));
+
+        Some(LightEmission {
+            ray: Ray::new(origin, dir),
+            normal: dir,
+            radiance: self.radiance(),
+            pdf_pos: 1.0 / (PI * radius * radius),
+            pdf_dir: if self.is_sharp() {
+                1.0
+            } else {
+                self.cone_pdf()
+            },
+        })
+    }
+
+    fn pdf_le(&self, scene: &Scene, _normal: Vec3, dir: Vec3) -> (f64, f64) {
+        let pdf_pos = match scene.bounding_sphere() {
+            Some((_, radius)) => 1.0 / (PI * radius * radius),
+            None => 0.0,
+        };
+        (pdf_pos, self.direction_pdf(dir))
+    }
+
+    fn is_delta(&self) -> bool {
+        true
+    }
+
+    fn is_infinite(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hittable::HittableList;
+    use crate::light::{DirectionalLight, Light};
+    use crate::renderer::{Scene, Vec3};
+
+    #[test]
+    fn test_directional_light() {
+        let origin = Vec3::new(0.0, 0.0, 0.0);
+
+        // A soft sun still delivers the same irradiance per sample
+        let scene = Scene::new(HittableList::new());
+        for angle in [0.0, 5.0] {
+            let sun =
+                DirectionalLight::new(Vec3::new(0.0, -1.0, 0.0), Vec3::new(3.0, 3.0, 3.0), angle);
+            let sample = sun.sample_li(origin).unwrap();
+            assert!((sample.radiance.x() / sample.pdf - 3.0).abs() < 1e-9);
+            assert!(sample.wi.y() > 0.99);
+            // With nothing in the scene there's nowhere to aim light paths
+            assert!(sun.sample_le(&scene).is_none());
+        }
+    }
+}
@@ -0,0 +1,11 @@
+mod diffuse_area;
+mod directional;
+mod point;
+mod spot;
+mod traits;
+
+pub use diffuse_area::DiffuseAreaLight;
+pub use directional::DirectionalLight;
+pub use point::PointLight;
+pub use spot::SpotLight;
+pub use traits::{Light, LightEmission, LightSample};
@@ -0,0 +1,75 @@
+use std::f64::consts::PI;
+
+use crate::light::{Light, LightEmission, LightSample};
+use crate::renderer::{Ray, Scene, Vec3};
+
+use Vec3 as Point3;
+
+/// Light from a single point, falling off with the square of the distance
+pub struct PointLight {
+    pub position: Point3,
+    /// Radiant intensity, power per unit solid angle
+    pub intensity: Vec3,
+}
+
+impl PointLight {
+    pub fn new(position: Point3, intensity: Vec3) -> Self {
+        Self {
+            position,
+            intensity,
+        }
+    }
+}
+
+impl Light for PointLight {
+    fn sample_li(&self, p: Point3) -> Option<LightSample> {
+        let to_light = self.position - p;
+        let distance = to_light.length();
+        if distance <= 0.0 {
+            return None;
+        }
+
+        Some(LightSample {
+            point: self.position,
+            normal: Vec3::new(0.0, 0.0, 0.0),
+            wi: to_light / distance,
+            distance,
+            radiance: self.intensity / (distance * distance),
+            pdf: 1.0,
+        })
+    }
+
+    fn sample_le(&self, _scene: &Scene) -> Option<LightEmission> {
+        let dir = Vec3::random_unit_vector();
+        Some(LightEmission {
+            ray: Ray::new(self.position, dir),
+            normal: dir,
+            radiance: self.intensity,
+            pdf_pos: 1.0,
+            pdf_dir: 1.0 / (4.0 * PI),
+        })
+    }
+
+    fn pdf_le(&self, _scene: &Scene, _normal: Vec3, _dir: Vec3) -> (f64, f64) {
+        (0.0, 1.0 / (4.0 * PI))
+    }
+
+    fn is_delta(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::light::{Light, PointLight};
+    use crate::renderer::Vec3;
+
+    #[test]
+    fn test_point_light() {
+        // Inverse square falloff
+        let point = PointLight::new(Vec3::new(0.0, 2.0, 0.0), Vec3::new(8.0, 8.0, 8.0));
+        let sample = point.sample_li(Vec3::new(0.0, 0.0, 0.0)).unwrap();
+        assert_eq!(sample.radiance.x(), 2.0);
+        assert_eq!(sample.wi.y(), 1.0);
+    }
+}
@@ -0,0 +1,134 @@
+use std::f64::consts::PI;
+
+use crate::light::{Light, LightEmission, LightSample};
+use crate::renderer::{random_double, Frame, Ray, Scene, Vec3};
+
+use Vec3 as Point3;
+
+/// A point light shining into a cone, fading out towards its edge
+pub struct SpotLight {
+    pub position: Point3,
+    pub intensity: Vec3,
+    frame: Frame,
+    cos_total: f64,
+    cos_falloff_start: f64,
+}
+
+impl SpotLight {
+    /// Angles are in degrees from the centre of the cone, light is at full strength out to
+    /// `falloff_start` and fades to nothing at `cone_angle`
+    pub fn new(
+        position: Point3,
+        direction: Vec3,
+        intensity: Vec3,
+        cone_angle: f64,
+        falloff_start: f64,
+    ) -> Self {
+        let cos_total = cone_angle.to_radians().cos();
+        Self {
+            position,
+            intensity,
+            frame: Frame::from_normal(direction.unit_vector()),
+            cos_total,
+            cos_falloff_start: falloff_start.to_radians().cos().max(cos_total),
+        }
+    }
+
+    fn falloff(&self, dir: Vec3) -> f64 {
+        let cos_theta = dir.unit_vector().dot(&self.frame.n);
+        if cos_theta < self.cos_total {
+            return 0.0;
+        }
+        if cos_theta >= self.cos_falloff_start {
+            return 1.0;
+        }
+        let delta = (cos_theta - self.cos_total) / (self.cos_falloff_start - self.cos_total);
+        (delta * delta) * (delta * delta)
+    }
+
+    fn cone_pdf(&self) -> f64 {
+        1.0 / (2.0 * PI * (1.0 - self.cos_total))
+    }
+}
+
+impl Light for SpotLight {
+    fn sample_li(&self, p: Point3) -> Option<LightSample> {
+        let to_light = self.position - p;
+        let distance = to_light.length();
+        if distance <= 0.0 {
+            return None;
+        }
+        let wi = to_light / distance;
+        let falloff = self.falloff(-wi);
+        if falloff == 0.0 {
+            return None;
+        }
+
+        Some(LightSample {
+            point: self.position,
+            normal: Vec3::new(0.0, 0.0, 0.0),
+            wi,
+            distance,
+            radiance: falloff * self.intensity / (distance * distance),
+            pdf: 1.0,
+        })
+    }
+
+    fn sample_le(&self, _scene: &Scene) -> Option<LightEmission> {
+        // Uniform over the cone
+        let cos_theta = 1.0 - random_double() * (1.0 - self.cos_total);
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi = 2.0 * PI * random_double();
+        let dir = self.frame.to_world(Vec3::new(
+            sin_theta * phi.cos(),
+            sin_theta * phi.sin(),
+            cos_theta,
+        ));
+
+        Some(LightEmission {
+            ray: Ray::new(self.position, dir),
+            normal: dir,
+            radiance: self.falloff(dir) * self.intensity,
+            pdf_pos: 1.0,
+            pdf_dir: self.cone_pdf(),
+        })
+    }
+
+    fn pdf_le(&self, _scene: &Scene, _normal: Vec3, dir: Vec3) -> (f64, f64) {
+        if dir.unit_vector().dot(&self.frame.n) >= self.cos_total {
+            (0.0, self.cone_pdf())
+        } else {
+            (0.0, 0.0)
+        }
+    }
+
+    fn is_delta(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::light::{Light, SpotLight};
+    use crate::renderer::Vec3;
+
+    #[test]
+    fn test_spot_light() {
+        // Outside the cone there's nothing, in the middle it's full strength
+        let spot = SpotLight::new(
+            Vec3::new(0.0, 2.0, 0.0),
+            Vec3::new(0.0, -1.0, 0.0),
+            Vec3::new(8.0, 8.0, 8.0),
+            30.0,
+            20.0,
+        );
+        assert_eq!(
+            spot.sample_li(Vec3::new(0.0, 0.0, 0.0))
+                .unwrap()
+                .radiance
+                .x(),
+            2.0
+        );
+        assert!(spot.sample_li(Vec3::new(5.0, 0.0, 0.0)).is_none());
+    }
+}
@@ -0,0 +1,60 @@
+use crate::hittable::HitRecord;
+use crate::renderer::{Ray, Scene, Vec3};
+
+use Vec3 as Point3;
+
+/// Light arriving at a point from a sampled spot on a light
+pub struct LightSample {
+    pub point: Point3,
+    pub normal: Vec3,
+    /// Unit direction from the receiving point towards the light
+    pub wi: Vec3,
+    pub distance: f64,
+    pub radiance: Vec3,
+    /// Solid angle density of `wi`, one for lights that can only be reached one way
+    pub pdf: f64,
+}
+
+/// A ray of light leaving a light, used to start light paths
+pub struct LightEmission {
+    pub ray: Ray,
+    pub normal: Vec3,
+    pub radiance: Vec3,
+    /// Area density of the ray origin
+    pub pdf_pos: f64,
+    /// Solid angle density of the ray direction
+    pub pdf_dir: f64,
+}
+
+/// Something in the scene which can be sampled for direct lighting or light tracing
+pub trait Light: Send + Sync {
+    /// Picks a point on the light as seen from `p`
+    fn sample_li(&self, p: Point3) -> Option<LightSample>;
+
+    /// The solid angle density `sample_li` would pick `rec`, a hit on this light, from `p`
+    fn pdf_li(&self, _p: Point3, _rec: &HitRecord) -> f64 {
+        0.0
+    }
+
+    /// Picks a ray leaving the light, lights far away need the scene to know where to aim
+    fn sample_le(&self, scene: &Scene) -> Option<LightEmission>;
+
+    /// The position and direction densities `sample_le` would have for a ray leaving the
+    /// light in `dir` from a point with the given normal
+    fn pdf_le(&self, scene: &Scene, normal: Vec3, dir: Vec3) -> (f64, f64);
+
+    /// Lights which can't be hit by chance, like points, only reach the scene through sampling
+    fn is_delta(&self) -> bool {
+        false
+    }
+
+    /// Lights infinitely far away, whose light paths start on a disk facing the scene
+    fn is_infinite(&self) -> bool {
+        false
+    }
+
+    /// Whether a ray hitting `rec` has found this light
+    fn emits_from(&self, _rec: &HitRecord) -> bool {
+        false
+    }
+}
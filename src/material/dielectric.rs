@@ -0,0 +1,123 @@
+use crate::hittable::HitRecord;
+use crate::material::microfacet::{fresnel_dielectric, refract};
+use crate::material::{Lobe, Material, Scatter};
+use crate::renderer::{random_double, Ray, Vec3};
+
+/// Wavelength in nanometres that glass is usually quoted at, the helium d line
+const D_LINE: f64 = 587.6;
+
+/// Index of refraction, optionally varying with wavelength
+#[derive(Copy, Clone, Debug)]
+pub enum Ior {
+    Constant(f64),
+    /// `a + b / λ²` with λ in micrometres
+    Cauchy {
+        a: f64,
+        b: f64,
+    },
+    /// Sellmeier coefficients, `c` in square micrometres
+    Sellmeier {
+        b: [f64; 3],
+        c: [f64; 3],
+    },
+}
+
+impl Ior {
+    /// Schott N-BK7, a common crown glass
+    pub const BK7: Ior = Ior::Sellmeier {
+        b: [1.03961212, 0.231792344, 1.01046945],
+        c: [0.00600069867, 0.0200179144, 103.560653],
+    };
+
+    /// Cauchy glass with the given index at the helium d line, `b` setting how much it spreads
+    pub fn cauchy(ior: f64, b: f64) -> Self {
+        let lambda = D_LINE / 1000.0;
+        Ior::Cauchy {
+            a: ior - b / (lambda * lambda),
+            b,
+        }
+    }
+
+    /// The index at a wavelength in nanometres
+    pub fn at(&self, wavelength: f64) -> f64 {
+        let lambda = wavelength / 1000.0;
+        let lambda2 = lambda * lambda;
+        match *self {
+            Ior::Constant(ior) => ior,
+            Ior::Cauchy { a, b } => a + b / lambda2,
+            Ior::Sellmeier { b, c } => {
+                let sum: f64 = (0..3).map(|i| b[i] * lambda2 / (lambda2 - c[i])).sum();
+                (1.0 + sum).sqrt()
+            }
+        }
+    }
+}
+
+/// Smooth glass or water
+#[derive(Debug)]
+pub struct Dielectric {
+    pub ior: Ior,
+}
+
+impl Dielectric {
+    pub fn new(ior: f64) -> Self {
+        Self {
+            ior: Ior::Constant(ior),
+        }
+    }
+
+    /// Glass which splits light up by wavelength when rendering spectrally
+    pub fn dispersive(ior: Ior) -> Self {
+        Self { ior }
+    }
+}
+
+impl Material for Dielectric {
+    fn scatter(&self, ray_in: &Ray, rec: &HitRecord) -> Option<Scatter> {
+        let ior = self.ior.at(ray_in.wavelength.unwrap_or(D_LINE));
+        let eta = if rec.front_face { ior } else { 1.0 / ior };
+
+        let wo = -ray_in.dir.unit_vector();
+        let reflectance = fresnel_dielectric(wo.dot(&rec.normal), eta);
+
+        // Choose between reflection and refraction in proportion to the Fresnel term, so the
+        // weights cancel out
+        let (direction, lobe) = match refract(wo, rec.normal, eta) {
+            Some(refracted) if random_double() >= reflectance => {
+                (refracted, Lobe::SpecularTransmission)
+            }
+            _ => ((-wo).reflect(&rec.normal), Lobe::Specular),
+        };
+
+        Some(Scatter {
+            attenuation: Vec3::new(1.0, 1.0, 1.0),
+            ray: Ray::new(rec.p, direction),
+            pdf: 0.0,
+            lobe,
+        })
+    }
+
+    fn is_delta(&self) -> bool {
+        true
+    }
+
+    fn is_dispersive(&self) -> bool {
+        !matches!(self.ior, Ior::Constant(_))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::material::Ior;
+
+    #[test]
+    fn test_ior_dispersion() {
+        assert!((Ior::BK7.at(587.6) - 1.5168).abs() < 1e-4);
+        // Blue bends more than red
+        assert!(Ior::BK7.at(450.0) > Ior::BK7.at(650.0));
+        let cauchy = Ior::Cauchy { a: 1.5, b: 0.005 };
+        assert!(cauchy.at(450.0) > cauchy.at(650.0));
+        assert!((Ior::cauchy(1.5, 0.005).at(587.6) - 1.5).abs() < 1e-12);
+        assert_eq!(Ior::Constant(1.33).at(400.0), 1.33);
+    }
+}
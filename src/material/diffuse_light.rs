@@ -0,0 +1,29 @@
+use crate::hittable::HitRecord;
+use crate::material::{Material, Scatter};
+use crate::renderer::{Ray, Vec3};
+
+/// Gives off light from the front of a surface and absorbs everything that hits it
+#[derive(Debug)]
+pub struct DiffuseLight {
+    pub emit: Vec3,
+}
+
+impl DiffuseLight {
+    pub fn new(emit: Vec3) -> Self {
+        Self { emit }
+    }
+}
+
+impl Material for DiffuseLight {
+    fn scatter(&self, _ray_in: &Ray, _rec: &HitRecord) -> Option<Scatter> {
+        None
+    }
+
+    fn emitted(&self, rec: &HitRecord) -> Vec3 {
+        if rec.front_face {
+            self.emit
+        } else {
+            Vec3::new(0.0, 0.0, 0.0)
+        }
+    }
+}
@@ -0,0 +1,44 @@
+use std::f64::consts::PI;
+
+use crate::hittable::HitRecord;
+use crate::material::{Lobe, Material, Scatter};
+use crate::renderer::{Ray, Vec3};
+
+#[derive(Debug)]
+pub struct Lambertian {
+    pub albedo: Vec3,
+}
+
+impl Lambertian {
+    pub fn new(albedo: Vec3) -> Self {
+        Self { albedo }
+    }
+}
+
+impl Material for Lambertian {
+    fn scatter(&self, _ray_in: &Ray, rec: &HitRecord) -> Option<Scatter> {
+        // Offsetting the normal by a point on the unit sphere gives a cosine distribution
+        let mut direction = rec.normal + Vec3::random_unit_vector();
+
+        // Catch degenerate scatter directions
+        if direction.length_squared() < 1e-12 {
+            direction = rec.normal;
+        }
+
+        let direction = direction.unit_vector();
+        Some(Scatter {
+            attenuation: self.albedo,
+            ray: Ray::new(rec.p, direction),
+            pdf: rec.normal.dot(&direction).max(0.0) / PI,
+            lobe: Lobe::Diffuse,
+        })
+    }
+
+    fn eval(&self, _wo: Vec3, wi: Vec3, rec: &HitRecord) -> Vec3 {
+        (rec.normal.dot(&wi).max(0.0) / PI) * self.albedo
+    }
+
+    fn pdf(&self, _wo: Vec3, wi: Vec3, rec: &HitRecord) -> f64 {
+        rec.normal.dot(&wi).max(0.0) / PI
+    }
+}
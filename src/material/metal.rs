@@ -0,0 +1,42 @@
+use crate::hittable::HitRecord;
+use crate::material::{Lobe, Material, Scatter};
+use crate::renderer::{Ray, Vec3};
+
+/// A mirror, `fuzz` randomly perturbs the reflection to look brushed
+#[derive(Debug)]
+pub struct Metal {
+    pub albedo: Vec3,
+    pub fuzz: f64,
+}
+
+impl Metal {
+    pub fn new(albedo: Vec3, fuzz: f64) -> Self {
+        Self {
+            albedo,
+            fuzz: fuzz.clamp(0.0, 1.0),
+        }
+    }
+}
+
+impl Material for Metal {
+    fn scatter(&self, ray_in: &Ray, rec: &HitRecord) -> Option<Scatter> {
+        let reflected = ray_in.dir.unit_vector().reflect(&rec.normal);
+        let direction = reflected + self.fuzz * Vec3::random_unit_vector();
+
+        // Fuzzed below the surface, absorb it
+        if direction.dot(&rec.normal) <= 0.0 {
+            return None;
+        }
+
+        Some(Scatter {
+            attenuation: self.albedo,
+            ray: Ray::new(rec.p, direction),
+            pdf: 0.0,
+            lobe: Lobe::Specular,
+        })
+    }
+
+    fn is_delta(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,177 @@
+use std::f64::consts::PI;
+
+use crate::renderer::Vec3;
+
+/// Below this roughness surfaces are treated as perfectly smooth
+pub const DELTA_ALPHA: f64 = 1e-3;
+
+/// The GGX (Trowbridge-Reitz) microfacet distribution, all directions are in the local shading
+/// frame where the normal is +z
+#[derive(Copy, Clone, Debug)]
+pub struct Ggx {
+    pub alpha: f64,
+}
+
+impl Ggx {
+    /// Uses the common remapping of perceptual roughness, alpha = roughness squared
+    pub fn from_roughness(roughness: f64) -> Self {
+        Self {
+            alpha: (roughness * roughness).max(DELTA_ALPHA * 0.5),
+        }
+    }
+
+    pub fn is_smooth(&self) -> bool {
+        self.alpha < DELTA_ALPHA
+    }
+
+    /// Distribution of microfacet normals
+    pub fn d(&self, wm: Vec3) -> f64 {
+        let cos2 = wm.z() * wm.z();
+        if cos2 <= 0.0 {
+            return 0.0;
+        }
+        let a2 = self.alpha * self.alpha;
+        let denom = cos2 * (a2 - 1.0) + 1.0;
+        a2 / (PI * denom * denom)
+    }
+
+    fn lambda(&self, w: Vec3) -> f64 {
+        let cos2 = w.z() * w.z();
+        if cos2 <= 0.0 {
+            return 0.0;
+        }
+        let tan2 = (1.0 - cos2).max(0.0) / cos2;
+        (f64::sqrt(1.0 + self.alpha * self.alpha * tan2) - 1.0) / 2.0
+    }
+
+    /// Smith masking for a single direction
+    pub fn g1(&self, w: Vec3) -> f64 {
+        1.0 / (1.0 + self.lambda(w))
+    }
+
+    /// Height correlated Smith masking-shadowing
+    pub fn g(&self, wo: Vec3, wi: Vec3) -> f64 {
+        1.0 / (1.0 + self.lambda(wo) + self.lambda(wi))
+    }
+
+    /// Density of visible normals as seen from `w`
+    pub fn visible_d(&self, w: Vec3, wm: Vec3) -> f64 {
+        let cos_o = w.z().abs();
+        if cos_o == 0.0 {
+            return 0.0;
+        }
+        self.g1(w) * w.dot(&wm).abs() * self.d(wm) / cos_o
+    }
+
+    /// Samples a normal from the visible normals as seen from `w` (Heitz 2018)
+    pub fn sample_visible_normal(&self, w: Vec3, u1: f64, u2: f64) -> Vec3 {
+        // Make sure we're sampling from the upper hemisphere
+        let w = if w.z() < 0.0 { -w } else { w };
+
+        // Stretch into the hemisphere configuration
+        let vh = Vec3::new(self.alpha * w.x(), self.alpha * w.y(), w.z()).unit_vector();
+        let len_sq = vh.x() * vh.x() + vh.y() * vh.y();
+        let t1 = if len_sq > 0.0 {
+            Vec3::new(-vh.y(), vh.x(), 0.0) / len_sq.sqrt()
+        } else {
+            Vec3::new(1.0, 0.0, 0.0)
+        };
+        let t2 = vh.cross(&t1);
+
+        // Sample the projected disk, warped towards the visible half
+        let r = u1.sqrt();
+        let phi = 2.0 * PI * u2;
+        let p1 = r * phi.cos();
+        let mut p2 = r * phi.sin();
+        let s = 0.5 * (1.0 + vh.z());
+        p2 = (1.0 - s) * f64::sqrt(1.0 - p1 * p1) + s * p2;
+
+        let nh = p1 * t1 + p2 * t2 + f64::sqrt((1.0 - p1 * p1 - p2 * p2).max(0.0)) * vh;
+
+        // Unstretch
+        Vec3::new(self.alpha * nh.x(), self.alpha * nh.y(), nh.z().max(1e-6)).unit_vector()
+    }
+}
+
+/// The generalised Trowbridge-Reitz distribution with gamma = 1, used by the clearcoat lobe
+#[derive(Copy, Clone, Debug)]
+pub struct Gtr1 {
+    pub alpha: f64,
+}
+
+impl Gtr1 {
+    pub fn d(&self, wm: Vec3) -> f64 {
+        let a2 = self.alpha * self.alpha;
+        let cos2 = wm.z() * wm.z();
+        (a2 - 1.0) / (PI * a2.ln() * (1.0 + (a2 - 1.0) * cos2))
+    }
+
+    pub fn sample_normal(&self, u1: f64, u2: f64) -> Vec3 {
+        let a2 = self.alpha * self.alpha;
+        let cos_theta = f64::sqrt(((1.0 - a2.powf(1.0 - u1)) / (1.0 - a2)).max(0.0));
+        let sin_theta = f64::sqrt((1.0 - cos_theta * cos_theta).max(0.0));
+        let phi = 2.0 * PI * u2;
+        Vec3::new(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta)
+    }
+}
+
+/// Schlick's approximation of Fresnel reflectance
+pub fn fresnel_schlick(f0: Vec3, cos_theta: f64) -> Vec3 {
+    let m = (1.0 - cos_theta).clamp(0.0, 1.0).powi(5);
+    f0 + m * (Vec3::new(1.0, 1.0, 1.0) - f0)
+}
+
+/// Exact Fresnel reflectance at a dielectric boundary, `eta` is the ratio of the transmitted to
+/// the incident index of refraction
+pub fn fresnel_dielectric(cos_theta_i: f64, eta: f64) -> f64 {
+    let (cos_theta_i, eta) = if cos_theta_i < 0.0 {
+        (-cos_theta_i, 1.0 / eta)
+    } else {
+        (cos_theta_i, eta)
+    };
+    let sin2_theta_t = (1.0 - cos_theta_i * cos_theta_i) / (eta * eta);
+    if sin2_theta_t >= 1.0 {
+        // Total internal reflection
+        return 1.0;
+    }
+    let cos_theta_t = f64::sqrt((1.0 - sin2_theta_t).max(0.0));
+    let r_parallel = (eta * cos_theta_i - cos_theta_t) / (eta * cos_theta_i + cos_theta_t);
+    let r_perpendicular = (cos_theta_i - eta * cos_theta_t) / (cos_theta_i + eta * cos_theta_t);
+    (r_parallel * r_parallel + r_perpendicular * r_perpendicular) / 2.0
+}
+
+/// Refracts `w` (pointing away from the surface) through the boundary with normal `n` on the
+/// same side, None on total internal reflection
+pub fn refract(w: Vec3, n: Vec3, eta: f64) -> Option<Vec3> {
+    let cos_theta_i = n.dot(&w);
+    let sin2_theta_i = (1.0 - cos_theta_i * cos_theta_i).max(0.0);
+    let sin2_theta_t = sin2_theta_i / (eta * eta);
+    if sin2_theta_t >= 1.0 {
+        return None;
+    }
+    let cos_theta_t = f64::sqrt(1.0 - sin2_theta_t);
+    Some(-w / eta + (cos_theta_i / eta - cos_theta_t) * n)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use crate::material::microfacet::Ggx;
+    use crate::renderer::Vec3;
+
+    #[test]
+    fn test_ggx_normalised() {
+        // The projected area of the microfacets should equal the macro surface
+        let ggx = Ggx::from_roughness(0.5);
+        let steps = 200_000;
+        let mut total = 0.;
+        for i in 0..steps {
+            let cos_theta = (i as f64 + 0.5) / steps as f64;
+            let wm = Vec3::new(f64::sqrt(1. - cos_theta * cos_theta), 0., cos_theta);
+            total += ggx.d(wm) * cos_theta * 2. * PI / steps as f64;
+        }
+
+        assert!((total - 1.).abs() < 1e-3);
+    }
+}
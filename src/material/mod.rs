@@ -0,0 +1,16 @@
+mod dielectric;
+mod diffuse_light;
+mod lambertian;
+mod metal;
+mod microfacet;
+mod phase;
+mod principled;
+mod traits;
+
+pub use dielectric::{Dielectric, Ior};
+pub use diffuse_light::DiffuseLight;
+pub use lambertian::Lambertian;
+pub use metal::Metal;
+pub use phase::{HenyeyGreenstein, Isotropic};
+pub use principled::Principled;
+pub use traits::{Lobe, Material, Scatter};
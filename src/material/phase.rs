@@ -0,0 +1,125 @@
+use std::f64::consts::PI;
+
+use crate::hittable::HitRecord;
+use crate::material::{Lobe, Material, Scatter};
+use crate::renderer::{random_double, Ray, Vec3};
+
+/// Phase function which scatters equally in all directions
+#[derive(Debug)]
+pub struct Isotropic {
+    pub albedo: Vec3,
+}
+
+impl Isotropic {
+    pub fn new(albedo: Vec3) -> Self {
+        Self { albedo }
+    }
+}
+
+impl Material for Isotropic {
+    fn scatter(&self, _ray_in: &Ray, rec: &HitRecord) -> Option<Scatter> {
+        Some(Scatter {
+            attenuation: self.albedo,
+            ray: Ray::new(rec.p, Vec3::random_unit_vector()),
+            pdf: 1. / (4. * PI),
+            lobe: Lobe::Diffuse,
+        })
+    }
+
+    fn eval(&self, _wo: Vec3, _wi: Vec3, _rec: &HitRecord) -> Vec3 {
+        (1. / (4. * PI)) * self.albedo
+    }
+
+    fn pdf(&self, _wo: Vec3, _wi: Vec3, _rec: &HitRecord) -> f64 {
+        1. / (4. * PI)
+    }
+
+    fn is_medium(&self) -> bool {
+        true
+    }
+}
+
+/// Henyey-Greenstein phase function, `g` > 0 scatters forwards and `g` < 0 backwards
+#[derive(Debug)]
+pub struct HenyeyGreenstein {
+    pub albedo: Vec3,
+    pub g: f64,
+}
+
+impl HenyeyGreenstein {
+    pub fn new(albedo: Vec3, g: f64) -> Self {
+        Self {
+            albedo,
+            g: g.clamp(-0.999, 0.999),
+        }
+    }
+
+    /// Evaluates the phase function for the cosine between the incoming and scattered directions
+    pub fn p(&self, cos_theta: f64) -> f64 {
+        let denom = 1. + self.g * self.g - 2. * self.g * cos_theta;
+        (1. - self.g * self.g) / (4. * PI * denom * denom.sqrt())
+    }
+
+    /// Samples the cosine between the incoming and scattered directions
+    fn sample_cos_theta(&self, u: f64) -> f64 {
+        let g = self.g;
+        if g.abs() < 1e-3 {
+            return 1. - 2. * u;
+        }
+        let sqr = (1. - g * g) / (1. - g + 2. * g * u);
+        ((1. + g * g - sqr * sqr) / (2. * g)).clamp(-1., 1.)
+    }
+}
+
+impl Material for HenyeyGreenstein {
+    fn scatter(&self, ray_in: &Ray, rec: &HitRecord) -> Option<Scatter> {
+        let forward = ray_in.dir.unit_vector();
+        let (u, v) = forward.orthonormal_basis();
+
+        let cos_theta = self.sample_cos_theta(random_double());
+        let sin_theta = f64::sqrt(f64::max(0., 1. - cos_theta * cos_theta));
+        let phi = 2. * PI * random_double();
+        let direction = sin_theta * phi.cos() * u + sin_theta * phi.sin() * v + cos_theta * forward;
+
+        Some(Scatter {
+            attenuation: self.albedo,
+            ray: Ray::new(rec.p, direction),
+            pdf: self.p(cos_theta),
+            lobe: Lobe::Diffuse,
+        })
+    }
+
+    fn eval(&self, wo: Vec3, wi: Vec3, _rec: &HitRecord) -> Vec3 {
+        // `wo` points back along the ray, so forward scattering is when `wi` is opposite it
+        self.p(-wo.dot(&wi)) * self.albedo
+    }
+
+    fn pdf(&self, wo: Vec3, wi: Vec3, _rec: &HitRecord) -> f64 {
+        self.p(-wo.dot(&wi))
+    }
+
+    fn is_medium(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::material::HenyeyGreenstein;
+    use crate::renderer::Vec3;
+
+    #[test]
+    fn test_henyey_greenstein_normalised() {
+        // Integrating the phase function over the sphere should give one
+        let phase = HenyeyGreenstein::new(Vec3::new(1., 1., 1.), 0.7);
+        let steps = 100_000;
+        let mut total = 0.;
+        for i in 0..steps {
+            let cos_theta = -1. + 2. * (i as f64 + 0.5) / steps as f64;
+            total += phase.p(cos_theta) * 2. / steps as f64;
+        }
+        total *= 2. * std::f64::consts::PI;
+
+        assert!((total - 1.).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,582 @@
+use std::f64::consts::PI;
+
+use crate::hittable::HitRecord;
+use crate::material::microfacet::{
+    fresnel_dielectric, fresnel_schlick, refract, Ggx, Gtr1, DELTA_ALPHA,
+};
+use crate::material::{Lobe, Material, Scatter};
+use crate::renderer::{random_double, Frame, Ray, Vec3};
+
+/// A direction sampled from one lobe, in the local shading frame
+pub struct LobeSample {
+    pub wi: Vec3,
+    /// Only set for delta samples, the value times cosine over the discrete probability
+    pub weight: Option<Vec3>,
+}
+
+/// One component of a BSDF, all directions are in the local shading frame with `wo.z()` > 0
+pub trait Bxdf {
+    /// The value of the lobe times the cosine of `wi`
+    fn eval(&self, wo: Vec3, wi: Vec3) -> Vec3;
+    fn sample(&self, wo: Vec3) -> Option<LobeSample>;
+    fn pdf(&self, wo: Vec3, wi: Vec3) -> f64;
+    fn is_delta(&self) -> bool {
+        false
+    }
+}
+
+fn schlick_weight(cos_theta: f64) -> f64 {
+    (1.0 - cos_theta).clamp(0.0, 1.0).powi(5)
+}
+
+fn lerp(a: Vec3, b: Vec3, t: f64) -> Vec3 {
+    (1.0 - t) * a + t * b
+}
+
+fn reflect_local(wo: Vec3, wm: Vec3) -> Vec3 {
+    -wo + 2.0 * wo.dot(&wm) * wm
+}
+
+fn cosine_sample_hemisphere() -> Vec3 {
+    let r = random_double().sqrt();
+    let phi = 2.0 * PI * random_double();
+    let x = r * phi.cos();
+    let y = r * phi.sin();
+    Vec3::new(x, y, f64::sqrt((1.0 - x * x - y * y).max(0.0)))
+}
+
+/// Burley's retro-reflective diffuse plus the grazing angle sheen for cloth
+pub struct DiffuseLobe {
+    pub colour: Vec3,
+    pub roughness: f64,
+    pub sheen: f64,
+    pub sheen_colour: Vec3,
+}
+
+impl Bxdf for DiffuseLobe {
+    fn eval(&self, wo: Vec3, wi: Vec3) -> Vec3 {
+        if wo.z() <= 0.0 || wi.z() <= 0.0 {
+            return Vec3::new(0.0, 0.0, 0.0);
+        }
+        let wm = (wo + wi).unit_vector();
+        let cos_d = wi.dot(&wm);
+        let fd90 = 0.5 + 2.0 * self.roughness * cos_d * cos_d;
+        let fl = schlick_weight(wi.z());
+        let fv = schlick_weight(wo.z());
+        let retro = (1.0 + (fd90 - 1.0) * fl) * (1.0 + (fd90 - 1.0) * fv);
+
+        let diffuse = (retro / PI) * self.colour;
+        let sheen = (self.sheen * schlick_weight(cos_d)) * self.sheen_colour;
+        wi.z() * (diffuse + sheen)
+    }
+
+    fn sample(&self, _wo: Vec3) -> Option<LobeSample> {
+        Some(LobeSample {
+            wi: cosine_sample_hemisphere(),
+            weight: None,
+        })
+    }
+
+    fn pdf(&self, wo: Vec3, wi: Vec3) -> f64 {
+        if wo.z() <= 0.0 || wi.z() <= 0.0 {
+            return 0.0;
+        }
+        wi.z() / PI
+    }
+}
+
+/// GGX reflection with Schlick Fresnel, covers both metals and the dielectric highlight
+pub struct SpecularLobe {
+    pub distribution: Ggx,
+    pub f0: Vec3,
+    pub scale: f64,
+}
+
+impl Bxdf for SpecularLobe {
+    fn eval(&self, wo: Vec3, wi: Vec3) -> Vec3 {
+        if self.is_delta() || wo.z() <= 0.0 || wi.z() <= 0.0 {
+            return Vec3::new(0.0, 0.0, 0.0);
+        }
+        let wm = (wo + wi).unit_vector();
+        let d = self.distribution.d(wm);
+        let g = self.distribution.g(wo, wi);
+        let f = fresnel_schlick(self.f0, wo.dot(&wm));
+        // The cosine to wi cancels with the denominator
+        (self.scale * d * g / (4.0 * wo.z())) * f
+    }
+
+    fn sample(&self, wo: Vec3) -> Option<LobeSample> {
+        if self.is_delta() {
+            let wi = Vec3::new(-wo.x(), -wo.y(), wo.z());
+            return Some(LobeSample {
+                wi,
+                weight: Some(self.scale * fresnel_schlick(self.f0, wo.z())),
+            });
+        }
+        let wm = self
+            .distribution
+            .sample_visible_normal(wo, random_double(), random_double());
+        let wi = reflect_local(wo, wm);
+        if wi.z() <= 0.0 {
+            return None;
+        }
+        Some(LobeSample { wi, weight: None })
+    }
+
+    fn pdf(&self, wo: Vec3, wi: Vec3) -> f64 {
+        if self.is_delta() || wo.z() <= 0.0 || wi.z() <= 0.0 {
+            return 0.0;
+        }
+        let wm = (wo + wi).unit_vector();
+        self.distribution.visible_d(wo, wm) / (4.0 * wo.dot(&wm).abs())
+    }
+
+    fn is_delta(&self) -> bool {
+        self.distribution.is_smooth()
+    }
+}
+
+/// A thin, colourless, glossy layer on top of everything else
+pub struct ClearcoatLobe {
+    pub distribution: Gtr1,
+    pub scale: f64,
+}
+
+impl ClearcoatLobe {
+    /// The clearcoat's own masking always uses a fixed roughness
+    const MASKING: Ggx = Ggx { alpha: 0.25 };
+}
+
+impl Bxdf for ClearcoatLobe {
+    fn eval(&self, wo: Vec3, wi: Vec3) -> Vec3 {
+        if wo.z() <= 0.0 || wi.z() <= 0.0 {
+            return Vec3::new(0.0, 0.0, 0.0);
+        }
+        let wm = (wo + wi).unit_vector();
+        let d = self.distribution.d(wm);
+        let g = Self::MASKING.g1(wo) * Self::MASKING.g1(wi);
+        let f = 0.04 + 0.96 * schlick_weight(wo.dot(&wm));
+        let value = self.scale * d * g * f / (4.0 * wo.z());
+        Vec3::new(value, value, value)
+    }
+
+    fn sample(&self, wo: Vec3) -> Option<LobeSample> {
+        let wm = self
+            .distribution
+            .sample_normal(random_double(), random_double());
+        let wi = reflect_local(wo, wm);
+        if wi.z() <= 0.0 {
+            return None;
+        }
+        Some(LobeSample { wi, weight: None })
+    }
+
+    fn pdf(&self, wo: Vec3, wi: Vec3) -> f64 {
+        if wo.z() <= 0.0 || wi.z() <= 0.0 {
+            return 0.0;
+        }
+        let wm = (wo + wi).unit_vector();
+        self.distribution.d(wm) * wm.z() / (4.0 * wo.dot(&wm).abs())
+    }
+}
+
+/// Rough dielectric reflection and refraction (Walter et al. 2007), `eta` is the ratio of the
+/// index of refraction on the far side to the near side
+pub struct TransmissionLobe {
+    pub distribution: Ggx,
+    pub eta: f64,
+    pub colour: Vec3,
+    pub scale: f64,
+}
+
+impl TransmissionLobe {
+    /// The generalised half vector, None for configurations no microfacet can produce
+    fn half_vector(&self, wo: Vec3, wi: Vec3) -> Option<Vec3> {
+        let reflect = wi.z() > 0.0;
+        let etap = if reflect { 1.0 } else { self.eta };
+        let wm = wi * etap + wo;
+        if wi.z() == 0.0 || wm.length_squared() == 0.0 {
+            return None;
+        }
+        let mut wm = wm.unit_vector();
+        if wm.z() < 0.0 {
+            wm = -wm;
+        }
+        // Discard back facing microfacets
+        if wm.dot(&wi) * wi.z() < 0.0 || wm.dot(&wo) * wo.z() < 0.0 {
+            return None;
+        }
+        Some(wm)
+    }
+}
+
+impl Bxdf for TransmissionLobe {
+    fn eval(&self, wo: Vec3, wi: Vec3) -> Vec3 {
+        let zero = Vec3::new(0.0, 0.0, 0.0);
+        if self.is_delta() || wo.z() <= 0.0 {
+            return zero;
+        }
+        let Some(wm) = self.half_vector(wo, wi) else {
+            return zero;
+        };
+
+        let d = self.distribution.d(wm);
+        let g = self.distribution.g(wo, wi);
+        let fresnel = fresnel_dielectric(wo.dot(&wm), self.eta);
+
+        if wi.z() > 0.0 {
+            let value = self.scale * d * g * fresnel / (4.0 * wo.z());
+            Vec3::new(value, value, value)
+        } else {
+            let denom = wi.dot(&wm) + wo.dot(&wm) / self.eta;
+            let denom = denom * denom;
+            let value =
+                d * g * (1.0 - fresnel) * (wi.dot(&wm) * wo.dot(&wm) / (wo.z() * denom)).abs();
+            (self.scale * value) * self.colour
+        }
+    }
+
+    fn sample(&self, wo: Vec3) -> Option<LobeSample> {
+        if self.is_delta() {
+            // Pick reflection or refraction by the Fresnel term, which then cancels
+            let n = Vec3::new(0.0, 0.0, 1.0);
+            let fresnel = fresnel_dielectric(wo.z(), self.eta);
+            return match refract(wo, n, self.eta) {
+                Some(wi) if random_double() >= fresnel => Some(LobeSample {
+                    wi,
+                    weight: Some(self.scale * self.colour),
+                }),
+                _ => Some(LobeSample {
+                    wi: Vec3::new(-wo.x(), -wo.y(), wo.z()),
+                    weight: Some(Vec3::new(self.scale, self.scale, self.scale)),
+                }),
+            };
+        }
+
+        let wm = self
+            .distribution
+            .sample_visible_normal(wo, random_double(), random_double());
+        let fresnel = fresnel_dielectric(wo.dot(&wm), self.eta);
+        let wi = if random_double() < fresnel {
+            let wi = reflect_local(wo, wm);
+            if wi.z() <= 0.0 {
+                return None;
+            }
+            wi
+        } else {
+            let wi = refract(wo, wm, self.eta)?;
+            if wi.z() >= 0.0 {
+                return None;
+            }
+            wi
+        };
+        Some(LobeSample { wi, weight: None })
+    }
+
+    fn pdf(&self, wo: Vec3, wi: Vec3) -> f64 {
+        if self.is_delta() || wo.z() <= 0.0 {
+            return 0.0;
+        }
+        let Some(wm) = self.half_vector(wo, wi) else {
+            return 0.0;
+        };
+
+        let fresnel = fresnel_dielectric(wo.dot(&wm), self.eta);
+        let visible = self.distribution.visible_d(wo, wm);
+        if wi.z() > 0.0 {
+            visible / (4.0 * wo.dot(&wm).abs()) * fresnel
+        } else {
+            let denom = wi.dot(&wm) + wo.dot(&wm) / self.eta;
+            let dwm_dwi = wi.dot(&wm).abs() / (denom * denom);
+            visible * dwm_dwi * (1.0 - fresnel)
+        }
+    }
+
+    fn is_delta(&self) -> bool {
+        self.distribution.is_smooth()
+    }
+}
+
+/// A Disney style principled material using the metallic/roughness workflow
+#[derive(Clone, Debug)]
+pub struct Principled {
+    pub base_colour: Vec3,
+    pub metallic: f64,
+    /// Perceptual roughness, squared to get the GGX alpha
+    pub roughness: f64,
+    /// Dielectric reflectance at normal incidence, 0.5 gives the usual 4%
+    pub specular: f64,
+    /// Tints the dielectric highlight towards the base colour
+    pub specular_tint: f64,
+    pub sheen: f64,
+    pub sheen_tint: f64,
+    pub clearcoat: f64,
+    pub clearcoat_gloss: f64,
+    /// Fraction of the dielectric part which refracts rather than diffusely reflects
+    pub transmission: f64,
+    pub ior: f64,
+}
+
+impl Default for Principled {
+    fn default() -> Self {
+        Self {
+            base_colour: Vec3::new(0.8, 0.8, 0.8),
+            metallic: 0.0,
+            roughness: 0.5,
+            specular: 0.5,
+            specular_tint: 0.0,
+            sheen: 0.0,
+            sheen_tint: 0.5,
+            clearcoat: 0.0,
+            clearcoat_gloss: 1.0,
+            transmission: 0.0,
+            ior: 1.5,
+        }
+    }
+}
+
+/// The lobes of a principled material at a hit point, along with how often each is sampled
+struct Lobes {
+    diffuse: DiffuseLobe,
+    specular: SpecularLobe,
+    clearcoat: ClearcoatLobe,
+    transmission: TransmissionLobe,
+    probabilities: [f64; 4],
+}
+
+impl Lobes {
+    fn all(&self) -> [&dyn Bxdf; 4] {
+        [
+            &self.diffuse,
+            &self.specular,
+            &self.clearcoat,
+            &self.transmission,
+        ]
+    }
+
+    fn eval(&self, wo: Vec3, wi: Vec3) -> Vec3 {
+        let mut value = Vec3::new(0.0, 0.0, 0.0);
+        for (lobe, &probability) in self.all().iter().zip(&self.probabilities) {
+            if probability > 0.0 && !lobe.is_delta() {
+                value += lobe.eval(wo, wi);
+            }
+        }
+        value
+    }
+
+    fn pdf(&self, wo: Vec3, wi: Vec3) -> f64 {
+        self.all()
+            .iter()
+            .zip(&self.probabilities)
+            .filter(|(lobe, &probability)| probability > 0.0 && !lobe.is_delta())
+            .map(|(lobe, probability)| probability * lobe.pdf(wo, wi))
+            .sum()
+    }
+}
+
+impl Principled {
+    pub fn new(base_colour: Vec3, metallic: f64, roughness: f64) -> Self {
+        Self {
+            base_colour,
+            metallic: metallic.clamp(0.0, 1.0),
+            roughness: roughness.clamp(0.0, 1.0),
+            ..Default::default()
+        }
+    }
+
+    fn lobes(&self, front_face: bool) -> Lobes {
+        let white = Vec3::new(1.0, 1.0, 1.0);
+        let luminance = self.base_colour.luminance();
+        let tint = if luminance > 0.0 {
+            self.base_colour / luminance
+        } else {
+            white
+        };
+
+        let dielectric = 1.0 - self.metallic;
+        let diffuse_weight = dielectric * (1.0 - self.transmission);
+        let transmission_weight = dielectric * self.transmission;
+
+        let f0 = lerp(
+            (0.08 * self.specular) * lerp(white, tint, self.specular_tint),
+            self.base_colour,
+            self.metallic,
+        );
+        let distribution = Ggx::from_roughness(self.roughness);
+
+        let lobes = Lobes {
+            diffuse: DiffuseLobe {
+                colour: diffuse_weight * self.base_colour,
+                roughness: self.roughness,
+                sheen: diffuse_weight * self.sheen,
+                sheen_colour: lerp(white, tint, self.sheen_tint),
+            },
+            specular: SpecularLobe {
+                distribution,
+                f0,
+                scale: 1.0 - transmission_weight,
+            },
+            clearcoat: ClearcoatLobe {
+                distribution: Gtr1 {
+                    alpha: (0.1 + (DELTA_ALPHA - 0.1) * self.clearcoat_gloss).max(DELTA_ALPHA),
+                },
+                scale: 0.25 * self.clearcoat,
+            },
+            transmission: TransmissionLobe {
+                distribution,
+                eta: if front_face { self.ior } else { 1.0 / self.ior },
+                colour: self.base_colour,
+                scale: transmission_weight,
+            },
+            probabilities: [0.0; 4],
+        };
+
+        // Roughly proportional to how much energy each lobe reflects
+        let mut probabilities = [
+            diffuse_weight * (luminance + self.sheen),
+            (1.0 - transmission_weight) * f0.luminance().max(0.2),
+            0.25 * self.clearcoat,
+            transmission_weight,
+        ];
+        // Light is only ever inside a transmissive object
+        if !front_face {
+            probabilities = [0.0, 0.0, 0.0, 1.0];
+        }
+        let total: f64 = probabilities.iter().sum();
+        if total > 0.0 {
+            probabilities.iter_mut().for_each(|p| *p /= total);
+        }
+
+        Lobes {
+            probabilities,
+            ..lobes
+        }
+    }
+}
+
+impl Material for Principled {
+    fn scatter(&self, ray_in: &Ray, rec: &HitRecord) -> Option<Scatter> {
+        let frame = Frame::from_normal(rec.normal);
+        let wo = frame.to_local(-ray_in.dir.unit_vector());
+        let lobes = self.lobes(rec.front_face);
+
+        // Pick a single lobe to sample from
+        let mut u = random_double();
+        let all = lobes.all();
+        let mut chosen = all.len() - 1;
+        for (index, &probability) in lobes.probabilities.iter().enumerate() {
+            if u < probability {
+                chosen = index;
+                break;
+            }
+            u -= probability;
+        }
+        let probability = lobes.probabilities[chosen];
+        if probability <= 0.0 {
+            return None;
+        }
+
+        let sample = all[chosen].sample(wo)?;
+        let transmitted = sample.wi.z() < 0.0;
+        let ray = Ray::new(rec.p, frame.to_world(sample.wi));
+
+        if let Some(weight) = sample.weight {
+            return Some(Scatter {
+                attenuation: weight / probability,
+                ray,
+                pdf: 0.0,
+                lobe: if transmitted {
+                    Lobe::SpecularTransmission
+                } else {
+                    Lobe::Specular
+                },
+            });
+        }
+
+        // Weight by every lobe that could have produced this direction
+        let pdf = lobes.pdf(wo, sample.wi);
+        if pdf <= 0.0 {
+            return None;
+        }
+        Some(Scatter {
+            attenuation: lobes.eval(wo, sample.wi) / pdf,
+            ray,
+            pdf,
+            lobe: if transmitted {
+                Lobe::GlossyTransmission
+            } else if chosen == 0 {
+                Lobe::Diffuse
+            } else {
+                Lobe::Glossy
+            },
+        })
+    }
+
+    fn eval(&self, wo: Vec3, wi: Vec3, rec: &HitRecord) -> Vec3 {
+        let frame = Frame::from_normal(rec.normal);
+        self.lobes(rec.front_face)
+            .eval(frame.to_local(wo), frame.to_local(wi))
+    }
+
+    fn pdf(&self, wo: Vec3, wi: Vec3, rec: &HitRecord) -> f64 {
+        let frame = Frame::from_normal(rec.normal);
+        self.lobes(rec.front_face)
+            .pdf(frame.to_local(wo), frame.to_local(wi))
+    }
+
+    fn is_delta(&self) -> bool {
+        let lobes = self.lobes(true);
+        lobes
+            .all()
+            .iter()
+            .zip(&lobes.probabilities)
+            .all(|(lobe, &probability)| probability == 0.0 || lobe.is_delta())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hittable::HitRecord;
+    use crate::material::{Material, Principled};
+    use crate::renderer::{Ray, Vec3};
+
+    #[test]
+    fn test_principled_sample_matches_eval() {
+        // Sampled weights should agree with evaluating the material in the sampled direction,
+        // and a white furnace should never gain energy
+        let materials = [
+            Principled::new(Vec3::new(0.8, 0.5, 0.2), 0.0, 0.6),
+            Principled::new(Vec3::new(0.9, 0.9, 0.9), 1.0, 0.3),
+            Principled {
+                clearcoat: 1.0,
+                sheen: 1.0,
+                ..Principled::new(Vec3::new(0.2, 0.3, 0.8), 0.2, 0.4)
+            },
+            Principled {
+                transmission: 1.0,
+                ..Principled::new(Vec3::new(1.0, 1.0, 1.0), 0.0, 0.3)
+            },
+        ];
+        let rec = HitRecord {
+            normal: Vec3::new(0., 0., 1.),
+            ..Default::default()
+        };
+        let ray_in = Ray::new(Vec3::new(-1., 0., 1.), Vec3::new(1., 0., -1.));
+        let wo = -ray_in.dir.unit_vector();
+
+        for material in materials {
+            let runs = 20_000;
+            let mut albedo = 0.;
+            for _ in 0..runs {
+                let Some(scatter) = material.scatter(&ray_in, &rec) else {
+                    continue;
+                };
+                let wi = scatter.ray.dir.unit_vector();
+                let expected = material.eval(wo, wi, &rec) / material.pdf(wo, wi, &rec);
+                assert!((expected - scatter.attenuation).length() < 1e-6);
+                albedo += scatter.attenuation.luminance() / runs as f64;
+            }
+            assert!(albedo <= 1.05, "{material:?} reflected {albedo}");
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use std::fmt::Debug;
+
+use crate::hittable::HitRecord;
+use crate::renderer::{Ray, Vec3};
+
+/// The kind of scattering event a sample came from
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Lobe {
+    Diffuse,
+    Glossy,
+    /// Perfect mirror reflection
+    Specular,
+    GlossyTransmission,
+    /// Perfectly smooth refraction
+    SpecularTransmission,
+}
+
+impl Lobe {
+    /// Delta lobes can't be evaluated for an arbitrary pair of directions, only sampled
+    pub fn is_delta(&self) -> bool {
+        matches!(self, Lobe::Specular | Lobe::SpecularTransmission)
+    }
+
+    pub fn is_transmission(&self) -> bool {
+        matches!(self, Lobe::GlossyTransmission | Lobe::SpecularTransmission)
+    }
+}
+
+/// The result of a ray scattering off a surface or inside a medium
+pub struct Scatter {
+    /// The material's value times the cosine term, divided by `pdf`
+    pub attenuation: Vec3,
+    pub ray: Ray,
+    /// Solid angle density of the sampled direction, zero for delta lobes
+    pub pdf: f64,
+    pub lobe: Lobe,
+}
+
+/// Directions passed to `eval` and `pdf` are unit vectors pointing away from the hit point, `wo`
+/// back along the incoming ray and `wi` along the scattered one
+pub trait Material: Debug + Send + Sync {
+    /// Samples the scattered ray and how much it is attenuated, or None if the ray is absorbed
+    fn scatter(&self, ray_in: &Ray, rec: &HitRecord) -> Option<Scatter>;
+
+    /// Evaluates the scattering function times the cosine to the normal for `wi`, phase functions
+    /// have no cosine. Delta lobes are never included
+    fn eval(&self, _wo: Vec3, _wi: Vec3, _rec: &HitRecord) -> Vec3 {
+        Vec3::new(0.0, 0.0, 0.0)
+    }
+
+    /// The solid angle density `scatter` would choose `wi` with, ignoring delta lobes
+    fn pdf(&self, _wo: Vec3, _wi: Vec3, _rec: &HitRecord) -> f64 {
+        0.0
+    }
+
+    /// Light given off at the hit point, most materials don't emit any
+    fn emitted(&self, _rec: &HitRecord) -> Vec3 {
+        Vec3::new(0.0, 0.0, 0.0)
+    }
+
+    /// Every lobe is a delta, so nothing can be gathered or connected to at the surface
+    fn is_delta(&self) -> bool {
+        false
+    }
+
+    /// The material bends light by different amounts depending on its wavelength
+    fn is_dispersive(&self) -> bool {
+        false
+    }
+
+    /// Phase functions scatter inside a medium rather than off a surface, so there is no
+    /// cosine term to account for at their hit points
+    fn is_medium(&self) -> bool {
+        false
+    }
+}
@@ -0,0 +1,280 @@
+use std::collections::VecDeque;
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use crate::net::{Job, Message, Setup, TileResult};
+use crate::renderer::hash_str;
+
+/// How the image is cut up and handed out
+#[derive(Debug, Clone, Copy)]
+pub struct Schedule {
+    pub tile_size: usize,
+    /// Samples per pixel for the whole render
+    pub samples: u32,
+    /// Samples per pixel in each job, fewer gives a rough image sooner
+    pub samples_per_job: u32,
+    pub seed: u64,
+    /// How long a worker gets to finish a job before it's given to someone else
+    pub timeout: Duration,
+}
+
+impl Default for Schedule {
+    fn default() -> Self {
+        Self {
+            tile_size: 32,
+            samples: 64,
+            samples_per_job: 4,
+            seed: 0,
+            timeout: Duration::from_secs(120),
+        }
+    }
+}
+
+impl Schedule {
+    /// Every job of the render, a pass over the whole image at a time so it sharpens evenly
+    pub fn jobs(&self, width: usize, height: usize) -> VecDeque<Job> {
+        let mut jobs = VecDeque::new();
+        let mut done = 0;
+        while done < self.samples {
+            let samples = self.samples_per_job.min(self.samples - done).max(1);
+            for y in (0..height).step_by(self.tile_size) {
+                for x in (0..width).step_by(self.tile_size) {
+                    let id = jobs.len() as u64;
+                    jobs.push_back(Job {
+                        id,
+                        x,
+                        y,
+                        width: self.tile_size.min(width - x),
+                        height: self.tile_size.min(height - y),
+                        samples,
+                        seed: hash_str(&format!("{} {id}", self.seed)),
+                    });
+                }
+            }
+            done += samples;
+        }
+        jobs
+    }
+}
+
+struct Shared {
+    setup: Setup,
+    timeout: Duration,
+    queue: Mutex<VecDeque<Job>>,
+    /// Jobs which haven't come back yet, whether queued or being rendered
+    remaining: AtomicUsize,
+    workers: AtomicUsize,
+}
+
+/// Hands out jobs to any worker which connects and collects what they send back. A worker
+/// which disconnects or takes too long has its job put back for someone else
+pub struct Coordinator {
+    address: SocketAddr,
+    total: usize,
+    shared: Arc<Shared>,
+    results: Receiver<TileResult>,
+}
+
+impl Coordinator {
+    pub fn start(address: &str, setup: Setup, schedule: Schedule) -> Result<Self, String> {
+        let listener = TcpListener::bind(address).map_err(|e| format!("{address}: {e}"))?;
+        let address = listener.local_addr().map_err(|e| e.to_string())?;
+
+        let jobs = schedule.jobs(setup.width, setup.height);
+        let total = jobs.len();
+        let shared = Arc::new(Shared {
+            setup,
+            timeout: schedule.timeout,
+            queue: Mutex::new(jobs),
+            remaining: AtomicUsize::new(total),
+            workers: AtomicUsize::new(0),
+        });
+        let (sender, results) = channel();
+
+        let accepting = shared.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let shared = accepting.clone();
+                let sender = sender.clone();
+                thread::spawn(move || {
+                    shared.workers.fetch_add(1, Ordering::SeqCst);
+                    if let Err(e) = Self::serve(stream, &shared, &sender) {
+                        eprintln!("Lost a worker: {e}");
+                    }
+                    shared.workers.fetch_sub(1, Ordering::SeqCst);
+                });
+            }
+        });
+
+        Ok(Self {
+            address,
+            total,
+            shared,
+            results,
+        })
+    }
+
+    /// Feeds one worker jobs until there are none left
+    fn serve(
+        mut stream: TcpStream,
+        shared: &Shared,
+        results: &Sender<TileResult>,
+    ) -> Result<(), String> {
+        stream
+            .set_read_timeout(Some(shared.timeout))
+            .map_err(|e| e.to_string())?;
+        Message::Setup(shared.setup.clone()).send(&mut stream)?;
+
+        loop {
+            let job = shared.queue.lock().unwrap().pop_front();
+            let job = match job {
+                Some(job) => job,
+                None if shared.remaining.load(Ordering::SeqCst) == 0 => {
+                    return Message::Done.send(&mut stream);
+                }
+                // Someone else might still drop their job
+                None => {
+                    thread::sleep(Duration::from_millis(50));
+                    continue;
+                }
+            };
+
+            let reply = Message::Job(job)
+                .send(&mut stream)
+                .and_then(|_| Message::receive(&mut stream))
+                .and_then(|reply| match reply {
+                    Message::Result(result) => result.check(&job).map(|_| result),
+                    _ => Err("sent back the wrong thing".to_string()),
+                });
+            match reply {
+                Ok(result) => {
+                    // Nobody listening just means the render was abandoned. Counting it after
+                    // sending means a finished render has all its results waiting
+                    results.send(result).ok();
+                    shared.remaining.fetch_sub(1, Ordering::SeqCst);
+                }
+                Err(e) => {
+                    shared.queue.lock().unwrap().push_front(job);
+                    return Err(e);
+                }
+            }
+        }
+    }
+
+    /// Where workers should connect to
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// Results which have arrived since last time
+    pub fn poll(&self) -> Vec<TileResult> {
+        self.results.try_iter().collect()
+    }
+
+    /// Number of jobs finished and in total
+    pub fn progress(&self) -> (usize, usize) {
+        let remaining = self.shared.remaining.load(Ordering::SeqCst);
+        (self.total - remaining, self.total)
+    }
+
+    pub fn workers(&self) -> usize {
+        self.shared.workers.load(Ordering::SeqCst)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.shared.remaining.load(Ordering::SeqCst) == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::TcpStream;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use crate::net::{run_worker, Coordinator, Message, Schedule, Setup, TileResult};
+    use crate::renderer::SampleMap;
+
+    #[test]
+    fn test_loopback_render() {
+        let setup = Setup {
+            width: 12,
+            height: 8,
+            integrator: "normals".to_string(),
+            max_depth: 0,
+            scene: String::new(),
+        };
+        let schedule = Schedule {
+            tile_size: 5,
+            samples: 3,
+            samples_per_job: 2,
+            ..Default::default()
+        };
+        let coordinator = Coordinator::start("127.0.0.1:0", setup, schedule).unwrap();
+        let address = coordinator.address().to_string();
+
+        // A worker which takes a job and then vanishes
+        let mut quitter = TcpStream::connect(&address).unwrap();
+        assert!(matches!(
+            Message::receive(&mut quitter),
+            Ok(Message::Setup(_))
+        ));
+        assert!(matches!(
+            Message::receive(&mut quitter),
+            Ok(Message::Job(_))
+        ));
+        drop(quitter);
+
+        // And one which sends back the wrong size of tile
+        let mut liar = TcpStream::connect(&address).unwrap();
+        Message::receive(&mut liar).unwrap();
+        let job = match Message::receive(&mut liar) {
+            Ok(Message::Job(job)) => job,
+            _ => panic!("expected a job"),
+        };
+        Message::Result(TileResult {
+            job,
+            sample_map: SampleMap::new(job.samples, job.width + 1, job.height),
+            splats: Vec::new(),
+        })
+        .send(&mut liar)
+        .unwrap();
+        assert!(Message::receive(&mut liar).is_err());
+
+        let workers: Vec<_> = (0..2)
+            .map(|_| {
+                let address = address.clone();
+                thread::spawn(move || run_worker(&address))
+            })
+            .collect();
+
+        let mut sample_map = SampleMap::new(3, 12, 8);
+        let start = Instant::now();
+        loop {
+            let finished = coordinator.is_finished();
+            for result in coordinator.poll() {
+                result.merge_into(&mut sample_map).unwrap();
+            }
+            if finished {
+                break;
+            }
+            assert!(start.elapsed() < Duration::from_secs(30));
+            thread::sleep(Duration::from_millis(10));
+        }
+        for worker in workers {
+            worker.join().unwrap().unwrap();
+        }
+
+        // Every pixel got every sample exactly once, including the dropped tile's
+        for y in 0..8 {
+            for x in 0..12 {
+                assert_eq!(sample_map.get_values(x, y).1, 3);
+            }
+        }
+        assert_eq!(coordinator.progress(), (2 * 6, 2 * 6));
+    }
+}
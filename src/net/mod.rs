@@ -0,0 +1,7 @@
+mod coordinator;
+mod protocol;
+mod worker;
+
+pub use coordinator::{Coordinator, Schedule};
+pub use protocol::{Job, Message, Setup, TileResult};
+pub use worker::{render_tile, run_worker};
@@ -0,0 +1,220 @@
+use std::io::{Read, Write};
+
+use crate::renderer::{ByteReader, PixelSplat, SampleMap, Vec3};
+
+/// Messages bigger than this are assumed to be garbage rather than allocated
+const MAX_MESSAGE: u64 = 1 << 30;
+
+/// What every worker needs to know before it can render anything
+#[derive(Debug, Clone, PartialEq)]
+pub struct Setup {
+    pub width: usize,
+    pub height: usize,
+    pub integrator: String,
+    /// Zero leaves the integrator's own limit
+    pub max_depth: u32,
+    /// The scene file's text, empty for the built in scene
+    pub scene: String,
+}
+
+/// Some samples of one tile of the image
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Job {
+    pub id: u64,
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+    /// Samples for every pixel in the tile
+    pub samples: u32,
+    pub seed: u64,
+}
+
+/// A finished job, the tile's samples plus any light splatted anywhere in the image
+pub struct TileResult {
+    pub job: Job,
+    pub sample_map: SampleMap,
+    pub splats: Vec<PixelSplat>,
+}
+
+impl TileResult {
+    /// Makes sure this is what was asked for, so a confused worker can't spoil the image
+    pub fn check(&self, job: &Job) -> Result<(), String> {
+        if self.job != *job {
+            return Err(format!(
+                "sent back job {} instead of {}",
+                self.job.id, job.id
+            ));
+        }
+        let map = &self.sample_map;
+        if (map.width, map.height, map.max_samples) != (job.width, job.height, job.samples) {
+            return Err(format!(
+                "sent back {}x{} pixels with {} samples for a {}x{} tile with {}",
+                map.width, map.height, map.max_samples, job.width, job.height, job.samples
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn merge_into(&self, sample_map: &mut SampleMap) -> Result<(), String> {
+        sample_map.merge_at(&self.sample_map, self.job.x, self.job.y)?;
+        for splat in &self.splats {
+            if splat.x >= sample_map.width || splat.y >= sample_map.height {
+                return Err(format!(
+                    "splat outside the image at ({}, {})",
+                    splat.x, splat.y
+                ));
+            }
+            sample_map.add_splat(splat.x, splat.y, splat.value.into());
+        }
+        Ok(())
+    }
+}
+
+pub enum Message {
+    Setup(Setup),
+    Job(Job),
+    Result(TileResult),
+    /// Nothing left to do, the worker can go home
+    Done,
+}
+
+fn put_u64(bytes: &mut Vec<u8>, value: u64) {
+    bytes.extend_from_slice(&value.to_le_bytes());
+}
+
+fn put_string(bytes: &mut Vec<u8>, value: &str) {
+    put_u64(bytes, value.len() as u64);
+    bytes.extend_from_slice(value.as_bytes());
+}
+
+fn put_job(bytes: &mut Vec<u8>, job: &Job) {
+    for value in [
+        job.id,
+        job.x as u64,
+        job.y as u64,
+        job.width as u64,
+        job.height as u64,
+    ] {
+        put_u64(bytes, value);
+    }
+    bytes.extend_from_slice(&job.samples.to_le_bytes());
+    put_u64(bytes, job.seed);
+}
+
+fn read_job(reader: &mut ByteReader) -> Result<Job, String> {
+    Ok(Job {
+        id: reader.u64()?,
+        x: reader.u64()? as usize,
+        y: reader.u64()? as usize,
+        width: reader.u64()? as usize,
+        height: reader.u64()? as usize,
+        samples: reader.u32()?,
+        seed: reader.u64()?,
+    })
+}
+
+impl Message {
+    fn tag(&self) -> u8 {
+        match self {
+            Message::Setup(_) => 0,
+            Message::Job(_) => 1,
+            Message::Result(_) => 2,
+            Message::Done => 3,
+        }
+    }
+
+    fn payload(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        match self {
+            Message::Setup(setup) => {
+                put_u64(&mut bytes, setup.width as u64);
+                put_u64(&mut bytes, setup.height as u64);
+                put_string(&mut bytes, &setup.integrator);
+                bytes.extend_from_slice(&setup.max_depth.to_le_bytes());
+                put_string(&mut bytes, &setup.scene);
+            }
+            Message::Job(job) => put_job(&mut bytes, job),
+            Message::Result(result) => {
+                put_job(&mut bytes, &result.job);
+                result.sample_map.write_bytes(&mut bytes);
+                put_u64(&mut bytes, result.splats.len() as u64);
+                for splat in &result.splats {
+                    put_u64(&mut bytes, splat.x as u64);
+                    put_u64(&mut bytes, splat.y as u64);
+                    for value in [splat.value.x(), splat.value.y(), splat.value.z()] {
+                        bytes.extend_from_slice(&value.to_le_bytes());
+                    }
+                }
+            }
+            Message::Done => {}
+        }
+        bytes
+    }
+
+    fn from_payload(tag: u8, payload: &[u8]) -> Result<Self, String> {
+        let mut reader = ByteReader::new(payload);
+        Ok(match tag {
+            0 => Message::Setup(Setup {
+                width: reader.u64()? as usize,
+                height: reader.u64()? as usize,
+                integrator: reader.string()?,
+                max_depth: reader.u32()?,
+                scene: reader.string()?,
+            }),
+            1 => Message::Job(read_job(&mut reader)?),
+            2 => {
+                let job = read_job(&mut reader)?;
+                let sample_map = SampleMap::read_bytes(&mut reader)?;
+                let count = reader.u64()?;
+                // Each splat is two positions and a colour
+                if count.saturating_mul(5 * 8) > reader.remaining() as u64 {
+                    return Err(format!("{count} splats don't fit in what's left"));
+                }
+                let mut splats = Vec::new();
+                for _ in 0..count {
+                    splats.push(PixelSplat {
+                        x: reader.u64()? as usize,
+                        y: reader.u64()? as usize,
+                        value: Vec3::new(reader.f64()?, reader.f64()?, reader.f64()?),
+                    });
+                }
+                Message::Result(TileResult {
+                    job,
+                    sample_map,
+                    splats,
+                })
+            }
+            3 => Message::Done,
+            _ => return Err(format!("unknown message type {tag}")),
+        })
+    }
+
+    /// Writes the message as its type, payload length and payload
+    pub fn send(&self, stream: &mut impl Write) -> Result<(), String> {
+        let payload = self.payload();
+        let mut bytes = vec![self.tag()];
+        put_u64(&mut bytes, payload.len() as u64);
+        bytes.extend_from_slice(&payload);
+        stream.write_all(&bytes).map_err(|e| e.to_string())
+    }
+
+    pub fn receive(stream: &mut impl Read) -> Result<Self, String> {
+        let mut header = [0; 9];
+        stream.read_exact(&mut header).map_err(|e| e.to_string())?;
+        let len = u64::from_le_bytes(header[1..].try_into().unwrap());
+        if len > MAX_MESSAGE {
+            return Err(format!("message of {len} bytes is too big"));
+        }
+        // Only grows as the bytes actually arrive, rather than trusting the length up front
+        let mut payload = Vec::new();
+        stream
+            .take(len)
+            .read_to_end(&mut payload)
+            .map_err(|e| e.to_string())?;
+        if payload.len() as u64 != len {
+            return Err("connection closed part way through a message".to_string());
+        }
+        Self::from_payload(header[0], &payload)
+    }
+}
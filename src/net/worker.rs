@@ -0,0 +1,80 @@
+use std::net::TcpStream;
+
+use crate::integrator::{Integrator, IntegratorKind};
+use crate::net::{Job, Message, Setup, TileResult};
+use crate::renderer::{seed_random, trace_pixel, F64x4, SampleMap, Scene, SceneFile};
+
+/// Renders every sample of one job
+pub fn render_tile(
+    scene: &Scene,
+    integrator: &dyn Integrator,
+    (width, height): (usize, usize),
+    job: Job,
+) -> TileResult {
+    seed_random(job.seed);
+    let mut sample_map = SampleMap::new(job.samples, job.width, job.height);
+    let mut splats = Vec::new();
+
+    for _ in 0..job.samples {
+        for j in 0..job.height {
+            for i in 0..job.width {
+                let (colour, aov) = trace_pixel(
+                    scene,
+                    integrator,
+                    (job.x + i, job.y + j),
+                    (width, height),
+                    &mut splats,
+                );
+                sample_map.add_aov(i, j, aov);
+                sample_map.set_value(i, j, F64x4::from(colour));
+                sample_map.add_light_path();
+            }
+        }
+    }
+
+    TileResult {
+        job,
+        sample_map,
+        splats,
+    }
+}
+
+/// Connects to a coordinator and renders whatever it asks for until it says it's done
+pub fn run_worker(address: &str) -> Result<(), String> {
+    let mut stream = TcpStream::connect(address).map_err(|e| format!("{address}: {e}"))?;
+    let setup: Setup = match Message::receive(&mut stream)? {
+        Message::Setup(setup) => setup,
+        _ => return Err("expected the render setup first".to_string()),
+    };
+
+    let mut scene = if setup.scene.is_empty() {
+        Scene::builtin()
+    } else {
+        SceneFile::parse(&setup.scene)?.build()?
+    };
+    scene.camera = scene
+        .camera
+        .with_aspect(setup.width as f64 / setup.height as f64);
+    let mut integrator = setup.integrator.parse::<IntegratorKind>()?.create();
+    if setup.max_depth > 0 {
+        integrator.set_max_depth(setup.max_depth);
+    }
+
+    loop {
+        match Message::receive(&mut stream)? {
+            Message::Job(job) => {
+                // Each job is a pass of its own
+                integrator.prepare(&scene);
+                let result = render_tile(
+                    &scene,
+                    integrator.as_ref(),
+                    (setup.width, setup.height),
+                    job,
+                );
+                Message::Result(result).send(&mut stream)?;
+            }
+            Message::Done => return Ok(()),
+            _ => return Err("unexpected message from the coordinator".to_string()),
+        }
+    }
+}
@@ -0,0 +1,97 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+use crate::integrator::Integrator;
+use crate::net::{render_tile, Schedule};
+use crate::renderer::{SampleMap, Scene};
+
+/// Renders the whole image without a window, with `threads` threads taking tiles in turn.
+/// The integrator is prepared before each pass, which is every sample for progressive ones
+/// and all of them at once otherwise. `progress` is told how many tiles are done out of how
+/// many after each one
+pub fn render_image(
+    scene: &Scene,
+    integrator: &mut dyn Integrator,
+    (width, height): (usize, usize),
+    samples: u32,
+    seed: u64,
+    threads: usize,
+    progress: impl Fn(usize, usize) + Sync,
+) -> SampleMap {
+    let schedule = Schedule {
+        tile_size: 16,
+        samples,
+        samples_per_job: if integrator.kind().is_progressive() {
+            1
+        } else {
+            samples
+        },
+        seed,
+        ..Default::default()
+    };
+    let mut jobs = schedule.jobs(width, height);
+    let total = jobs.len();
+    // Jobs come a pass at a time, each one covering the image once
+    let per_pass = width.div_ceil(schedule.tile_size) * height.div_ceil(schedule.tile_size);
+    let finished = AtomicUsize::new(0);
+    let sample_map = Mutex::new(SampleMap::new(samples, width, height));
+
+    while !jobs.is_empty() {
+        integrator.prepare(scene);
+        let integrator = &*integrator;
+        let pass = Mutex::new(
+            jobs.drain(..per_pass.min(jobs.len()))
+                .collect::<VecDeque<_>>(),
+        );
+        thread::scope(|s| {
+            for _ in 0..threads {
+                s.spawn(|| loop {
+                    let Some(job) = pass.lock().unwrap().pop_front() else {
+                        break;
+                    };
+                    let result = render_tile(scene, integrator, (width, height), job);
+                    result
+                        .merge_into(&mut sample_map.lock().unwrap())
+                        .expect("Tile doesn't fit in the image");
+                    progress(finished.fetch_add(1, Ordering::SeqCst) + 1, total);
+                });
+            }
+        });
+    }
+    sample_map.into_inner().unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::integrator::IntegratorKind;
+    use crate::renderer::{render_image, Scene};
+
+    #[test]
+    fn test_threads_match() {
+        let scene = Scene::builtin();
+        let mut integrator = IntegratorKind::Path.create();
+        let mut render = |threads| {
+            render_image(
+                &scene,
+                integrator.as_mut(),
+                (20, 12),
+                2,
+                3,
+                threads,
+                |_, _| {},
+            )
+        };
+
+        // Every tile is seeded on its own, so the thread count doesn't change the image
+        let one = render(1);
+        let four = render(4);
+        for y in 0..12 {
+            for x in 0..20 {
+                assert_eq!(one.get_values(x, y).1, 2);
+                assert_eq!(one.get_colour(x, y), four.get_colour(x, y));
+            }
+        }
+    }
+}
@@ -1,6 +1,17 @@
 use crate::renderer::{Ray, Vec3};
 
 use Vec3 as Point3;
+
+/// A point seen by the camera, found when connecting light paths to the lens
+pub struct CameraSample {
+    /// Image plane position, matching the arguments to `get_ray`
+    pub u: f64,
+    pub v: f64,
+    pub importance: f64,
+    /// Density of picking the lens point, as a solid angle from the point being seen
+    pub pdf: f64,
+}
+
 pub struct Camera {
     pub origin: Point3,
     pub lower_left: Point3,
@@ -10,7 +21,11 @@ pub struct Camera {
 
 impl Camera {
     pub fn new() -> Self {
-        let aspect_ratio = 16. / 9.;
+        Self::with_aspect_ratio(16. / 9.)
+    }
+
+    /// A camera for an image `aspect_ratio` times wider than it is tall
+    pub fn with_aspect_ratio(aspect_ratio: f64) -> Self {
         let viewport_height = 2.0;
         let viewport_width = aspect_ratio * viewport_height;
         let focal_length = 1.0;
@@ -28,11 +43,120 @@ impl Camera {
         }
     }
 
+    /// A camera at `origin` looking towards `look_at`, with `vfov` degrees of view from the
+    /// bottom of the image to the top
+    pub fn look_at(
+        origin: Point3,
+        look_at: Point3,
+        up: Vec3,
+        vfov: f64,
+        aspect_ratio: f64,
+    ) -> Self {
+        let viewport_height = 2.0 * (vfov.to_radians() / 2.0).tan();
+        let viewport_width = aspect_ratio * viewport_height;
+
+        let w = (origin - look_at).unit_vector();
+        let u = up.cross(&w).unit_vector();
+        let v = w.cross(&u);
+
+        let horizontal = viewport_width * u;
+        let vertical = viewport_height * v;
+        Self {
+            origin,
+            lower_left: origin - horizontal / 2.0 - vertical / 2.0 - w,
+            horizontal,
+            vertical,
+        }
+    }
+
+    /// The same view for an image of another shape, keeping the height of the view
+    pub fn with_aspect(&self, aspect_ratio: f64) -> Self {
+        let centre = self.lower_left + self.horizontal / 2.0 + self.vertical / 2.0;
+        let horizontal = self.horizontal.unit_vector() * (self.vertical.length() * aspect_ratio);
+        Self {
+            origin: self.origin,
+            lower_left: centre - horizontal / 2.0 - self.vertical / 2.0,
+            horizontal,
+            vertical: self.vertical,
+        }
+    }
+
     pub fn get_ray(&self, u: f64, v: f64) -> Ray {
-        Ray {
-            orig: self.origin,
-            dir: (self.lower_left + u * self.horizontal + v * self.vertical) - self.origin,
+        Ray::new(
+            self.origin,
+            (self.lower_left + u * self.horizontal + v * self.vertical) - self.origin,
+        )
+    }
+
+    /// Unit vector through the centre of the image and the distance to the image plane
+    fn forward(&self) -> (Vec3, f64) {
+        let centre = self.lower_left + self.horizontal / 2.0 + self.vertical / 2.0;
+        let to_centre = centre - self.origin;
+        let distance = to_centre.length();
+        (to_centre / distance, distance)
+    }
+
+    pub fn direction(&self) -> Vec3 {
+        self.forward().0
+    }
+
+    /// Area of the image if the image plane was moved to be one unit away
+    fn image_area(&self) -> f64 {
+        let (_, distance) = self.forward();
+        self.horizontal.length() * self.vertical.length() / (distance * distance)
+    }
+
+    /// Where `p` lands on the image, if it's in view
+    pub fn project(&self, p: Point3) -> Option<(f64, f64)> {
+        let (forward, distance) = self.forward();
+        let dir = p - self.origin;
+        let cos_theta = dir.dot(&forward);
+        if cos_theta <= 0.0 {
+            return None;
+        }
+
+        let on_plane = self.origin + (distance / cos_theta) * dir - self.lower_left;
+        let u = on_plane.dot(&self.horizontal) / self.horizontal.length_squared();
+        let v = on_plane.dot(&self.vertical) / self.vertical.length_squared();
+        if (0.0..=1.0).contains(&u) && (0.0..=1.0).contains(&v) {
+            Some((u, v))
+        } else {
+            None
+        }
+    }
+
+    /// Importance emitted by the pinhole along `dir`, normalised so it integrates to one
+    /// over the image
+    pub fn importance(&self, dir: Vec3) -> f64 {
+        if self.project(self.origin + dir).is_none() {
+            return 0.0;
+        }
+        let cos_theta = dir.unit_vector().dot(&self.direction());
+        let cos2_theta = cos_theta * cos_theta;
+        1.0 / (self.image_area() * cos2_theta * cos2_theta)
+    }
+
+    /// Solid angle density of `get_ray` with uniform image positions producing `dir`
+    pub fn pdf_dir(&self, dir: Vec3) -> f64 {
+        if self.project(self.origin + dir).is_none() {
+            return 0.0;
         }
+        let cos_theta = dir.unit_vector().dot(&self.direction());
+        1.0 / (self.image_area() * cos_theta * cos_theta * cos_theta)
+    }
+
+    /// Connects `p` to the pinhole
+    pub fn sample_wi(&self, p: Point3) -> Option<CameraSample> {
+        let (u, v) = self.project(p)?;
+        let dir = p - self.origin;
+        let cos_theta = dir.unit_vector().dot(&self.direction());
+
+        Some(CameraSample {
+            u,
+            v,
+            importance: self.importance(dir),
+            pdf: dir.length_squared() / cos_theta,
+        })
     }
 
     pub fn translate_x(&mut self, by: f64) {
@@ -50,3 +174,57 @@ impl Camera {
             origin - self.horizontal / 2.0 - self.vertical / 2.0 - Vec3::new(0.0, 0.0, 1.0);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::renderer::{Camera, Vec3};
+
+    #[test]
+    fn test_camera_project() {
+        let camera = Camera::new();
+        let ray = camera.get_ray(0.25, 0.75);
+        let (u, v) = camera.project(ray.at(3.0)).unwrap();
+        assert!((u - 0.25).abs() < 1e-9);
+        assert!((v - 0.75).abs() < 1e-9);
+
+        assert!(camera.project(Vec3::new(0.0, 0.0, 1.0)).is_none());
+        assert_eq!(camera.importance(Vec3::new(0.0, 0.0, 1.0)), 0.0);
+    }
+
+    #[test]
+    fn test_camera_look_at() {
+        // The default camera is a 90 degree view down -z
+        let default = Camera::with_aspect_ratio(2.0);
+        let camera = Camera::look_at(
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(0.0, 0.0, -5.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            90.0,
+            2.0,
+        );
+        for (a, b) in [
+            (camera.lower_left, default.lower_left),
+            (camera.horizontal, default.horizontal),
+            (camera.vertical, default.vertical),
+        ] {
+            assert!((a - b).length() < 1e-9);
+        }
+
+        // Looking along +x from above, the centre of the image is what it was pointed at
+        let camera = Camera::look_at(
+            Vec3::new(0.0, 2.0, 0.0),
+            Vec3::new(3.0, 2.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            40.0,
+            1.0,
+        );
+        let (u, v) = camera.project(Vec3::new(3.0, 2.0, 0.0)).unwrap();
+        assert!((u - 0.5).abs() < 1e-9 && (v - 0.5).abs() < 1e-9);
+
+        // Changing the aspect ratio keeps where it looks and how tall the view is
+        let wide = camera.with_aspect(2.0);
+        assert_eq!(wide.origin, camera.origin);
+        assert!((wide.direction() - camera.direction()).length() < 1e-9);
+        assert!((wide.horizontal.length() - 2.0 * camera.vertical.length()).abs() < 1e-9);
+    }
+}
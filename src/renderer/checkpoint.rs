@@ -0,0 +1,175 @@
+use std::fs;
+
+use crate::integrator::IntegratorKind;
+use crate::renderer::SampleMap;
+
+const MAGIC: &[u8; 4] = b"PTCK";
+const VERSION: u32 = 2;
+
+/// FNV-1a, unlike the standard library's hasher it won't change between builds
+pub fn hash_str(text: &str) -> u64 {
+    text.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Identifies the scene and every setting which changes what the samples mean, so only
+/// renders of the same thing are resumed or merged
+pub fn settings_hash(
+    scene_hash: u64,
+    (width, height): (usize, usize),
+    samples: u32,
+    integrator: IntegratorKind,
+    max_depth: Option<u32>,
+) -> u64 {
+    hash_str(&format!(
+        "{scene_hash} {width}x{height} {samples} {integrator} {max_depth:?}"
+    ))
+}
+
+/// Pulls little endian values off the front of a byte slice
+pub struct ByteReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> ByteReader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes }
+    }
+
+    pub fn take<const N: usize>(&mut self) -> Result<[u8; N], String> {
+        if self.bytes.len() < N {
+            return Err("file is truncated".to_string());
+        }
+        let (head, rest) = self.bytes.split_at(N);
+        self.bytes = rest;
+        Ok(head.try_into().unwrap())
+    }
+
+    /// Bytes not read yet
+    pub fn remaining(&self) -> usize {
+        self.bytes.len()
+    }
+
+    pub fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_le_bytes(self.take()?))
+    }
+
+    pub fn u64(&mut self) -> Result<u64, String> {
+        Ok(u64::from_le_bytes(self.take()?))
+    }
+
+    pub fn f64(&mut self) -> Result<f64, String> {
+        Ok(f64::from_le_bytes(self.take()?))
+    }
+
+    /// A string written as its length followed by its UTF-8 bytes
+    pub fn string(&mut self) -> Result<String, String> {
+        let len = self.u64()? as usize;
+        if self.bytes.len() < len {
+            return Err("file is truncated".to_string());
+        }
+        let (head, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        String::from_utf8(head.to_vec()).map_err(|e| e.to_string())
+    }
+}
+
+/// Everything needed to carry on a progressive render after the process has gone away
+pub struct Checkpoint {
+    /// Hash of the scene and render settings the samples belong to
+    pub hash: u64,
+    /// Where the random sequence had got to
+    pub random_state: u64,
+    pub sample_map: SampleMap,
+}
+
+impl Checkpoint {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&VERSION.to_le_bytes());
+        bytes.extend_from_slice(&self.hash.to_le_bytes());
+        bytes.extend_from_slice(&self.random_state.to_le_bytes());
+        self.sample_map.write_bytes(&mut bytes);
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let mut reader = ByteReader::new(bytes);
+        if &reader.take::<4>()? != MAGIC {
+            return Err("not a checkpoint file".to_string());
+        }
+        let version = reader.u32()?;
+        if version != VERSION {
+            return Err(format!("unsupported checkpoint version {version}"));
+        }
+        Ok(Self {
+            hash: reader.u64()?,
+            random_state: reader.u64()?,
+            sample_map: SampleMap::read_bytes(&mut reader)?,
+        })
+    }
+
+    /// Writes next to the file first so a crash while saving can't lose the last checkpoint
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let temp = format!("{path}.tmp");
+        fs::write(&temp, self.to_bytes()).map_err(|e| format!("{temp}: {e}"))?;
+        fs::rename(&temp, path).map_err(|e| format!("{path}: {e}"))
+    }
+
+    pub fn load(path: &str) -> Result<Self, String> {
+        let bytes = fs::read(path).map_err(|e| format!("{path}: {e}"))?;
+        Self::from_bytes(&bytes).map_err(|e| format!("{path}: {e}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::renderer::{hash_str, Checkpoint, F64x4, SampleMap};
+
+    #[test]
+    fn test_checkpoint_round_trip() {
+        let mut sample_map = SampleMap::new(4, 3, 2);
+        sample_map.add_aov(1, 1, F64x4::from_array([0.0, 1.0, 0.0, 2.5]));
+        sample_map.set_value(1, 1, F64x4::from_array([0.25, 0.5, 0.75, 0.0]));
+        sample_map.add_splat(2, 0, F64x4::splat(1.0));
+        sample_map.add_light_path();
+
+        let checkpoint = Checkpoint {
+            hash: hash_str("scene"),
+            random_state: 42,
+            sample_map,
+        };
+        let loaded = Checkpoint::from_bytes(&checkpoint.to_bytes()).unwrap();
+        assert_eq!(loaded.hash, checkpoint.hash);
+        assert_eq!(loaded.random_state, 42);
+        assert_eq!(
+            loaded.sample_map.get_values(1, 1),
+            (F64x4::from_array([0.25, 0.5, 0.75, 0.0]), 1)
+        );
+        assert_eq!(loaded.sample_map.get_aov(1, 1)[3], 2.5);
+        assert_eq!(
+            loaded.sample_map.get_colour(2, 0),
+            checkpoint.sample_map.get_colour(2, 0)
+        );
+
+        assert_ne!(hash_str("scene"), hash_str("scene "));
+        assert!(Checkpoint::from_bytes(&checkpoint.to_bytes()[..20]).is_err());
+        assert!(Checkpoint::from_bytes(b"nonsense").is_err());
+
+        // A bad header mustn't allocate for an image that isn't there
+        let bytes = checkpoint.to_bytes();
+        let truncated = &bytes[..bytes.len() - 1];
+        assert!(Checkpoint::from_bytes(truncated)
+            .err()
+            .unwrap()
+            .contains("doesn't fit"));
+        let mut huge = bytes.clone();
+        // Width follows the magic, version, hash and random state
+        huge[24..32].copy_from_slice(&(u64::MAX / 2).to_le_bytes());
+        assert!(Checkpoint::from_bytes(&huge).is_err());
+        huge[24..32].copy_from_slice(&(1u64 << 40).to_le_bytes());
+        assert!(Checkpoint::from_bytes(&huge).is_err());
+    }
+}
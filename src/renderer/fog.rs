@@ -0,0 +1,31 @@
+use std::sync::Arc;
+
+use crate::hittable::{ConstantMedium, HitRecord, Hittable, Sphere};
+use crate::material::Material;
+use crate::renderer::{Ray, Vec3};
+
+/// Homogeneous fog filling the whole scene, out to `extent` from the world origin so the sky
+/// can still light it. The medium is built up front, so make a new `Fog` to change its density
+/// or extent
+pub struct Fog {
+    medium: ConstantMedium,
+}
+
+impl Fog {
+    pub fn new(density: f64, phase_function: Arc<dyn Material>, extent: f64) -> Self {
+        let boundary = Box::new(Sphere::new(Vec3::new(0.0, 0.0, 0.0), extent));
+        Self {
+            medium: ConstantMedium::new(boundary, density, phase_function),
+        }
+    }
+
+    /// Samples a scattering event along the ray before `t_max`, which should be the nearest
+    /// surface hit (or infinity if there is none)
+    pub fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        self.medium.hit(ray, t_min, t_max, rec)
+    }
+
+    pub fn transmittance(&self, ray: &Ray, t_min: f64, t_max: f64) -> f64 {
+        self.medium.transmittance(ray, t_min, t_max)
+    }
+}
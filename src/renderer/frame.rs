@@ -0,0 +1,25 @@
+use crate::renderer::Vec3;
+
+/// An orthonormal frame, local coordinates have the normal (or axis) along z
+#[derive(Copy, Clone, Debug)]
+pub struct Frame {
+    pub s: Vec3,
+    pub t: Vec3,
+    pub n: Vec3,
+}
+
+impl Frame {
+    pub fn from_normal(n: Vec3) -> Self {
+        let n = n.unit_vector();
+        let (s, t) = n.orthonormal_basis();
+        Self { s, t, n }
+    }
+
+    pub fn to_local(&self, v: Vec3) -> Vec3 {
+        Vec3::new(v.dot(&self.s), v.dot(&self.t), v.dot(&self.n))
+    }
+
+    pub fn to_world(&self, v: Vec3) -> Vec3 {
+        v.x() * self.s + v.y() * self.t + v.z() * self.n
+    }
+}
@@ -0,0 +1,122 @@
+use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::renderer::{F64x4, SampleMap};
+
+/// 8 bit binary PPM, clamped the same way as the window
+pub fn write_ppm(path: &str, sample_map: &SampleMap) -> Result<(), String> {
+    let mut bytes = format!("P6\n{} {}\n255\n", sample_map.width, sample_map.height).into_bytes();
+    for y in 0..sample_map.height {
+        for x in 0..sample_map.width {
+            let colour = sample_map.get_colour(x, y).clamp(0., 0.999) * F64x4::splat(255.);
+            bytes.extend_from_slice(&colour.cast_u8()[..3]);
+        }
+    }
+    fs::write(path, bytes).map_err(|e| format!("{path}: {e}"))
+}
+
+/// Unclamped floating point PFM, which stores its rows from the bottom up
+pub fn write_pfm(path: &str, sample_map: &SampleMap) -> Result<(), String> {
+    let mut bytes = format!("PF\n{} {}\n-1.0\n", sample_map.width, sample_map.height).into_bytes();
+    for y in (0..sample_map.height).rev() {
+        for x in 0..sample_map.width {
+            for value in &sample_map.get_colour(x, y).to_array()[..3] {
+                bytes.extend_from_slice(&(*value as f32).to_le_bytes());
+            }
+        }
+    }
+    fs::write(path, bytes).map_err(|e| format!("{path}: {e}"))
+}
+
+/// Picks the format from the file's extension
+pub fn write_image(path: &str, sample_map: &SampleMap) -> Result<(), String> {
+    match Path::new(path).extension().and_then(|e| e.to_str()) {
+        Some("ppm") => write_ppm(path, sample_map),
+        Some("pfm") => write_pfm(path, sample_map),
+        _ => Err(format!("{path}: images can be .ppm or .pfm")),
+    }
+}
+
+/// Writes both `<stem>.ppm` to look at and `<stem>.pfm` with the full range
+pub fn write_snapshot(stem: &str, sample_map: &SampleMap) -> Result<(), String> {
+    write_ppm(&format!("{stem}.ppm"), sample_map)?;
+    write_pfm(&format!("{stem}.pfm"), sample_map)
+}
+
+/// UTC date and time as `YYYYMMDD-HHMMSS`, which sorts properly in a file name
+pub fn timestamp(time: SystemTime) -> String {
+    let seconds = time.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    let (days, seconds) = (seconds / 86400, seconds % 86400);
+
+    // Days to a civil date, from Howard Hinnant's date algorithms
+    let z = days as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + (month <= 2) as i64;
+
+    format!(
+        "{year:04}{month:02}{day:02}-{:02}{:02}{:02}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use crate::renderer::{timestamp, write_image, write_snapshot, F64x4, SampleMap};
+
+    #[test]
+    fn test_merge_and_write() {
+        let mut first = SampleMap::new(1, 2, 2);
+        first.set_value(0, 0, F64x4::from_array([0.2, 0.2, 0.2, 0.0]));
+        let mut second = SampleMap::new(1, 2, 2);
+        second.set_value(0, 0, F64x4::from_array([0.6, 0.6, 0.6, 0.0]));
+        second.set_value(1, 1, F64x4::from_array([1.0, 0.5, 0.0, 0.0]));
+
+        first.merge(&second).unwrap();
+        assert_eq!(first.get_values(0, 0).1, 2);
+        assert!((first.get_colour(0, 0)[0] - 0.4).abs() < 1e-12);
+        assert_eq!(first.get_colour(1, 1)[1], 0.5);
+        assert!(first.merge(&SampleMap::new(1, 3, 2)).is_err());
+
+        let dir = std::env::temp_dir();
+        let ppm = dir.join("merge_test.ppm").to_str().unwrap().to_string();
+        write_image(&ppm, &first).unwrap();
+        let bytes = std::fs::read(&ppm).unwrap();
+        assert!(bytes.starts_with(b"P6\n2 2\n255\n"));
+        assert_eq!(bytes.len(), 11 + 2 * 2 * 3);
+
+        let pfm = dir.join("merge_test.pfm").to_str().unwrap().to_string();
+        write_image(&pfm, &first).unwrap();
+        let bytes = std::fs::read(&pfm).unwrap();
+        // The bottom row comes first
+        let red = f32::from_le_bytes(bytes[12..16].try_into().unwrap());
+        assert_eq!(red, 0.0);
+        let red = f32::from_le_bytes(bytes[24..28].try_into().unwrap());
+        assert_eq!(red, 1.0);
+        assert!(write_image("image.png", &first).is_err());
+
+        let stem = dir.join("snapshot_test").to_str().unwrap().to_string();
+        write_snapshot(&stem, &first).unwrap();
+        assert_eq!(std::fs::read(format!("{stem}.pfm")).unwrap(), bytes);
+        assert!(std::fs::metadata(format!("{stem}.ppm")).is_ok());
+    }
+
+    #[test]
+    fn test_timestamp() {
+        assert_eq!(timestamp(UNIX_EPOCH), "19700101-000000");
+        let leap_day = UNIX_EPOCH + Duration::from_secs(951_827_696);
+        assert_eq!(timestamp(leap_day), "20000229-123456");
+        let new_year = UNIX_EPOCH + Duration::from_secs(1_798_761_599);
+        assert_eq!(timestamp(new_year), "20261231-235959");
+    }
+}
@@ -0,0 +1,109 @@
+/// Where the image sits in the window, zoomed and panned independently of the camera
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ImageView {
+    /// 1 fits the image to the window
+    pub zoom: f64,
+    /// Point of the image, in pixels, at the middle of the window
+    pub centre: (f64, f64),
+}
+
+impl ImageView {
+    pub const MIN_ZOOM: f64 = 0.25;
+    pub const MAX_ZOOM: f64 = 256.0;
+
+    /// The whole image filling the window
+    pub fn fit(image: (u32, u32)) -> Self {
+        Self {
+            zoom: 1.0,
+            centre: (image.0 as f64 / 2.0, image.1 as f64 / 2.0),
+        }
+    }
+
+    /// Window pixels per image pixel along each axis
+    fn scale(&self, image: (u32, u32), window: (u32, u32)) -> (f64, f64) {
+        (
+            self.zoom * window.0 as f64 / image.0 as f64,
+            self.zoom * window.1 as f64 / image.1 as f64,
+        )
+    }
+
+    pub fn image_to_window(
+        &self,
+        (x, y): (f64, f64),
+        image: (u32, u32),
+        window: (u32, u32),
+    ) -> (f64, f64) {
+        let (sx, sy) = self.scale(image, window);
+        (
+            (x - self.centre.0) * sx + window.0 as f64 / 2.0,
+            (y - self.centre.1) * sy + window.1 as f64 / 2.0,
+        )
+    }
+
+    pub fn window_to_image(
+        &self,
+        (x, y): (f64, f64),
+        image: (u32, u32),
+        window: (u32, u32),
+    ) -> (f64, f64) {
+        let (sx, sy) = self.scale(image, window);
+        (
+            (x - window.0 as f64 / 2.0) / sx + self.centre.0,
+            (y - window.1 as f64 / 2.0) / sy + self.centre.1,
+        )
+    }
+
+    /// Zooms by `factor`, keeping the image still under the window point `at`
+    pub fn zoom_at(&mut self, factor: f64, at: (f64, f64), image: (u32, u32), window: (u32, u32)) {
+        let before = self.window_to_image(at, image, window);
+        self.zoom = (self.zoom * factor).clamp(Self::MIN_ZOOM, Self::MAX_ZOOM);
+        let after = self.window_to_image(at, image, window);
+        self.centre.0 += before.0 - after.0;
+        self.centre.1 += before.1 - after.1;
+    }
+
+    /// Drags the image by some window pixels
+    pub fn pan(&mut self, (dx, dy): (f64, f64), image: (u32, u32), window: (u32, u32)) {
+        let (sx, sy) = self.scale(image, window);
+        self.centre.0 -= dx / sx;
+        self.centre.1 -= dy / sy;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::renderer::ImageView;
+
+    #[test]
+    fn test_zoom_and_pan() {
+        let image = (100, 50);
+        let window = (200, 100);
+        let mut view = ImageView::fit(image);
+        assert_eq!(view.image_to_window((0.0, 0.0), image, window), (0.0, 0.0));
+        assert_eq!(
+            view.window_to_image((200.0, 100.0), image, window),
+            (100.0, 50.0)
+        );
+
+        // The pixel under the cursor stays put
+        view.zoom_at(4.0, (50.0, 20.0), image, window);
+        assert_eq!(view.zoom, 4.0);
+        assert_eq!(
+            view.window_to_image((50.0, 20.0), image, window),
+            (25.0, 10.0)
+        );
+        assert_eq!(
+            view.image_to_window((26.0, 10.0), image, window),
+            (58.0, 20.0)
+        );
+
+        view.pan((8.0, -8.0), image, window);
+        assert_eq!(
+            view.window_to_image((58.0, 12.0), image, window),
+            (25.0, 10.0)
+        );
+
+        view.zoom_at(1e6, (0.0, 0.0), image, window);
+        assert_eq!(view.zoom, ImageView::MAX_ZOOM);
+    }
+}
@@ -0,0 +1,210 @@
+//! Four f64 lanes, the storage behind `Vec3` and the sample buffers. With the `simd` feature
+//! they're portable SIMD, which needs nightly, otherwise plain arrays which build on stable.
+//! Both have the same methods so nothing else has to care which one it got
+
+use std::ops::{Add, AddAssign, Div, Index, IndexMut, Mul, MulAssign, Neg, Sub};
+
+#[cfg(feature = "simd")]
+use std::simd::prelude::*;
+
+#[cfg(feature = "simd")]
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+pub struct F64x4(Simd<f64, 4>);
+
+#[cfg(not(feature = "simd"))]
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+pub struct F64x4([f64; 4]);
+
+#[cfg(feature = "simd")]
+impl F64x4 {
+    pub fn from_array(lanes: [f64; 4]) -> Self {
+        Self(Simd::from_array(lanes))
+    }
+
+    pub fn splat(value: f64) -> Self {
+        Self(Simd::splat(value))
+    }
+
+    pub fn to_array(self) -> [f64; 4] {
+        self.0.to_array()
+    }
+
+    pub fn reduce_sum(self) -> f64 {
+        self.0.reduce_sum()
+    }
+
+    pub fn clamp(self, min: f64, max: f64) -> Self {
+        Self(self.0.simd_clamp(Simd::splat(min), Simd::splat(max)))
+    }
+
+    /// Truncates each lane to a byte, saturating like `as u8`
+    pub fn cast_u8(self) -> [u8; 4] {
+        self.0.cast::<u8>().to_array()
+    }
+}
+
+#[cfg(not(feature = "simd"))]
+impl F64x4 {
+    pub fn from_array(lanes: [f64; 4]) -> Self {
+        Self(lanes)
+    }
+
+    pub fn splat(value: f64) -> Self {
+        Self([value; 4])
+    }
+
+    pub fn to_array(self) -> [f64; 4] {
+        self.0
+    }
+
+    pub fn reduce_sum(self) -> f64 {
+        self.0.iter().sum()
+    }
+
+    pub fn clamp(self, min: f64, max: f64) -> Self {
+        Self(self.0.map(|lane| lane.max(min).min(max)))
+    }
+
+    /// Truncates each lane to a byte, saturating like `as u8`
+    pub fn cast_u8(self) -> [u8; 4] {
+        self.0.map(|lane| lane as u8)
+    }
+
+    fn zip(self, other: Self, f: impl Fn(f64, f64) -> f64) -> Self {
+        Self(std::array::from_fn(|i| f(self.0[i], other.0[i])))
+    }
+}
+
+#[cfg(feature = "simd")]
+impl Add for F64x4 {
+    type Output = F64x4;
+    fn add(self, rhs: Self) -> Self::Output {
+        Self(self.0 + rhs.0)
+    }
+}
+
+#[cfg(not(feature = "simd"))]
+impl Add for F64x4 {
+    type Output = F64x4;
+    fn add(self, rhs: Self) -> Self::Output {
+        self.zip(rhs, |a, b| a + b)
+    }
+}
+
+#[cfg(feature = "simd")]
+impl Sub for F64x4 {
+    type Output = F64x4;
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self(self.0 - rhs.0)
+    }
+}
+
+#[cfg(not(feature = "simd"))]
+impl Sub for F64x4 {
+    type Output = F64x4;
+    fn sub(self, rhs: Self) -> Self::Output {
+        self.zip(rhs, |a, b| a - b)
+    }
+}
+
+#[cfg(feature = "simd")]
+impl Mul for F64x4 {
+    type Output = F64x4;
+    fn mul(self, rhs: Self) -> Self::Output {
+        Self(self.0 * rhs.0)
+    }
+}
+
+#[cfg(not(feature = "simd"))]
+impl Mul for F64x4 {
+    type Output = F64x4;
+    fn mul(self, rhs: Self) -> Self::Output {
+        self.zip(rhs, |a, b| a * b)
+    }
+}
+
+#[cfg(feature = "simd")]
+impl Div for F64x4 {
+    type Output = F64x4;
+    fn div(self, rhs: Self) -> Self::Output {
+        Self(self.0 / rhs.0)
+    }
+}
+
+#[cfg(not(feature = "simd"))]
+impl Div for F64x4 {
+    type Output = F64x4;
+    fn div(self, rhs: Self) -> Self::Output {
+        self.zip(rhs, |a, b| a / b)
+    }
+}
+
+#[cfg(feature = "simd")]
+impl Neg for F64x4 {
+    type Output = F64x4;
+    fn neg(self) -> Self::Output {
+        Self(-self.0)
+    }
+}
+
+#[cfg(not(feature = "simd"))]
+impl Neg for F64x4 {
+    type Output = F64x4;
+    fn neg(self) -> Self::Output {
+        Self(self.0.map(|lane| -lane))
+    }
+}
+
+impl AddAssign for F64x4 {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl MulAssign for F64x4 {
+    fn mul_assign(&mut self, rhs: Self) {
+        *self = *self * rhs;
+    }
+}
+
+impl Index<usize> for F64x4 {
+    type Output = f64;
+    fn index(&self, index: usize) -> &f64 {
+        &self.0[index]
+    }
+}
+
+impl IndexMut<usize> for F64x4 {
+    fn index_mut(&mut self, index: usize) -> &mut f64 {
+        &mut self.0[index]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::renderer::F64x4;
+
+    #[test]
+    fn test_lanes() {
+        // Whichever backend is built, these have to agree
+        let a = F64x4::from_array([1.0, -2.0, 3.0, 0.5]);
+        let b = F64x4::splat(2.0);
+        assert_eq!((a + b).to_array(), [3.0, 0.0, 5.0, 2.5]);
+        assert_eq!((a - b).to_array(), [-1.0, -4.0, 1.0, -1.5]);
+        assert_eq!((a * b).to_array(), [2.0, -4.0, 6.0, 1.0]);
+        assert_eq!((a / b).to_array(), [0.5, -1.0, 1.5, 0.25]);
+        assert_eq!((-a).to_array(), [-1.0, 2.0, -3.0, -0.5]);
+        assert_eq!(a.reduce_sum(), 2.5);
+        assert_eq!(a.clamp(0.0, 1.0).to_array(), [1.0, 0.0, 1.0, 0.5]);
+
+        let mut c = a;
+        c += b;
+        c *= b;
+        c[3] = 9.0;
+        assert_eq!(c.to_array(), [6.0, 0.0, 10.0, 9.0]);
+        assert_eq!(
+            (F64x4::from_array([0.5, 254.9, 300.0, -1.0])).cast_u8(),
+            [0, 254, 255, 0]
+        );
+    }
+}
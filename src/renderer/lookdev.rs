@@ -0,0 +1,359 @@
+use crate::hittable::HitRecord;
+use crate::renderer::{EntryObject, Ray, Scene, SceneFile, Vec3};
+
+/// One number of a material that can be nudged up and down
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Param {
+    pub key: &'static str,
+    /// Which component of a colour, None for a plain number or for scaling a whole colour
+    pub channel: Option<usize>,
+    /// The value the scene file uses when the key is missing
+    pub default: f64,
+    /// Added or taken away each nudge, or for scaled parameters the factor to multiply by
+    pub step: f64,
+    /// The lowest value, or for scaled parameters the brightness a black colour starts from
+    pub min: f64,
+    pub max: f64,
+    /// Multiplies the whole colour rather than stepping a number
+    pub scale: bool,
+}
+
+const fn colour(key: &'static str, channel: usize) -> Param {
+    Param {
+        key,
+        channel: Some(channel),
+        default: 0.0,
+        step: 0.05,
+        min: 0.0,
+        max: 1.0,
+        scale: false,
+    }
+}
+
+const fn number(key: &'static str, default: f64, step: f64, (min, max): (f64, f64)) -> Param {
+    Param {
+        key,
+        channel: None,
+        default,
+        step,
+        min,
+        max,
+        scale: false,
+    }
+}
+
+const ALBEDO: [Param; 3] = [
+    colour("albedo", 0),
+    colour("albedo", 1),
+    colour("albedo", 2),
+];
+const METAL: [Param; 4] = [
+    ALBEDO[0],
+    ALBEDO[1],
+    ALBEDO[2],
+    number("fuzz", 0.0, 0.05, (0.0, 1.0)),
+];
+// Below one light would bend the wrong way, and at zero refraction divides by it
+const IOR: Param = number("ior", 1.5, 0.05, (1.0, 3.0));
+// Zero is plain glass, and crown glass spreads by about 0.004
+const DIELECTRIC: [Param; 2] = [IOR, number("cauchy_b", 0.0, 0.002, (0.0, 0.05))];
+const PRINCIPLED: [Param; 8] = [
+    colour("colour", 0),
+    colour("colour", 1),
+    colour("colour", 2),
+    number("roughness", 0.5, 0.05, (0.0, 1.0)),
+    number("metallic", 0.0, 0.05, (0.0, 1.0)),
+    number("clearcoat", 0.0, 0.05, (0.0, 1.0)),
+    number("transmission", 0.0, 0.05, (0.0, 1.0)),
+    IOR,
+];
+const HENYEY_GREENSTEIN: [Param; 4] = [
+    ALBEDO[0],
+    ALBEDO[1],
+    ALBEDO[2],
+    // Negative scatters back towards the light
+    number("g", 0.0, 0.05, (-0.95, 0.95)),
+];
+// Brightness covers too big a range to step through evenly
+const LIGHT: [Param; 1] = [Param {
+    key: "emit",
+    channel: None,
+    default: 0.0,
+    step: 1.25,
+    min: 0.1,
+    max: f64::INFINITY,
+    scale: true,
+}];
+
+/// What can be edited for each material type, with the same defaults as `SceneFile::build`
+pub fn material_params(kind: &str) -> &'static [Param] {
+    match kind {
+        "lambertian" | "isotropic" => &ALBEDO,
+        "metal" => &METAL,
+        "dielectric" => &DIELECTRIC,
+        "principled" => &PRINCIPLED,
+        "henyey_greenstein" => &HENYEY_GREENSTEIN,
+        "light" => &LIGHT,
+        _ => &[],
+    }
+}
+
+/// Short enough to read in a scene file
+fn format_number(value: f64) -> String {
+    let text = format!("{value:.3}");
+    let text = text.trim_end_matches('0').trim_end_matches('.');
+    if text.is_empty() || text == "-" {
+        "0".to_string()
+    } else {
+        text.to_string()
+    }
+}
+
+/// Picks objects out of a scene file and edits their materials, rebuilding the scene each time
+pub struct Lookdev {
+    pub file: SceneFile,
+    objects: Vec<EntryObject>,
+    /// Entry of the picked object
+    selected: Option<usize>,
+    param: usize,
+}
+
+impl Lookdev {
+    pub fn new(file: SceneFile) -> Result<(Self, Scene), String> {
+        let (scene, objects) = file.build_with_objects()?;
+        let lookdev = Self {
+            file,
+            objects,
+            selected: None,
+            param: 0,
+        };
+        Ok((lookdev, scene))
+    }
+
+    /// Entry of the nearest object along the ray
+    pub fn object_at(&self, ray: &Ray) -> Option<usize> {
+        let mut nearest = None;
+        let mut closest = f64::INFINITY;
+        for (index, object) in &self.objects {
+            let mut rec = HitRecord::default();
+            if object.hit(ray, 0.001, closest, &mut rec) {
+                closest = rec.t;
+                nearest = Some(*index);
+            }
+        }
+        nearest
+    }
+
+    /// Selects whatever the ray hits first, or nothing
+    pub fn pick(&mut self, ray: &Ray) -> Option<usize> {
+        self.selected = self.object_at(ray);
+        self.param = 0;
+        self.selected
+    }
+
+    pub fn selected(&self) -> Option<usize> {
+        self.selected
+    }
+
+    /// Entry of the picked object's material
+    fn material(&self) -> Option<usize> {
+        let name = self.file.entries[self.selected?].get("material")?;
+        self.file
+            .entries
+            .iter()
+            .position(|entry| entry.kind == "material" && entry.name.as_deref() == Some(name))
+    }
+
+    fn params(&self) -> &'static [Param] {
+        match self.material() {
+            Some(index) => material_params(self.file.entries[index].get("type").unwrap_or("")),
+            None => &[],
+        }
+    }
+
+    /// Moves on to editing the material's next parameter
+    pub fn next_param(&mut self) {
+        let count = self.params().len();
+        if count > 0 {
+            self.param = (self.param + 1) % count;
+        }
+    }
+
+    /// The current parameter and its value as written in the scene file
+    fn current(&self) -> Option<(Param, f64)> {
+        let param = *self.params().get(self.param)?;
+        let entry = &self.file.entries[self.material()?];
+        let value = match param.channel {
+            Some(channel) => entry.vec3(param.key, None).ok()?.0[channel],
+            // Scaled colours show their brightest component
+            None if param.scale => entry.vec3(param.key, None).ok()?.max_component(),
+            None => entry.float(param.key, Some(param.default)).ok()?,
+        };
+        Some((param, value))
+    }
+
+    /// Nudges the current parameter up, or down for a negative `direction`, and rebuilds the
+    /// scene. Every object sharing the material changes with it
+    pub fn adjust(&mut self, direction: f64) -> Result<Option<Scene>, String> {
+        let (param, value) = match self.current() {
+            Some(current) => current,
+            None => return Ok(None),
+        };
+        let index = self.material().unwrap();
+        let entry = &mut self.file.entries[index];
+
+        let text = match param.channel {
+            Some(channel) => {
+                let mut colour = entry.vec3(param.key, None)?;
+                colour.0[channel] = (value + direction * param.step).clamp(param.min, param.max);
+                format!(
+                    "{},{},{}",
+                    format_number(colour.x()),
+                    format_number(colour.y()),
+                    format_number(colour.z())
+                )
+            }
+            None if param.scale => {
+                let colour = entry.vec3(param.key, None)?;
+                // Black can't be multiplied into anything, so it starts again as a dim white
+                let colour = if colour.max_component() <= 0.0 && direction > 0.0 {
+                    Vec3::new(param.min, param.min, param.min)
+                } else {
+                    colour * param.step.powf(direction)
+                };
+                format!(
+                    "{},{},{}",
+                    format_number(colour.x()),
+                    format_number(colour.y()),
+                    format_number(colour.z())
+                )
+            }
+            None => format_number((value + direction * param.step).clamp(param.min, param.max)),
+        };
+        entry.set(param.key, text);
+
+        let (scene, objects) = self.file.build_with_objects()?;
+        self.objects = objects;
+        Ok(Some(scene))
+    }
+
+    /// What's picked and being edited, for the overlay
+    pub fn lines(&self) -> Vec<String> {
+        let selected = match self.selected {
+            Some(selected) => &self.file.entries[selected],
+            None => return vec!["Click an object to edit its material".to_string()],
+        };
+        let mut lines = vec![format!(
+            "Picked {} on line {}",
+            selected.kind, selected.line
+        )];
+        match (self.material(), self.current()) {
+            (Some(index), Some((param, value))) => {
+                let material = &self.file.entries[index];
+                lines.push(format!(
+                    "Material {} ({})",
+                    material.name.as_deref().unwrap_or_default(),
+                    material.get("type").unwrap_or_default()
+                ));
+                let name = match param.channel {
+                    Some(channel) => format!("{}.{}", param.key, ["r", "g", "b"][channel]),
+                    None => param.key.to_string(),
+                };
+                lines.push(format!(
+                    "{name} = {}  (Tab next, -/+ change)",
+                    format_number(value)
+                ));
+            }
+            _ => lines.push("No material to edit".to_string()),
+        }
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::renderer::{Lookdev, Ray, SceneFile, Vec3};
+
+    #[test]
+    fn test_pick_and_edit() {
+        let file = SceneFile::parse(
+            "
+            material red type=principled colour=0.8,0.1,0.1
+            material lamp type=light emit=4,4,4
+            sphere center=0,0,-2 radius=0.5 material=red
+            sphere center=0,0,-4 radius=1 material=red
+            disk center=0,2,-2 normal=0,-1,0 radius=0.5 material=lamp
+            ",
+        )
+        .unwrap();
+        let (mut lookdev, _) = Lookdev::new(file).unwrap();
+
+        // The nearer sphere hides the further one
+        let ray = Ray::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, -1.0));
+        assert_eq!(lookdev.pick(&ray), Some(2));
+        let up = Ray::new(Vec3::new(0.0, 1.0, -2.0), Vec3::new(0.0, 1.0, 0.0));
+        assert_eq!(lookdev.object_at(&up), Some(4));
+
+        // Colours step per channel, roughness comes from its default
+        assert!(lookdev.adjust(-1.0).unwrap().is_some());
+        assert_eq!(lookdev.file.entries[0].get("colour"), Some("0.75,0.1,0.1"));
+        for _ in 0..3 {
+            lookdev.next_param();
+        }
+        lookdev.adjust(1.0).unwrap();
+        assert_eq!(lookdev.file.entries[0].get("roughness"), Some("0.55"));
+        assert!(lookdev.lines()[2].starts_with("roughness = 0.55"));
+
+        // Lights get brighter and darker by a factor
+        lookdev.pick(&up);
+        lookdev.adjust(-1.0).unwrap();
+        assert_eq!(lookdev.file.entries[1].get("emit"), Some("3.2,3.2,3.2"));
+
+        let away = Ray::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0));
+        assert_eq!(lookdev.pick(&away), None);
+        assert!(lookdev.adjust(1.0).unwrap().is_none());
+
+        // Edits stop where the material stops making sense
+        let file = SceneFile::parse(
+            "
+            material glass type=dielectric ior=1.02
+            material haze type=henyey_greenstein albedo=0.9,0.9,0.9 g=-0.5
+            material lamp type=light emit=0,0,0
+            sphere center=0,0,-2 radius=0.5 material=glass
+            sphere center=0,0,2 radius=0.5 material=haze
+            sphere center=2,0,0 radius=0.5 material=lamp
+            ",
+        )
+        .unwrap();
+        lookdev = Lookdev::new(file).unwrap().0;
+        let origin = Vec3::new(0.0, 0.0, 0.0);
+
+        // Glass stops at an index of one rather than going through zero
+        lookdev.pick(&Ray::new(origin, Vec3::new(0.0, 0.0, -1.0)));
+        lookdev.adjust(-1.0).unwrap();
+        lookdev.adjust(-1.0).unwrap();
+        assert_eq!(lookdev.file.entries[0].get("ior"), Some("1"));
+
+        // Backward scattering is kept and can go further
+        lookdev.pick(&Ray::new(origin, Vec3::new(0.0, 0.0, 1.0)));
+        for _ in 0..3 {
+            lookdev.next_param();
+        }
+        lookdev.adjust(-1.0).unwrap();
+        assert_eq!(lookdev.file.entries[1].get("g"), Some("-0.55"));
+        for _ in 0..20 {
+            lookdev.adjust(-1.0).unwrap();
+        }
+        assert_eq!(lookdev.file.entries[1].get("g"), Some("-0.95"));
+
+        // A light that's off can be turned on again
+        lookdev.pick(&Ray::new(origin, Vec3::new(1.0, 0.0, 0.0)));
+        lookdev.adjust(1.0).unwrap();
+        assert_eq!(lookdev.file.entries[2].get("emit"), Some("0.1,0.1,0.1"));
+        lookdev.adjust(1.0).unwrap();
+        assert_eq!(
+            lookdev.file.entries[2].get("emit"),
+            Some("0.125,0.125,0.125")
+        );
+    }
+}
@@ -1,11 +1,45 @@
+mod batch;
 mod camera;
+mod checkpoint;
+mod fog;
+mod frame;
+mod image;
+mod image_view;
+mod lanes;
+mod lookdev;
+mod random;
 mod ray;
+#[cfg(feature = "sdl")]
 mod renderer;
 mod sample_map;
+mod scene;
+mod scene_file;
+mod scene_watch;
+mod sky;
+mod spectrum;
+mod stats;
+mod tracer;
 mod vec3;
 
-pub use camera::Camera;
+pub use batch::render_image;
+pub use camera::{Camera, CameraSample};
+pub use checkpoint::{hash_str, settings_hash, ByteReader, Checkpoint};
+pub use fog::Fog;
+pub use frame::Frame;
+pub use image::{timestamp, write_image, write_pfm, write_ppm, write_snapshot};
+pub use image_view::ImageView;
+pub use lanes::F64x4;
+pub use lookdev::{material_params, Lookdev, Param};
+pub use random::{random_double, random_range, random_state, seed_random};
 pub use ray::Ray;
-pub use renderer::Renderer;
+#[cfg(feature = "sdl")]
+pub use renderer::{Graphics, Renderer};
 pub use sample_map::SampleMap;
+pub use scene::{rays_traced, Scene};
+pub use scene_file::{Entry, EntryObject, SceneFile};
+pub use scene_watch::SceneWatcher;
+pub use sky::Sky;
+pub use spectrum::{rgb_to_spectrum, sample_wavelengths, spectrum_to_rgb, xyz_to_rgb};
+pub use stats::{format_count, format_duration, RenderStats};
+pub use tracer::{camera_ray, trace_pixel, PixelSplat};
 pub use vec3::Vec3;
@@ -0,0 +1,37 @@
+use std::cell::Cell;
+
+thread_local! {
+    // SplitMix64, small enough that its whole state can go in a checkpoint
+    static STATE: Cell<u64> = Cell::new(rand::random());
+}
+
+/// Restarts this thread's random sequence from `seed`, so renders can be repeated
+pub fn seed_random(seed: u64) {
+    STATE.with(|state| state.set(seed));
+}
+
+/// Where this thread's random sequence has got to, pass it to `seed_random` to carry on
+pub fn random_state() -> u64 {
+    STATE.with(|state| state.get())
+}
+
+fn next_u64() -> u64 {
+    STATE.with(|state| {
+        let s = state.get().wrapping_add(0x9E37_79B9_7F4A_7C15);
+        state.set(s);
+        let mut z = s;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    })
+}
+
+/// Returns a random number in [0, 1)
+pub fn random_double() -> f64 {
+    (next_u64() >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Returns a random number in [min, max)
+pub fn random_range(min: f64, max: f64) -> f64 {
+    min + (max - min) * random_double()
+}
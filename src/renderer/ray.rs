@@ -5,11 +5,17 @@ use Vec3 as Point3;
 pub struct Ray {
     pub orig: Point3,
     pub dir: Vec3,
+    /// The hero wavelength in nanometres when rendering spectrally
+    pub wavelength: Option<f64>,
 }
 
 impl Ray {
     pub fn new(orig: Point3, dir: Vec3) -> Ray {
-        Ray { orig, dir }
+        Ray {
+            orig,
+            dir,
+            wavelength: None,
+        }
     }
 
     pub fn at(&self, t: f64) -> Point3 {
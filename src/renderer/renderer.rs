@@ -1,108 +1,182 @@
-use rand::distributions::Uniform;
-use rand::prelude::*;
 use sdl2::video::WindowContext;
 use sdl2::EventPump;
-use std::time::Instant;
+use std::time::{Duration, Instant, SystemTime};
 
-use sdl2::pixels::Color;
-use sdl2::rect::{Point, Rect};
+use sdl2::pixels::{Color, PixelFormatEnum};
+use sdl2::rect::Rect;
 use sdl2::render::{Canvas, RenderTarget, Texture, TextureAccess, TextureCreator};
+use sdl2::rwops::RWops;
 use sdl2::ttf::{Font, Sdl2TtfContext};
 
-use crate::hittable::{HitRecord, Hittable, HittableList, Sphere};
-use crate::renderer::{Camera, Ray, SampleMap, Vec3};
+use crate::integrator::{Integrator, IntegratorKind};
+use crate::net::TileResult;
+use crate::renderer::{
+    camera_ray, format_count, format_duration, hash_str, random_range, random_state, rays_traced,
+    seed_random, settings_hash, timestamp, trace_pixel, write_snapshot, Checkpoint, F64x4, Fog,
+    ImageView, Ray, RenderStats, SampleMap, Scene, Vec3,
+};
+
+/// Built into the binary so the overlay works without any fonts installed
+const EMBEDDED_FONT: &[u8] = include_bytes!("../../assets/DejaVuSansMono.ttf");
+const FONT_SIZE: u16 = 12;
+
+/// What textures and fonts are made from. They borrow it, so it's made before the `Renderer`
+/// and has to outlive it
+pub struct Graphics {
+    sdl_context: sdl2::Sdl,
+    texture_creator: TextureCreator<WindowContext>,
+    ttf_context: Sdl2TtfContext,
+}
 
-use std::simd::{Simd, SimdFloat};
+impl Graphics {
+    /// Opens a window, returning its canvas to hand to `Renderer::new`
+    pub fn new(width: u32, height: u32) -> (Self, sdl2::render::Canvas<sdl2::video::Window>) {
+        let (canvas, sdl_context) = Renderer::create_sdl_canvas(width, height);
+        let graphics = Self {
+            sdl_context,
+            texture_creator: canvas.texture_creator(),
+            ttf_context: sdl2::ttf::init().expect("Failed to initialise SDL2_ttf"),
+        };
+        (graphics, canvas)
+    }
+}
 
-pub struct Renderer {
-    camera: Camera,
-    hittables: HittableList,
+pub struct Renderer<'a> {
+    scene: Scene,
+    integrator: Box<dyn Integrator>,
     sample_map: SampleMap,
     canvas: sdl2::render::Canvas<sdl2::video::Window>,
     height: f64,
     width: f64,
-    texture_creator: TextureCreator<WindowContext>,
-    sdl_context: sdl2::Sdl,
+    graphics: &'a Graphics,
+    /// The tone mapped image, copied over from the sample map every frame
+    display: Texture<'a>,
+    font: Font<'a, 'static>,
+    /// The last lines of text drawn, each only rendered again when it changes
+    text: Vec<(String, Texture<'a>)>,
+    stats: RenderStats,
+    show_stats: bool,
+    view: ImageView,
+    /// Last place the mouse was seen in the window
+    mouse: (i32, i32),
+    show_probe: bool,
+    /// Pixels showing the picked object, outlined over the image
+    highlight: Option<Vec<bool>>,
+    /// Extra lines for the overlay, such as what's being edited
+    info: Vec<String>,
+    /// Identifies the scene being rendered, so checkpoints can't be resumed into another one
+    scene_hash: u64,
+    checkpoint: Option<(String, Duration)>,
+    last_checkpoint: Instant,
+    /// Samples per pixel at which the image is saved, in order
+    snapshot_at: Vec<u32>,
+    /// The first of those not reached yet
+    next_snapshot: usize,
+    /// Screenshots saved so far, so two in the same second get different names
+    screenshots: u32,
+    /// Samples in the image when the integrator is next prepared, a pass further on each time
+    next_pass: u64,
+    max_depth: Option<u32>,
 }
 
-impl Renderer {
+impl<'a> Renderer<'a> {
     /// Creates a new Renderer
     /// # Arguments
-    /// * `hittables` - A list of objects that implement the Hittable trait
+    /// * `graphics` - What the window's textures and fonts are made from
     /// * `canvas` - An SDL2 canvas, here of type Window but I suppose it could be anything
     /// * `image_height` - The height of the output image
     /// * `image_width` - The width of the output image
     /// * `max_samples` - The maximum number of ray samples per pixel
-    pub fn new<'a>(height: u32, width: u32, max_samples: u32) -> Self {
-        // For now just add objects to the scene statically
-        let hittables = Self::create_world();
-        let (canvas, sdl_context) = Self::create_sdl_canvas(width, height);
-
-        let texture_creator = canvas.texture_creator();
+    /// * `integrator` - Which rendering algorithm to start with
+    /// * `scene` - What to render
+    pub fn new(
+        graphics: &'a Graphics,
+        canvas: sdl2::render::Canvas<sdl2::video::Window>,
+        height: u32,
+        width: u32,
+        max_samples: u32,
+        integrator: IntegratorKind,
+        scene: Scene,
+    ) -> Self {
+        let display = Self::create_display(&graphics.texture_creator, width, height);
+        let font = graphics
+            .ttf_context
+            .load_font_from_rwops(
+                RWops::from_bytes(EMBEDDED_FONT).expect("Failed to read embedded font"),
+                FONT_SIZE,
+            )
+            .expect("Failed to load embedded font");
 
         Self {
-            camera: Camera::new(),
-            hittables,
+            scene,
+            integrator: integrator.create(),
             sample_map: SampleMap::new(max_samples, width as usize, height as usize),
             canvas,
             height: height as f64,
             width: width as f64,
-            texture_creator,
-            sdl_context,
+            graphics,
+            display,
+            font,
+            text: Vec::new(),
+            stats: RenderStats::new(Instant::now()),
+            show_stats: false,
+            view: ImageView::fit((width, height)),
+            mouse: (0, 0),
+            show_probe: false,
+            highlight: None,
+            info: Vec::new(),
+            scene_hash: hash_str(""),
+            checkpoint: None,
+            last_checkpoint: Instant::now(),
+            snapshot_at: Vec::new(),
+            next_snapshot: 0,
+            screenshots: 0,
+            next_pass: 0,
+            max_depth: None,
         }
     }
 
-    fn create_world() -> HittableList {
-        let mut hittables = HittableList::new();
-        hittables.add_hittable(Box::new(Sphere::new(Vec3::new(0.0, 0.0, -1.0), 0.5)));
-        hittables.add_hittable(Box::new(Sphere::new(Vec3::new(0.0, -100.5, -1.0), 100.0)));
-        hittables
-    }
-
     /// Renders as many pixels as possible in 1/60 seconds (Maybe make the time constant variable)
-    pub fn render_one(
-        &mut self,
-        width_rng: Uniform<f64>,
-        height_rng: Uniform<f64>,
-        rng: Uniform<f64>,
-    ) {
+    pub fn render_one(&mut self) {
         let now = Instant::now();
+        let mut splats = Vec::new();
+
+        // Pixels are picked at random, so a pass is when enough samples for the whole image have
+        // landed
+        let total = self.sample_map.total_samples();
+        if total >= self.next_pass {
+            self.integrator.prepare(&self.scene);
+            self.next_pass = total + (self.sample_map.width * self.sample_map.height) as u64;
+        }
 
         while now.elapsed().as_secs_f64() < 1. / 60. {
             // Get a random pixel
-            let i = width_rng.sample(&mut thread_rng());
-            let j = height_rng.sample(&mut thread_rng());
-
-            // Add jitter
-            let jitter_u = rng.sample(&mut thread_rng());
-            let jitter_v = rng.sample(&mut thread_rng());
-            let u = (i + jitter_u) / (self.width - 1.);
-            let v = ((self.height - j) + jitter_v) / (self.height - 1.);
+            let i = random_range(0., self.width).floor();
+            let j = random_range(0., self.height).floor();
 
             // Determine if the ray intersects any objects
-            let ray = Self::ray_colour(&self.camera.get_ray(u, v), &self.hittables);
+            let (colour, aov) = trace_pixel(
+                &self.scene,
+                self.integrator.as_ref(),
+                (i as usize, j as usize),
+                (self.width as usize, self.height as usize),
+                &mut splats,
+            );
+            self.sample_map.add_aov(i as usize, j as usize, aov);
             self.sample_map
-                .set_value(i as usize, j as usize, Simd::<f64, 4>::from(ray));
-
-            // Draw the colour we expect
-            self.canvas.set_draw_color(Self::constrain_colour(
-                i as usize,
-                j as usize,
-                &self.sample_map,
-            ));
-            self.canvas
-                .draw_point(Point::new(i as i32, j as i32))
-                .expect("Failed to draw point");
+                .set_value(i as usize, j as usize, F64x4::from(colour));
+
+            // Light traced from the lights lands wherever it likes
+            self.sample_map.add_light_path();
+            for splat in splats.drain(..) {
+                self.sample_map
+                    .add_splat(splat.x, splat.y, F64x4::from(splat.value));
+            }
         }
     }
 
     /// Perform the actual render looping, generate as many pixels as possible in 1/60 seconds
     pub fn render(&mut self) {
-        // Create our random sources
-        let width_rng = Uniform::from((0.)..self.width);
-        let height_rng = Uniform::from((0.)..self.height);
-        let uniform_dist = Uniform::from((0.)..=1.);
-
         // for j in (0..IMAGE_HEIGHT).rev() {
         //     println!("Lines left: {j}");
         //     for i in 0..=IMAGE_WIDTH {
@@ -125,16 +199,324 @@ impl Renderer {
 
         let now = Instant::now();
         // Render a frame to a texture
-        self.render_one(width_rng, height_rng, uniform_dist);
+        self.render_one();
+
+        if let Some((_, interval)) = &self.checkpoint {
+            if self.last_checkpoint.elapsed() >= *interval {
+                if let Err(e) = self.save_checkpoint() {
+                    eprintln!("Failed to save checkpoint: {e}");
+                }
+            }
+        }
+
+        let fps_str = format!(
+            "{:.2} FPS ({})",
+            1.0 / now.elapsed().as_secs_f64(),
+            self.integrator.kind()
+        );
+        self.present(fps_str);
+    }
+
+    /// Adds a tile rendered somewhere else
+    pub fn add_tile(&mut self, result: &TileResult) -> Result<(), String> {
+        result.merge_into(&mut self.sample_map)?;
+        Ok(())
+    }
+
+    /// Starts again at a new resolution, with the camera stretched to match
+    pub fn resize(&mut self, width: u32, height: u32) {
+        if width == 0 || height == 0 {
+            return;
+        }
+        if (width as f64, height as f64) == (self.width, self.height) {
+            return;
+        }
+        self.width = width as f64;
+        self.height = height as f64;
+        self.sample_map =
+            SampleMap::new(self.sample_map.max_samples, width as usize, height as usize);
+        self.display = Self::create_display(&self.graphics.texture_creator, width, height);
+        self.view = ImageView::fit((width, height));
+        self.highlight = None;
+        self.scene.camera = self.scene.camera.with_aspect(width as f64 / height as f64);
+        self.restart();
+    }
+
+    fn create_display(
+        texture_creator: &'a TextureCreator<WindowContext>,
+        width: u32,
+        height: u32,
+    ) -> Texture<'a> {
+        texture_creator
+            .create_texture_streaming(PixelFormatEnum::RGB24, width, height)
+            .expect("Failed to create display texture")
+    }
+
+    /// Tone maps the whole sample map into the display texture and draws it over the window
+    fn draw_display(&mut self) {
+        let sample_map = &self.sample_map;
+        let highlight = self.highlight.as_deref();
+        let (width, height) = (sample_map.width, sample_map.height);
+        // Only the edge of the highlight, so what's inside can still be seen
+        let outline = |x: usize, y: usize| match highlight {
+            Some(mask) if mask[x + y * width] => {
+                x == 0
+                    || y == 0
+                    || x + 1 == width
+                    || y + 1 == height
+                    || !mask[x - 1 + y * width]
+                    || !mask[x + 1 + y * width]
+                    || !mask[x + (y - 1) * width]
+                    || !mask[x + (y + 1) * width]
+            }
+            _ => false,
+        };
+        self.display
+            .with_lock(None, |buffer, pitch| {
+                for y in 0..height {
+                    let row = &mut buffer[y * pitch..];
+                    for x in 0..width {
+                        let colour = if outline(x, y) {
+                            [255, 0, 255]
+                        } else {
+                            Self::constrain_colour(x, y, sample_map)
+                        };
+                        row[x * 3..x * 3 + 3].copy_from_slice(&colour);
+                    }
+                }
+            })
+            .expect("Failed to update display texture");
+        let image = self.image_size();
+        let window = self.window_size();
+        let (x, y) = self.view.image_to_window((0.0, 0.0), image, window);
+        let (right, bottom) =
+            self.view
+                .image_to_window((image.0 as f64, image.1 as f64), image, window);
+        let destination = Rect::new(
+            x.round() as i32,
+            y.round() as i32,
+            (right - x).round().max(1.0) as u32,
+            (bottom - y).round().max(1.0) as u32,
+        );
+
+        // Anything not covered by the image when zoomed out
+        self.canvas.set_draw_color(Color::RGB(32, 32, 32));
+        self.canvas.clear();
+        self.canvas
+            .copy(&self.display, None, destination)
+            .expect("Failed to draw display texture");
+    }
+
+    fn image_size(&self) -> (u32, u32) {
+        (self.sample_map.width as u32, self.sample_map.height as u32)
+    }
+
+    fn window_size(&self) -> (u32, u32) {
+        self.canvas
+            .output_size()
+            .expect("Failed to get window size")
+    }
+
+    /// Zooms the image by `factor` about the mouse
+    pub fn zoom(&mut self, factor: f64) {
+        let at = (self.mouse.0 as f64, self.mouse.1 as f64);
+        let (image, window) = (self.image_size(), self.window_size());
+        self.view.zoom_at(factor, at, image, window);
+    }
+
+    /// Drags the image by some window pixels
+    pub fn pan(&mut self, dx: i32, dy: i32) {
+        let (image, window) = (self.image_size(), self.window_size());
+        self.view.pan((dx as f64, dy as f64), image, window);
+    }
+
+    /// Back to the whole image filling the window
+    pub fn reset_view(&mut self) {
+        self.view = ImageView::fit(self.image_size());
+    }
+
+    pub fn set_mouse(&mut self, x: i32, y: i32) {
+        self.mouse = (x, y);
+    }
+
+    /// The camera ray through the image under the mouse, if it's over the image
+    pub fn ray_at_mouse(&self) -> Option<Ray> {
+        let (image, window) = (self.image_size(), self.window_size());
+        let (x, y) =
+            self.view
+                .window_to_image((self.mouse.0 as f64, self.mouse.1 as f64), image, window);
+        if x < 0.0 || y < 0.0 || x >= image.0 as f64 || y >= image.1 as f64 {
+            return None;
+        }
+        Some(camera_ray(
+            &self.scene.camera,
+            (x, y),
+            (image.0 as usize, image.1 as usize),
+        ))
+    }
+
+    /// Outlines every pixel whose camera ray `is_selected` says yes to
+    pub fn highlight(&mut self, is_selected: impl Fn(&Ray) -> bool) {
+        let (width, height) = (self.sample_map.width, self.sample_map.height);
+        let mut mask = vec![false; width * height];
+        for y in 0..height {
+            for x in 0..width {
+                let ray = camera_ray(
+                    &self.scene.camera,
+                    (x as f64 + 0.5, y as f64 + 0.5),
+                    (width, height),
+                );
+                mask[x + y * width] = is_selected(&ray);
+            }
+        }
+        self.highlight = Some(mask);
+    }
+
+    pub fn clear_highlight(&mut self) {
+        self.highlight = None;
+    }
+
+    /// Lines shown at the bottom of the overlay until replaced
+    pub fn set_info(&mut self, lines: Vec<String>) {
+        self.info = lines;
+    }
+
+    /// Renders something else, from its own camera fitted to the window, starting the image
+    /// again
+    pub fn set_scene(&mut self, mut scene: Scene) {
+        scene.camera = scene.camera.with_aspect(self.width / self.height);
+        self.scene = scene;
+        self.restart();
+    }
+
+    /// Shows or hides what's been gathered for the pixel under the mouse
+    pub fn toggle_probe(&mut self) {
+        self.show_probe = !self.show_probe;
+    }
+
+    fn probe_lines(&mut self) -> Vec<String> {
+        let (image, window) = (self.image_size(), self.window_size());
+        let (x, y) =
+            self.view
+                .window_to_image((self.mouse.0 as f64, self.mouse.1 as f64), image, window);
+        if x < 0.0 || y < 0.0 || x >= image.0 as f64 || y >= image.1 as f64 {
+            return vec!["Probe: outside the image".to_string()];
+        }
+        let (x, y) = (x as usize, y as usize);
+
+        // Outline the pixel once it's big enough to see
+        let (left, top) = self
+            .view
+            .image_to_window((x as f64, y as f64), image, window);
+        let (right, bottom) =
+            self.view
+                .image_to_window(((x + 1) as f64, (y + 1) as f64), image, window);
+        if right - left >= 4.0 {
+            self.canvas.set_draw_color(Color::RGB(255, 0, 255));
+            self.canvas
+                .draw_rect(Rect::new(
+                    left.round() as i32,
+                    top.round() as i32,
+                    (right - left).round() as u32,
+                    (bottom - top).round() as u32,
+                ))
+                .expect("Failed to outline pixel");
+        }
 
-        let fps_str = format!("{:.2} FPS", 1.0 / now.elapsed().as_secs_f64());
-        self.render_text(fps_str, 0, 0);
+        let lanes = |value: F64x4| {
+            let [r, g, b, _] = value.to_array();
+            format!("({r:.5}, {g:.5}, {b:.5})")
+        };
+        let (_, samples) = self.sample_map.get_values(x, y);
+        let aov = self.sample_map.get_aov(x, y);
+        vec![
+            format!("Probe ({x}, {y}): {samples} samples"),
+            format!("Radiance: {}", lanes(self.sample_map.get_colour(x, y))),
+            format!("Variance: {}", lanes(self.sample_map.get_variance(x, y))),
+            format!(
+                "Normal: ({:.3}, {:.3}, {:.3})  Distance: {:.4}",
+                aov[0], aov[1], aov[2], aov[3]
+            ),
+        ]
+    }
 
+    /// Shows the image without rendering anything, with `status` in the corner
+    pub fn present(&mut self, status: String) {
+        let now = Instant::now();
+        self.stats
+            .record(now, self.sample_map.total_samples(), rays_traced());
+        self.save_snapshots();
+
+        self.draw_display();
+        let mut lines = vec![status];
+        if self.show_stats {
+            lines.extend(self.stats_lines(now));
+        }
+        if self.show_probe {
+            let probe = self.probe_lines();
+            lines.extend(probe);
+        }
+        lines.extend(self.info.iter().cloned());
+        self.render_text(&lines, 0, 0);
         self.canvas.present();
     }
 
+    /// Shows or hides the detailed stats under the status line
+    pub fn toggle_stats(&mut self) {
+        self.show_stats = !self.show_stats;
+    }
+
+    fn stats_lines(&self, now: Instant) -> Vec<String> {
+        let (mean, min, max) = self.sample_map.sample_stats();
+        let pixels = (self.sample_map.width * self.sample_map.height) as u64;
+        let remaining = (pixels * self.sample_map.max_samples as u64)
+            .saturating_sub(self.sample_map.total_samples());
+        let eta = match self.stats.eta(remaining) {
+            Some(eta) => format_duration(eta),
+            None => "?".to_string(),
+        };
+        let origin = self.scene.camera.origin;
+        vec![
+            format!("Integrator: {}", self.integrator.kind()),
+            format!(
+                "Samples/s: {}  Rays/s: {}",
+                format_count(self.stats.samples_per_second),
+                format_count(self.stats.rays_per_second)
+            ),
+            format!(
+                "Samples per pixel: {mean:.1} mean, {min} min, {max} max of {}",
+                self.sample_map.max_samples
+            ),
+            format!(
+                "Elapsed: {}  Remaining: {eta}",
+                format_duration(self.stats.elapsed(now))
+            ),
+            format!(
+                "Camera: ({:.2}, {:.2}, {:.2})",
+                origin.x(),
+                origin.y(),
+                origin.z()
+            ),
+        ]
+    }
+
+    /// Starts the stats again along with the image
+    /// Throws away the image and anything the integrator worked out beforehand, such as photon
+    /// maps, to be worked out again at the start of the next pass
+    fn restart(&mut self) {
+        self.integrator.reset();
+        self.invalidate_samples();
+        self.next_pass = 0;
+    }
+
+    fn invalidate_samples(&mut self) {
+        self.sample_map.invalidate_samples();
+        self.stats.reset(Instant::now(), 0, rays_traced());
+        self.skip_snapshots();
+    }
+
     pub fn get_event_pump(&self) -> EventPump {
-        self.sdl_context.event_pump().unwrap()
+        self.graphics.sdl_context.event_pump().unwrap()
     }
 
     pub fn create_sdl_canvas(
@@ -148,24 +530,146 @@ impl Renderer {
             .window("raytracer", width, height)
             .opengl()
             .position_centered()
+            .resizable()
             .build()
             .unwrap();
 
         (window.into_canvas().build().unwrap(), sdl_context)
     }
 
-    fn ray_colour(ray: &Ray, world: &impl Hittable) -> Vec3 {
-        let mut rec = HitRecord {
-            ..Default::default()
-        };
+    /// Enables homogeneous fog throughout the scene, pass None to clear it
+    pub fn set_fog(&mut self, fog: Option<Fog>) {
+        self.scene.fog = fog;
+        self.restart();
+    }
 
-        if world.hit(ray, 0.0, f64::INFINITY, &mut rec) {
-            return 0.5 * (rec.normal + Vec3::new(1.0, 1.0, 1.0));
+    /// Switches rendering algorithm, starting the image again
+    pub fn set_integrator(&mut self, kind: IntegratorKind) {
+        self.integrator = kind.create();
+        if let Some(depth) = self.max_depth {
+            self.integrator.set_max_depth(depth);
         }
+        self.restart();
+    }
+
+    /// Limits path lengths for this and any later integrator
+    pub fn set_max_depth(&mut self, depth: u32) {
+        self.max_depth = Some(depth);
+        self.integrator.set_max_depth(depth);
+        self.restart();
+    }
 
-        let unit_direction = ray.dir.unit_vector();
-        let t = 0.5 * (unit_direction.y() + 1.0);
-        (1.0 - t) * Vec3::new(1.0, 1.0, 1.0) + t * Vec3::new(0.5, 0.7, 1.0)
+    pub fn integrator_kind(&self) -> IntegratorKind {
+        self.integrator.kind()
+    }
+
+    /// Sets what identifies the scene in checkpoints, such as a hash of its scene file
+    pub fn set_scene_hash(&mut self, hash: u64) {
+        self.scene_hash = hash;
+    }
+
+    /// Saves the render to `path` every `interval` so it can be resumed later
+    pub fn set_checkpoint(&mut self, path: String, interval: Duration) {
+        self.checkpoint = Some((path, interval));
+        self.last_checkpoint = Instant::now();
+    }
+
+    /// Saves the image each time the average samples per pixel reaches one of `milestones`
+    pub fn set_snapshots(&mut self, mut milestones: Vec<u32>) {
+        milestones.sort_unstable();
+        milestones.dedup();
+        self.snapshot_at = milestones;
+        self.skip_snapshots();
+    }
+
+    fn mean_samples(&self) -> f64 {
+        self.sample_map.total_samples() as f64 / (self.width * self.height)
+    }
+
+    /// Leaves out milestones the image is already past, so a resumed render doesn't save them
+    fn skip_snapshots(&mut self) {
+        let mean = self.mean_samples();
+        self.next_snapshot = self
+            .snapshot_at
+            .iter()
+            .filter(|&&n| n as f64 <= mean)
+            .count();
+    }
+
+    fn save_snapshots(&mut self) {
+        let mean = self.mean_samples();
+        // Workers can deliver several milestones at once, only the last is worth saving
+        let reached = self.snapshot_at[self.next_snapshot..]
+            .iter()
+            .take_while(|&&n| n as f64 <= mean)
+            .last()
+            .copied();
+        if let Some(milestone) = reached {
+            self.skip_snapshots();
+            let stem = format!("snapshot-{milestone}spp-{}", timestamp(SystemTime::now()));
+            match write_snapshot(&stem, &self.sample_map) {
+                Ok(()) => println!("Saved {stem}.ppm and {stem}.pfm"),
+                Err(e) => eprintln!("Failed to save snapshot: {e}"),
+            }
+        }
+    }
+
+    /// Saves the image as it is now to timestamped files, returning their name without the
+    /// extension
+    pub fn screenshot(&mut self) -> Result<String, String> {
+        self.screenshots += 1;
+        let stem = format!(
+            "screenshot-{}-{}",
+            timestamp(SystemTime::now()),
+            self.screenshots
+        );
+        write_snapshot(&stem, &self.sample_map)?;
+        Ok(stem)
+    }
+
+    /// Hash of the scene and every setting which changes what the samples mean
+    fn checkpoint_hash(&self) -> u64 {
+        settings_hash(
+            self.scene_hash,
+            (self.width as usize, self.height as usize),
+            self.sample_map.max_samples,
+            self.integrator.kind(),
+            self.max_depth,
+        )
+    }
+
+    pub fn save_checkpoint(&mut self) -> Result<(), String> {
+        let path = match &self.checkpoint {
+            Some((path, _)) => path.clone(),
+            None => return Ok(()),
+        };
+        self.last_checkpoint = Instant::now();
+        Checkpoint {
+            hash: self.checkpoint_hash(),
+            random_state: random_state(),
+            sample_map: self.sample_map.clone(),
+        }
+        .save(&path)
+    }
+
+    /// Carries on from a saved render, as long as it was of the same scene with the same settings
+    pub fn resume(&mut self, path: &str) -> Result<(), String> {
+        let checkpoint = Checkpoint::load(path)?;
+        if checkpoint.hash != self.checkpoint_hash() {
+            return Err(format!(
+                "{path} was saved from a different scene or with different settings"
+            ));
+        }
+        self.sample_map = checkpoint.sample_map;
+        seed_random(checkpoint.random_state);
+        self.skip_snapshots();
+        self.stats.reset(
+            Instant::now(),
+            self.sample_map.total_samples(),
+            rays_traced(),
+        );
+
+        Ok(())
     }
 
     fn constrain_colour_instant(colour: &mut Vec3, samples: u32) -> Color {
@@ -186,33 +690,58 @@ impl Renderer {
         Color::RGB((r * 255.0) as u8, (g * 255.0) as u8, (b * 255.0) as u8)
     }
 
-    fn constrain_colour(x: usize, y: usize, sample_map: &SampleMap) -> Color {
-        let mut values = sample_map.get_values(x, y);
-        let scale = 1. / values.1 as f64;
-        values.0 *= Simd::splat(scale);
-        values.0 = values.0.simd_clamp(Simd::splat(0.), Simd::splat(0.999));
-        values.0 *= Simd::splat(255.);
-        let cast_vec = Simd::cast::<u8>(values.0);
-        Color::RGB(cast_vec[0], cast_vec[1], cast_vec[2])
+    fn constrain_colour(x: usize, y: usize, sample_map: &SampleMap) -> [u8; 3] {
+        let mut colour = sample_map.get_colour(x, y);
+        colour = colour.clamp(0., 0.999);
+        colour *= F64x4::splat(255.);
+        let cast_vec = colour.cast_u8();
+        [cast_vec[0], cast_vec[1], cast_vec[2]]
     }
 
-    fn render_text(&mut self, text: String, x: i32, y: i32) {
-        let ttf_context = &sdl2::ttf::init().unwrap();
-        let font = ttf_context
-            .load_font("C:\\Windows\\Fonts\\verdana.ttf", 12)
-            .expect("Failed to load font");
-
-        let rect_size = font.size_of(&text).unwrap();
-
-        let rendered_text = font.render(&text).solid(Color::RGBA(0, 0, 0, 255)).unwrap();
-        let text_texture = rendered_text.as_texture(&self.texture_creator).unwrap();
+    /// Uses a font file for the overlay instead of the embedded one
+    pub fn set_font(&mut self, path: &str) -> Result<(), String> {
+        self.font = self
+            .graphics
+            .ttf_context
+            .load_font(path, FONT_SIZE)
+            .map_err(|e| format!("{path}: {e}"))?;
+        self.text.clear();
+        Ok(())
+    }
 
-        self.canvas
-            .copy(
-                &text_texture,
-                None,
-                Rect::new(x, y, rect_size.0, rect_size.1),
-            )
-            .unwrap();
+    /// Draws each line under the last, starting at (`x`, `y`)
+    fn render_text(&mut self, lines: &[String], x: i32, y: i32) {
+        self.text.truncate(lines.len());
+        let mut y = y;
+        for (i, line) in lines.iter().enumerate() {
+            let cached = matches!(self.text.get(i), Some((drawn, _)) if drawn == line);
+            if !cached {
+                // SDL_ttf can't render an empty string
+                let rendered_text = self
+                    .font
+                    .render(if line.is_empty() { " " } else { line })
+                    .solid(Color::RGBA(0, 0, 0, 255))
+                    .unwrap();
+                let text_texture = rendered_text
+                    .as_texture(&self.graphics.texture_creator)
+                    .unwrap();
+                if i < self.text.len() {
+                    self.text[i] = (line.clone(), text_texture);
+                } else {
+                    self.text.push((line.clone(), text_texture));
+                }
+            }
+
+            let text_texture = &self.text[i].1;
+            let query = text_texture.query();
+            self.canvas
+                .copy(
+                    text_texture,
+                    None,
+                    Rect::new(x, y, query.width, query.height),
+                )
+                .unwrap();
+            y += query.height as i32;
+        }
     }
 }
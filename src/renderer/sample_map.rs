@@ -1,9 +1,20 @@
-use std::simd::Simd;
 use std::vec::Vec;
 
+use crate::renderer::{ByteReader, F64x4};
+
+/// Bytes `write_bytes` uses for each pixel, its sample count then four buffers of four lanes
+const PIXEL_BYTES: usize = 4 + 4 * 4 * 8;
+
+#[derive(Clone)]
 pub struct SampleMap {
-    colours: Vec<Simd<f64, 4>>,
+    colours: Vec<F64x4>,
     samples: Vec<u32>,
+    splats: Vec<F64x4>,
+    /// Summed squares of the camera samples, for their variance
+    squares: Vec<F64x4>,
+    /// Summed normal of the first thing each sample hit, with its distance in the last lane
+    aovs: Vec<F64x4>,
+    light_paths: u64,
     pub width: usize,
     pub height: usize,
     pub max_samples: u32,
@@ -12,8 +23,12 @@ pub struct SampleMap {
 impl Default for SampleMap {
     fn default() -> Self {
         Self {
-            colours: vec![Simd::splat(0.0); 1],
+            colours: vec![F64x4::splat(0.0); 1],
             samples: vec![0; 1],
+            splats: vec![F64x4::splat(0.0); 1],
+            squares: vec![F64x4::splat(0.0); 1],
+            aovs: vec![F64x4::splat(0.0); 1],
+            light_paths: 0,
             width: 1,
             height: 1,
             max_samples: 1,
@@ -27,8 +42,12 @@ impl SampleMap {
             max_samples,
             width,
             height,
-            colours: vec![Simd::splat(0.); width * height],
+            colours: vec![F64x4::splat(0.); width * height],
             samples: vec![0; width * height],
+            splats: vec![F64x4::splat(0.); width * height],
+            squares: vec![F64x4::splat(0.); width * height],
+            aovs: vec![F64x4::splat(0.); width * height],
+            light_paths: 0,
         }
     }
 
@@ -36,7 +55,7 @@ impl SampleMap {
         x + self.width * y
     }
 
-    pub fn set_value(&mut self, x: usize, y: usize, value: Simd<f64, 4>) {
+    pub fn set_value(&mut self, x: usize, y: usize, value: F64x4) {
         // Get the index into our array
         let index = self.calc_index(x, y);
         let samples = self.samples.get_mut(index).expect("Index out of bounds");
@@ -46,19 +65,58 @@ impl SampleMap {
             return;
         }
         let colour = self.colours.get_mut(index).expect("Index out of bounds");
+        let square = self.squares.get_mut(index).expect("Index out of bounds");
 
         // Have we sampled this ray before?
         if *samples == 0 {
             *colour = value;
+            *square = value * value;
         } else {
             *colour += value;
+            *square += value * value;
         }
 
         // Increment our sample
         *samples += 1;
     }
 
-    pub fn get_values(&self, x: usize, y: usize) -> (Simd<f64, 4>, u32) {
+    /// Adds to the auxiliary buffers, call this before `set_value` for the same sample
+    pub fn add_aov(&mut self, x: usize, y: usize, value: F64x4) {
+        let index = self.calc_index(x, y);
+        let samples = self.samples[index];
+        if samples == self.max_samples {
+            return;
+        }
+        let aov = self.aovs.get_mut(index).expect("Index out of bounds");
+        if samples == 0 {
+            *aov = value;
+        } else {
+            *aov += value;
+        }
+    }
+
+    /// The averaged normal and distance of the first hit
+    pub fn get_aov(&self, x: usize, y: usize) -> F64x4 {
+        let index = self.calc_index(x, y);
+        match self.samples[index] {
+            0 => F64x4::splat(0.),
+            samples => self.aovs[index] / F64x4::splat(samples as f64),
+        }
+    }
+
+    /// Unbiased variance of the camera samples, leaving out splatted light
+    pub fn get_variance(&self, x: usize, y: usize) -> F64x4 {
+        let (sum, samples) = self.get_values(x, y);
+        if samples < 2 {
+            return F64x4::splat(0.);
+        }
+        let n = F64x4::splat(samples as f64);
+        let mean = sum / n;
+        (self.squares[self.calc_index(x, y)] / n - mean * mean) * n
+            / F64x4::splat(samples as f64 - 1.0)
+    }
+
+    pub fn get_values(&self, x: usize, y: usize) -> (F64x4, u32) {
         let index = self.calc_index(x, y);
         (
             *self.colours.get(index).expect("Index out of range"),
@@ -66,7 +124,195 @@ impl SampleMap {
         )
     }
 
+    /// Adds light traced from the light sources which landed on this pixel
+    pub fn add_splat(&mut self, x: usize, y: usize, value: F64x4) {
+        let index = self.calc_index(x, y);
+        *self.splats.get_mut(index).expect("Index out of bounds") += value;
+    }
+
+    /// Counts a traced light path, whether or not it ended up splatting anything
+    pub fn add_light_path(&mut self) {
+        self.light_paths += 1;
+    }
+
+    /// The averaged camera samples plus this pixel's share of the splatted light
+    pub fn get_colour(&self, x: usize, y: usize) -> F64x4 {
+        let (colour, samples) = self.get_values(x, y);
+        let mut colour = if samples == 0 {
+            F64x4::splat(0.)
+        } else {
+            colour / F64x4::splat(samples as f64)
+        };
+
+        // Every light path could have landed on any pixel, so each one is worth a whole
+        // image's worth of samples. Camera rays spread each pixel over 1 / (width - 1) of the
+        // image across, and 1 / (height - 1) down
+        if self.light_paths > 0 {
+            let pixels = (self.width.max(2) - 1) * (self.height.max(2) - 1);
+            let scale = pixels as f64 / self.light_paths as f64;
+            colour += self.splats[self.calc_index(x, y)] * F64x4::splat(scale);
+        }
+        colour
+    }
+
+    pub fn write_bytes(&self, bytes: &mut Vec<u8>) {
+        for value in [self.width as u64, self.height as u64, self.light_paths] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        bytes.extend_from_slice(&self.max_samples.to_le_bytes());
+        for index in 0..self.width * self.height {
+            bytes.extend_from_slice(&self.samples[index].to_le_bytes());
+            for buffer in [&self.colours, &self.splats, &self.squares, &self.aovs] {
+                for value in buffer[index].to_array() {
+                    bytes.extend_from_slice(&value.to_le_bytes());
+                }
+            }
+        }
+    }
+
+    pub fn read_bytes(reader: &mut ByteReader) -> Result<Self, String> {
+        let width = reader.u64()? as usize;
+        let height = reader.u64()? as usize;
+        let light_paths = reader.u64()?;
+        let max_samples = reader.u32()?;
+
+        // Check the size against what's actually there before allocating anything for it
+        let pixels = width
+            .checked_mul(height)
+            .filter(|pixels| {
+                pixels
+                    .checked_mul(PIXEL_BYTES)
+                    .is_some_and(|size| size <= reader.remaining())
+            })
+            .ok_or_else(|| format!("a {width}x{height} image doesn't fit in what's left"))?;
+
+        let mut map = Self::new(max_samples, width, height);
+        map.light_paths = light_paths;
+        for index in 0..pixels {
+            map.samples[index] = reader.u32()?;
+            for buffer in [
+                &mut map.colours,
+                &mut map.splats,
+                &mut map.squares,
+                &mut map.aovs,
+            ] {
+                let mut lanes = [0.0; 4];
+                for lane in lanes.iter_mut() {
+                    *lane = reader.f64()?;
+                }
+                buffer[index] = F64x4::from_array(lanes);
+            }
+        }
+        Ok(map)
+    }
+
+    /// Adds another render of the same image into this one, as if one process had taken every
+    /// sample
+    pub fn merge(&mut self, other: &SampleMap) -> Result<(), String> {
+        if (self.width, self.height) != (other.width, other.height) {
+            return Err(format!(
+                "can't merge a {}x{} render into a {}x{} one",
+                other.width, other.height, self.width, self.height
+            ));
+        }
+        self.merge_at(other, 0, 0)?;
+        self.max_samples += other.max_samples;
+        Ok(())
+    }
+
+    /// Adds a render of part of the image, with its top left corner at (x, y)
+    pub fn merge_at(&mut self, other: &SampleMap, x: usize, y: usize) -> Result<(), String> {
+        let fits = |start: usize, size: usize, limit: usize| {
+            start.checked_add(size).is_some_and(|end| end <= limit)
+        };
+        if !fits(x, other.width, self.width) || !fits(y, other.height, self.height) {
+            return Err(format!(
+                "a {}x{} tile at ({x}, {y}) doesn't fit in a {}x{} render",
+                other.width, other.height, self.width, self.height
+            ));
+        }
+
+        for j in 0..other.height {
+            for i in 0..other.width {
+                let other_index = other.calc_index(i, j);
+                let index = self.calc_index(x + i, y + j);
+
+                // Buffers aren't cleared when samples are invalidated, so only trust sampled
+                // pixels
+                match (self.samples[index], other.samples[other_index]) {
+                    (_, 0) => {}
+                    (0, _) => {
+                        self.colours[index] = other.colours[other_index];
+                        self.squares[index] = other.squares[other_index];
+                        self.aovs[index] = other.aovs[other_index];
+                    }
+                    _ => {
+                        self.colours[index] += other.colours[other_index];
+                        self.squares[index] += other.squares[other_index];
+                        self.aovs[index] += other.aovs[other_index];
+                    }
+                }
+                self.samples[index] += other.samples[other_index];
+                self.splats[index] += other.splats[other_index];
+            }
+        }
+        self.light_paths += other.light_paths;
+        Ok(())
+    }
+
+    /// Mean, fewest and most samples of any pixel
+    pub fn sample_stats(&self) -> (f64, u32, u32) {
+        let total: u64 = self.samples.iter().map(|&n| n as u64).sum();
+        let min = self.samples.iter().copied().min().unwrap_or(0);
+        let max = self.samples.iter().copied().max().unwrap_or(0);
+        (total as f64 / self.samples.len() as f64, min, max)
+    }
+
+    /// Camera samples taken across the whole image
+    pub fn total_samples(&self) -> u64 {
+        self.samples.iter().map(|&n| n as u64).sum()
+    }
+
     pub fn invalidate_samples(&mut self) {
         self.samples = vec![0; self.width * self.height];
+        self.splats = vec![F64x4::splat(0.); self.width * self.height];
+        self.light_paths = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::renderer::{F64x4, SampleMap};
+
+    #[test]
+    fn test_sample_stats() {
+        let mut sample_map = SampleMap::new(3, 2, 2);
+        for _ in 0..5 {
+            sample_map.set_value(0, 0, F64x4::splat(1.0));
+        }
+        sample_map.set_value(1, 1, F64x4::splat(1.0));
+
+        // Samples past the limit aren't counted
+        assert_eq!(sample_map.sample_stats(), (1.0, 0, 3));
+        assert_eq!(sample_map.total_samples(), 4);
+
+        sample_map.invalidate_samples();
+        assert_eq!(sample_map.sample_stats(), (0.0, 0, 0));
+    }
+
+    #[test]
+    fn test_variance() {
+        let mut sample_map = SampleMap::new(8, 1, 1);
+        for value in [1.0, 2.0, 3.0, 6.0] {
+            sample_map.set_value(0, 0, F64x4::splat(value));
+        }
+        // Mean 3, squared differences 4 + 1 + 0 + 9 over n - 1
+        assert_eq!(sample_map.get_variance(0, 0).to_array(), [14.0 / 3.0; 4]);
+
+        // Survives being split up and merged back together
+        let mut other = SampleMap::new(8, 1, 1);
+        other.set_value(0, 0, F64x4::splat(3.0));
+        sample_map.merge_at(&other, 0, 0).unwrap();
+        assert!((sample_map.get_variance(0, 0)[0] - 3.5).abs() < 1e-12);
     }
 }
@@ -0,0 +1,165 @@
+use std::cell::Cell;
+use std::sync::Arc;
+
+use crate::hittable::{ConstantMedium, HitRecord, Hittable, HittableList, Sampleable, Sphere};
+use crate::light::{DiffuseAreaLight, Light};
+use crate::material::{DiffuseLight, Isotropic, Lambertian, Principled};
+use crate::renderer::{random_double, Camera, Fog, Ray, Sky, Vec3};
+
+thread_local! {
+    static RAYS: Cell<u64> = const { Cell::new(0) };
+}
+
+/// Rays this thread has traced through any scene, counting hits and shadow rays
+pub fn rays_traced() -> u64 {
+    RAYS.with(|rays| rays.get())
+}
+
+/// Everything an integrator needs to know about the world
+pub struct Scene {
+    pub camera: Camera,
+    pub world: HittableList,
+    pub fog: Option<Fog>,
+    pub lights: Vec<Arc<dyn Light>>,
+    /// Daylight for outdoor scenes, otherwise the old gradient is used
+    pub sky: Option<Sky>,
+}
+
+impl Scene {
+    pub fn new(world: HittableList) -> Self {
+        Self {
+            camera: Camera::new(),
+            world,
+            fog: None,
+            lights: Vec::new(),
+            sky: None,
+        }
+    }
+
+    /// The scene rendered when no scene file is given
+    pub fn builtin() -> Self {
+        let mut hittables = HittableList::new();
+        hittables.add_hittable(Box::new(Sphere::new(Vec3::new(0.0, 0.0, -1.0), 0.5)));
+        hittables.add_hittable(Box::new(Sphere::with_material(
+            Vec3::new(0.0, -100.5, -1.0),
+            100.0,
+            Arc::new(Lambertian::new(Vec3::new(0.5, 0.5, 0.5))),
+        )));
+        hittables.add_hittable(Box::new(Sphere::with_material(
+            Vec3::new(-1.1, -0.1, -1.4),
+            0.4,
+            Arc::new(Principled {
+                clearcoat: 1.0,
+                ..Principled::new(Vec3::new(0.8, 0.1, 0.1), 0.0, 0.4)
+            }),
+        )));
+        hittables.add_hittable(Box::new(ConstantMedium::new(
+            Box::new(Sphere::new(Vec3::new(1.1, -0.1, -1.4), 0.4)),
+            2.0,
+            Arc::new(Isotropic::new(Vec3::new(0.9, 0.9, 0.9))),
+        )));
+
+        let mut scene = Scene::new(hittables);
+        let emission = Arc::new(DiffuseLight::new(Vec3::new(4.0, 3.6, 3.0)));
+        scene.add_area_light(
+            Arc::new(Sphere::with_material(
+                Vec3::new(0.0, 1.4, -1.2),
+                0.25,
+                emission.clone(),
+            )),
+            emission,
+        );
+        scene
+    }
+
+    pub fn add_light(&mut self, light: Arc<dyn Light>) {
+        self.lights.push(light);
+    }
+
+    /// Lights the scene with a daylight sky and the sun that goes with it
+    pub fn set_sky(&mut self, sky: Sky) {
+        self.add_light(Arc::new(sky.sun_light()));
+        self.sky = Some(sky);
+    }
+
+    /// Adds an emitting shape to the world and the list of lights. The shape should have been
+    /// created with `emission` as its material
+    pub fn add_area_light(&mut self, shape: Arc<dyn Sampleable>, emission: Arc<DiffuseLight>) {
+        self.world.add_hittable(Box::new(shape.clone()));
+        self.add_light(Arc::new(DiffuseAreaLight::new(shape, emission)));
+    }
+
+    /// Picks one of the lights uniformly, along with the chance of picking it
+    pub fn pick_light(&self) -> Option<(&Arc<dyn Light>, f64)> {
+        if self.lights.is_empty() {
+            return None;
+        }
+        let count = self.lights.len();
+        let index = ((random_double() * count as f64) as usize).min(count - 1);
+        Some((&self.lights[index], 1.0 / count as f64))
+    }
+
+    /// The chance of `pick_light` choosing any given light
+    pub fn light_pdf(&self) -> f64 {
+        if self.lights.is_empty() {
+            0.0
+        } else {
+            1.0 / self.lights.len() as f64
+        }
+    }
+
+    /// The light whose surface was hit, if any
+    pub fn light_for_hit(&self, rec: &HitRecord) -> Option<&Arc<dyn Light>> {
+        self.lights.iter().find(|light| light.emits_from(rec))
+    }
+
+    /// Finds the nearest surface or fog scattering event along the ray
+    pub fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        RAYS.with(|rays| rays.set(rays.get() + 1));
+        let mut hit = self.world.hit(ray, t_min, t_max, rec);
+
+        // The fog may scatter the ray before it reaches the surface
+        if let Some(fog) = &self.fog {
+            let t_max = if hit { rec.t } else { t_max };
+            hit |= fog.hit(ray, t_min, t_max, rec);
+        }
+        hit
+    }
+
+    /// The fraction of light making it along the ray between `t_min` and `t_max`
+    pub fn transmittance(&self, ray: &Ray, t_min: f64, t_max: f64) -> f64 {
+        RAYS.with(|rays| rays.set(rays.get() + 1));
+        let transmittance = self.world.transmittance(ray, t_min, t_max);
+        match &self.fog {
+            Some(fog) if transmittance > 0.0 => {
+                transmittance * fog.transmittance(ray, t_min, t_max)
+            }
+            _ => transmittance,
+        }
+    }
+
+    /// The fraction of light making it in a straight line between two points
+    pub fn visibility(&self, from: Vec3, to: Vec3) -> f64 {
+        let to_target = to - from;
+        let distance = to_target.length();
+        let ray = Ray::new(from, to_target / distance);
+        self.transmittance(&ray, 0.001, distance - 0.001)
+    }
+
+    /// A sphere around everything in the world, as a centre and radius
+    pub fn bounding_sphere(&self) -> Option<(Vec3, f64)> {
+        let bounds = self.world.bounding_box()?;
+        let centre = 0.5 * (bounds.min + bounds.max);
+        Some((centre, (bounds.max - centre).length()))
+    }
+
+    /// Light arriving from the sky for rays which escape the scene
+    pub fn background(&self, ray: &Ray) -> Vec3 {
+        if let Some(sky) = &self.sky {
+            return sky.radiance(ray.dir);
+        }
+        let unit_direction = ray.dir.unit_vector();
+        let t = 0.5 * (unit_direction.y() + 1.0);
+        (1.0 - t) * Vec3::new(1.0, 1.0, 1.0) + t * Vec3::new(0.5, 0.7, 1.0)
+    }
+}
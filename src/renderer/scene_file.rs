@@ -0,0 +1,447 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+use crate::hittable::{Disk, HittableList, Quad, Sampleable, Sphere};
+use crate::light::{DirectionalLight, PointLight, SpotLight};
+use crate::material::{
+    Dielectric, DiffuseLight, HenyeyGreenstein, Ior, Isotropic, Lambertian, Material, Metal,
+    Principled,
+};
+use crate::renderer::{Camera, Fog, Scene, Sky, Vec3};
+
+/// One line of a scene file, `kind [name] key=value key=value ...`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    /// Line number in the file, counting from one
+    pub line: usize,
+    pub kind: String,
+    pub name: Option<String>,
+    pub params: Vec<(String, String)>,
+}
+
+impl Entry {
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Replaces a parameter, adding it if it wasn't there before
+    pub fn set(&mut self, key: &str, value: String) {
+        match self.params.iter_mut().find(|(k, _)| k == key) {
+            Some(param) => param.1 = value,
+            None => self.params.push((key.to_string(), value)),
+        }
+    }
+
+    fn error(&self, message: impl fmt::Display) -> String {
+        format!("line {}: {message}", self.line)
+    }
+
+    pub fn float(&self, key: &str, default: Option<f64>) -> Result<f64, String> {
+        match self.get(key) {
+            Some(value) => value
+                .parse()
+                .map_err(|_| self.error(format!("'{key}' should be a number, not '{value}'"))),
+            None => default.ok_or_else(|| self.error(format!("missing '{key}'"))),
+        }
+    }
+
+    pub fn vec3(&self, key: &str, default: Option<Vec3>) -> Result<Vec3, String> {
+        let value = match self.get(key) {
+            Some(value) => value,
+            None => return default.ok_or_else(|| self.error(format!("missing '{key}'"))),
+        };
+        let parts: Vec<f64> = value
+            .split(',')
+            .map(|part| part.trim().parse())
+            .collect::<Result<_, _>>()
+            .map_err(|_| self.error(format!("'{key}' should be x,y,z, not '{value}'")))?;
+        match parts[..] {
+            [x, y, z] => Ok(Vec3::new(x, y, z)),
+            // A single number means a grey colour
+            [v] => Ok(Vec3::new(v, v, v)),
+            _ => Err(self.error(format!("'{key}' should be x,y,z, not '{value}'"))),
+        }
+    }
+}
+
+impl fmt::Display for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.kind)?;
+        if let Some(name) = &self.name {
+            write!(f, " {name}")?;
+        }
+        for (key, value) in &self.params {
+            write!(f, " {key}={value}")?;
+        }
+        Ok(())
+    }
+}
+
+/// A shape in the built scene and the index of the entry it came from
+pub type EntryObject = (usize, Arc<dyn Sampleable>);
+
+/// A plain text description of a scene, for example
+///
+/// ```text
+/// material floor type=lambertian albedo=0.5,0.5,0.5
+/// sphere center=0,-100.5,-1 radius=100 material=floor
+/// point_light position=0,2,-1 intensity=5,5,5
+/// ```
+///
+/// Objects using a `light` material are added as area lights when their shape can be sampled
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SceneFile {
+    pub entries: Vec<Entry>,
+}
+
+impl SceneFile {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut entries = Vec::new();
+
+        for (i, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let mut words = line.split_whitespace();
+            let mut entry = Entry {
+                line: i + 1,
+                kind: words.next().unwrap_or_default().to_string(),
+                name: None,
+                params: Vec::new(),
+            };
+            for word in words {
+                match word.split_once('=') {
+                    Some((key, value)) => entry.params.push((key.to_string(), value.to_string())),
+                    None if entry.name.is_none() && entry.params.is_empty() => {
+                        entry.name = Some(word.to_string())
+                    }
+                    None => return Err(entry.error(format!("expected key=value, not '{word}'"))),
+                }
+            }
+            entries.push(entry);
+        }
+
+        Ok(Self { entries })
+    }
+
+    pub fn load(path: &str) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("{path}: {e}"))?;
+        Self::parse(&text).map_err(|e| format!("{path}: {e}"))
+    }
+
+    pub fn build(&self) -> Result<Scene, String> {
+        Ok(self.build_with_objects()?.0)
+    }
+
+    /// Builds the scene along with every object in it and the index of the entry it came from
+    pub fn build_with_objects(&self) -> Result<(Scene, Vec<EntryObject>), String> {
+        let mut objects = Vec::new();
+        let mut materials: HashMap<&str, Arc<dyn Material>> = HashMap::new();
+        let mut emitters: HashMap<&str, Arc<DiffuseLight>> = HashMap::new();
+        let mut area_lights: Vec<(Arc<dyn Sampleable>, Arc<DiffuseLight>)> = Vec::new();
+        let mut world = HittableList::new();
+        let mut scene_lights = Vec::new();
+        let mut fog = None;
+        let mut sky = None;
+        let mut camera = None;
+
+        for (index, entry) in self.entries.iter().enumerate() {
+            match entry.kind.as_str() {
+                "material" => {
+                    let name = entry
+                        .name
+                        .as_deref()
+                        .ok_or_else(|| entry.error("materials need a name"))?;
+                    let kind = entry
+                        .get("type")
+                        .ok_or_else(|| entry.error("missing 'type'"))?;
+                    let material: Arc<dyn Material> = match kind {
+                        "lambertian" => Arc::new(Lambertian::new(entry.vec3("albedo", None)?)),
+                        "metal" => Arc::new(Metal::new(
+                            entry.vec3("albedo", None)?,
+                            entry.float("fuzz", Some(0.0))?,
+                        )),
+                        "dielectric" => {
+                            let ior = entry.float("ior", Some(1.5))?;
+                            match entry.float("cauchy_b", Some(0.0))? {
+                                0.0 => Arc::new(Dielectric::new(ior)),
+                                b => Arc::new(Dielectric::dispersive(Ior::cauchy(ior, b))),
+                            }
+                        }
+                        "principled" => Arc::new(Principled {
+                            clearcoat: entry.float("clearcoat", Some(0.0))?,
+                            transmission: entry.float("transmission", Some(0.0))?,
+                            ior: entry.float("ior", Some(1.5))?,
+                            ..Principled::new(
+                                entry.vec3("colour", None)?,
+                                entry.float("metallic", Some(0.0))?,
+                                entry.float("roughness", Some(0.5))?,
+                            )
+                        }),
+                        "isotropic" => Arc::new(Isotropic::new(entry.vec3("albedo", None)?)),
+                        "henyey_greenstein" => Arc::new(HenyeyGreenstein::new(
+                            entry.vec3("albedo", None)?,
+                            entry.float("g", Some(0.0))?,
+                        )),
+                        "light" => {
+                            let light = Arc::new(DiffuseLight::new(entry.vec3("emit", None)?));
+                            emitters.insert(name, light.clone());
+                            light
+                        }
+                        _ => return Err(entry.error(format!("unknown material type '{kind}'"))),
+                    };
+                    materials.insert(name, material);
+                }
+                "sphere" | "quad" | "disk" => {
+                    let material = match entry.get("material") {
+                        Some(name) => Some(
+                            materials
+                                .get(name)
+                                .cloned()
+                                .ok_or_else(|| entry.error(format!("no material '{name}'")))?,
+                        ),
+                        None => None,
+                    };
+                    let shape: Arc<dyn Sampleable> = match (entry.kind.as_str(), material) {
+                        ("sphere", Some(material)) => Arc::new(Sphere::with_material(
+                            entry.vec3("center", None)?,
+                            entry.float("radius", None)?,
+                            material,
+                        )),
+                        ("sphere", None) => Arc::new(Sphere::new(
+                            entry.vec3("center", None)?,
+                            entry.float("radius", None)?,
+                        )),
+                        ("quad", Some(material)) => Arc::new(Quad::with_material(
+                            entry.vec3("corner", None)?,
+                            entry.vec3("u", None)?,
+                            entry.vec3("v", None)?,
+                            material,
+                        )),
+                        ("quad", None) => Arc::new(Quad::new(
+                            entry.vec3("corner", None)?,
+                            entry.vec3("u", None)?,
+                            entry.vec3("v", None)?,
+                        )),
+                        (_, Some(material)) => Arc::new(Disk::with_material(
+                            entry.vec3("center", None)?,
+                            entry.vec3("normal", None)?,
+                            entry.float("radius", None)?,
+                            material,
+                        )),
+                        (_, None) => Arc::new(Disk::new(
+                            entry.vec3("center", None)?,
+                            entry.vec3("normal", None)?,
+                            entry.float("radius", None)?,
+                        )),
+                    };
+
+                    objects.push((index, shape.clone()));
+                    match entry.get("material").and_then(|name| emitters.get(name)) {
+                        Some(emission) => area_lights.push((shape, emission.clone())),
+                        None => world.add_hittable(Box::new(shape)),
+                    }
+                }
+                "point_light" => scene_lights.push(Arc::new(PointLight::new(
+                    entry.vec3("position", None)?,
+                    entry.vec3("intensity", None)?,
+                )) as Arc<_>),
+                "spot_light" => {
+                    let cone_angle = entry.float("cone_angle", Some(30.0))?;
+                    scene_lights.push(Arc::new(SpotLight::new(
+                        entry.vec3("position", None)?,
+                        entry.vec3("direction", None)?,
+                        entry.vec3("intensity", None)?,
+                        cone_angle,
+                        entry.float("falloff_start", Some(cone_angle * 0.8))?,
+                    )) as Arc<_>)
+                }
+                "directional_light" => scene_lights.push(Arc::new(DirectionalLight::new(
+                    entry.vec3("direction", None)?,
+                    entry.vec3("irradiance", None)?,
+                    entry.float("angular_diameter", Some(0.0))?,
+                )) as Arc<_>),
+                "fog" => {
+                    let phase = Arc::new(HenyeyGreenstein::new(
+                        entry.vec3("albedo", Some(Vec3::new(0.9, 0.9, 0.9)))?,
+                        entry.float("g", Some(0.0))?,
+                    ));
+                    fog = Some(Fog::new(
+                        entry.float("density", None)?,
+                        phase,
+                        entry.float("extent", Some(100.0))?,
+                    ));
+                }
+                "sky" => {
+                    sky = Some(Sky::new(
+                        entry.float("elevation", Some(45.0))?,
+                        entry.float("azimuth", Some(0.0))?,
+                        entry.float("turbidity", Some(3.0))?,
+                    ));
+                }
+                // Whoever renders it sets the aspect ratio to match their image
+                "camera" => {
+                    let origin = entry.vec3("origin", Some(Vec3::new(0.0, 0.0, 0.0)))?;
+                    let look_at = entry.vec3("look_at", Some(Vec3::new(0.0, 0.0, -1.0)))?;
+                    let up = entry.vec3("up", Some(Vec3::new(0.0, 1.0, 0.0)))?;
+                    let vfov = entry.float("vfov", Some(90.0))?;
+                    // Any of these leave the camera without a direction and the image black
+                    let forward = look_at - origin;
+                    if forward.length() == 0.0 {
+                        return Err(entry.error("'look_at' has to be away from 'origin'"));
+                    }
+                    let side = forward.unit_vector().cross(&up.unit_vector()).length();
+                    if side.is_nan() || side < 1e-6 {
+                        return Err(entry.error("'up' can't point along the view"));
+                    }
+                    if vfov.is_nan() || vfov <= 0.0 || vfov >= 180.0 {
+                        return Err(entry.error(format!(
+                            "'vfov' should be between 0 and 180 degrees, not {vfov}"
+                        )));
+                    }
+                    camera = Some(Camera::look_at(origin, look_at, up, vfov, 16.0 / 9.0));
+                }
+                kind => return Err(entry.error(format!("unknown entry '{kind}'"))),
+            }
+        }
+
+        let mut scene = Scene::new(world);
+        scene.fog = fog;
+        for (shape, emission) in area_lights {
+            scene.add_area_light(shape, emission);
+        }
+        for light in scene_lights {
+            scene.add_light(light);
+        }
+        if let Some(sky) = sky {
+            scene.set_sky(sky);
+        }
+        if let Some(camera) = camera {
+            scene.camera = camera;
+        }
+        Ok((scene, objects))
+    }
+}
+
+impl fmt::Display for SceneFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for entry in &self.entries {
+            writeln!(f, "{entry}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hittable::HitRecord;
+    use crate::renderer::{Ray, SceneFile, Vec3};
+
+    #[test]
+    fn test_parse_and_build() {
+        let text = "
+            # A floor lit by every kind of light
+            material floor type=lambertian albedo=0.5
+            material lamp type=light emit=4,4,4
+            sphere center=0,-100.5,-1 radius=100 material=floor
+            quad corner=-0.5,2,-1.5 u=1,0,0 v=0,0,1 material=lamp
+            point_light position=0,2,-1 intensity=5,5,5
+            spot_light position=1,2,-1 direction=0,-1,0 intensity=10 cone_angle=25
+            directional_light direction=-1,-1,-1 irradiance=2 angular_diameter=0.5
+        ";
+        let file = SceneFile::parse(text).unwrap();
+        assert_eq!(file.entries.len(), 7);
+        assert_eq!(file.entries[0].line, 3);
+        assert_eq!(file.entries[0].name.as_deref(), Some("floor"));
+        assert_eq!(file.entries[2].get("radius"), Some("100"));
+
+        let scene = file.build().unwrap();
+        assert_eq!(scene.lights.len(), 4);
+
+        // Writing it out again gives the same scene back
+        assert_eq!(
+            SceneFile::parse(&file.to_string()).unwrap().entries.len(),
+            7
+        );
+
+        let err = SceneFile::parse("sphere center=0,0,0 radius=x")
+            .unwrap()
+            .build()
+            .err()
+            .unwrap();
+        assert!(err.starts_with("line 1:"));
+        let err = SceneFile::parse("\nsphere center=0,0,0 radius=1 material=nope")
+            .unwrap()
+            .build()
+            .err()
+            .unwrap();
+        assert!(err.contains("line 2") && err.contains("nope"));
+    }
+
+    #[test]
+    fn test_camera_entry() {
+        let default = SceneFile::parse("").unwrap().build().unwrap().camera;
+        let scene = SceneFile::parse("camera origin=0,1,3 look_at=0,0,-1 vfov=40")
+            .unwrap()
+            .build()
+            .unwrap();
+        assert_eq!(scene.camera.origin, Vec3::new(0.0, 1.0, 3.0));
+        assert!((scene.camera.direction() - default.direction()).length() > 0.1);
+        assert!(scene.camera.vertical.length() < default.vertical.length());
+
+        for (bad, key) in [
+            ("camera origin=0,1,3 look_at=0,1,3", "look_at"),
+            ("camera origin=0,1,3 look_at=0,0,3", "up"),
+            ("camera up=0,0,0", "up"),
+            ("camera vfov=180", "vfov"),
+            ("camera vfov=-10", "vfov"),
+        ] {
+            let err = SceneFile::parse(&format!("\n{bad}"))
+                .unwrap()
+                .build()
+                .err()
+                .unwrap();
+            assert!(
+                err.starts_with("line 2:") && err.contains(key),
+                "{bad}: {err}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_dispersive_glass() {
+        let text = "
+            material plain type=dielectric ior=1.5
+            material prism type=dielectric ior=1.5 cauchy_b=0.004
+            sphere center=0,0,-1 radius=0.5 material=plain
+            sphere center=0,0,-3 radius=0.5 material=prism
+        ";
+        let (_, objects) = SceneFile::parse(text)
+            .unwrap()
+            .build_with_objects()
+            .unwrap();
+        let dispersive: Vec<bool> = objects
+            .iter()
+            .map(|(_, object)| {
+                let mut rec = HitRecord::default();
+                let ray = Ray::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, -1.0));
+                assert!(object.hit(&ray, 0.001, f64::INFINITY, &mut rec));
+                rec.material.unwrap().is_dispersive()
+            })
+            .collect();
+        assert_eq!(dispersive, [false, true]);
+
+        let err = SceneFile::parse("material m type=dielectric cauchy_b=lots")
+            .unwrap()
+            .build()
+            .err()
+            .unwrap();
+        assert!(err.contains("cauchy_b"));
+    }
+}
@@ -0,0 +1,102 @@
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::renderer::SceneFile;
+
+/// Often enough to feel instant after saving without checking the file every frame
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Notices when a scene file is saved and loads it again
+pub struct SceneWatcher {
+    path: String,
+    /// None while the file can't be found
+    modified: Option<SystemTime>,
+    last_check: Instant,
+}
+
+fn modified(path: &str) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+impl SceneWatcher {
+    /// Starts from the file as it is now, so it's only reloaded once it changes
+    pub fn new(path: &str, now: Instant) -> Self {
+        Self {
+            path: path.to_string(),
+            modified: modified(path),
+            last_check: now,
+        }
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// The file parsed again if it's changed since last time. A file which vanishes is only
+    /// reported once, and loaded again whenever it comes back
+    pub fn poll(&mut self, now: Instant) -> Option<Result<SceneFile, String>> {
+        if now.duration_since(self.last_check) < POLL_INTERVAL {
+            return None;
+        }
+        self.last_check = now;
+
+        let modified = modified(&self.path);
+        if modified == self.modified {
+            return None;
+        }
+        self.modified = modified;
+        match modified {
+            Some(_) => Some(SceneFile::load(&self.path)),
+            None => Some(Err(format!("{}: can't be read any more", self.path))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::time::{Duration, Instant, SystemTime};
+
+    use crate::renderer::SceneWatcher;
+
+    #[test]
+    fn test_scene_watcher() {
+        let path = std::env::temp_dir().join("watch_test.scene");
+        let path = path.to_str().unwrap().to_string();
+        let save = |text: &str, age: u64| {
+            std::fs::write(&path, text).unwrap();
+            // Saves can land within the filesystem's timestamp resolution, so set it outright
+            let time = SystemTime::now() - Duration::from_secs(age);
+            File::options()
+                .write(true)
+                .open(&path)
+                .unwrap()
+                .set_modified(time)
+                .unwrap();
+        };
+
+        save("sphere center=0,0,-1 radius=0.5", 60);
+        let start = Instant::now();
+        let mut watcher = SceneWatcher::new(&path, start);
+        let later = |n: u64| start + Duration::from_secs(n);
+        assert!(watcher.poll(later(1)).is_none());
+
+        // Checked no more often than the interval
+        save("sphere center=0,0,-1 radius=1", 30);
+        assert!(watcher.poll(later(1)).is_none());
+        let file = watcher.poll(later(2)).unwrap().unwrap();
+        assert_eq!(file.entries[0].get("radius"), Some("1"));
+        assert!(watcher.poll(later(3)).is_none());
+
+        // Mistakes are reported, then fixed
+        save("sphere center=0,0,-1 radius", 20);
+        assert!(watcher.poll(later(4)).unwrap().is_err());
+        save("sphere center=0,0,-1 radius=2", 10);
+        assert!(watcher.poll(later(5)).unwrap().is_ok());
+
+        std::fs::remove_file(&path).unwrap();
+        assert!(watcher.poll(later(6)).unwrap().is_err());
+        assert!(watcher.poll(later(7)).is_none());
+        save("sphere center=0,0,-1 radius=3", 5);
+        assert!(watcher.poll(later(8)).unwrap().is_ok());
+    }
+}
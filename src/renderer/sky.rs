@@ -0,0 +1,171 @@
+use std::f64::consts::PI;
+
+use crate::light::DirectionalLight;
+use crate::renderer::{xyz_to_rgb, Vec3};
+
+/// Turns the model's kilocandelas into something the renderer is happy with
+const SCALE: f64 = 0.05;
+/// Illuminance from the sun above the atmosphere, in kilolux
+const SOLAR_ILLUMINANCE: f64 = 128.0;
+/// The sun as seen from the ground, in degrees
+const SUN_ANGULAR_DIAMETER: f64 = 0.53;
+
+/// Coefficients of the Perez sky distribution
+#[derive(Debug, Clone, Copy)]
+struct Perez([f64; 5]);
+
+impl Perez {
+    fn f(&self, cos_theta: f64, gamma: f64) -> f64 {
+        let [a, b, c, d, e] = self.0;
+        (1.0 + a * (b / cos_theta).exp()) * (1.0 + c * (d * gamma).exp() + e * gamma.cos().powi(2))
+    }
+}
+
+/// Preetham, Shirley and Smits' analytic daylight model. The sky doesn't include the sun
+/// itself, get that from `sun_light` so it can be sampled directly
+#[derive(Debug, Clone)]
+pub struct Sky {
+    /// Degrees above the horizon
+    pub elevation: f64,
+    /// Degrees clockwise from straight ahead, looking down -z
+    pub azimuth: f64,
+    /// How hazy the air is, 2 is very clear and 10 is thick haze
+    pub turbidity: f64,
+    sun_direction: Vec3,
+    /// Luminance and chromaticity straight up
+    zenith: [f64; 3],
+    perez: [Perez; 3],
+}
+
+impl Sky {
+    pub fn new(elevation: f64, azimuth: f64, turbidity: f64) -> Self {
+        let (el, az) = (elevation.to_radians(), azimuth.to_radians());
+        let sun_direction = Vec3::new(el.cos() * az.sin(), el.sin(), -el.cos() * az.cos());
+
+        let t = turbidity;
+        let theta_s = PI / 2.0 - el.max(0.0);
+        let chi = (4.0 / 9.0 - t / 120.0) * (PI - 2.0 * theta_s);
+        let luminance = (4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192;
+
+        let theta = [theta_s.powi(3), theta_s.powi(2), theta_s, 1.0];
+        let dot = |c: [f64; 4]| c.iter().zip(theta).map(|(c, t)| c * t).sum::<f64>();
+        let x = t * t * dot([0.00166, -0.00375, 0.00209, 0.0])
+            + t * dot([-0.02903, 0.06377, -0.03202, 0.00394])
+            + dot([0.11693, -0.21196, 0.06052, 0.25886]);
+        let y = t * t * dot([0.00275, -0.00610, 0.00317, 0.0])
+            + t * dot([-0.04214, 0.08970, -0.04153, 0.00516])
+            + dot([0.15346, -0.26756, 0.06670, 0.26688]);
+
+        let perez = [
+            Perez([
+                0.1787 * t - 1.4630,
+                -0.3554 * t + 0.4275,
+                -0.0227 * t + 5.3251,
+                0.1206 * t - 2.5771,
+                -0.0670 * t + 0.3703,
+            ]),
+            Perez([
+                -0.0193 * t - 0.2592,
+                -0.0665 * t + 0.0008,
+                -0.0004 * t + 0.2125,
+                -0.0641 * t - 0.8989,
+                -0.0033 * t + 0.0452,
+            ]),
+            Perez([
+                -0.0167 * t - 0.2608,
+                -0.0950 * t + 0.0092,
+                -0.0079 * t + 0.2102,
+                -0.0441 * t - 1.6537,
+                -0.0109 * t + 0.0529,
+            ]),
+        ];
+
+        Self {
+            elevation,
+            azimuth,
+            turbidity,
+            sun_direction,
+            zenith: [luminance, x, y],
+            perez,
+        }
+    }
+
+    /// Unit vector pointing at the sun
+    pub fn sun_direction(&self) -> Vec3 {
+        self.sun_direction
+    }
+
+    /// Light from the sky arriving along `-dir`. Below the horizon the sky just carries on
+    /// from the horizon
+    pub fn radiance(&self, dir: Vec3) -> Vec3 {
+        let dir = dir.unit_vector();
+        let cos_theta = dir.y().max(0.01);
+        let theta_s = PI / 2.0 - self.elevation.to_radians().max(0.0);
+        let gamma = dir.dot(&self.sun_direction).clamp(-1.0, 1.0).acos();
+
+        // Luminance and chromaticity each follow their own distribution over the sky
+        let [luminance, x, y] = [0, 1, 2].map(|i| {
+            let perez = self.perez[i];
+            self.zenith[i] * perez.f(cos_theta, gamma) / perez.f(1.0, theta_s)
+        });
+
+        let xyz = Vec3::new(x / y * luminance, luminance, (1.0 - x - y) / y * luminance);
+        let rgb = xyz_to_rgb(xyz) * SCALE;
+        Vec3::new(rgb.x().max(0.0), rgb.y().max(0.0), rgb.z().max(0.0))
+    }
+
+    /// The sun after passing through the atmosphere, following the paper's Rayleigh and
+    /// aerosol scattering terms at a red, green and blue wavelength
+    pub fn sun_light(&self) -> DirectionalLight {
+        let theta_s = (90.0 - self.elevation).clamp(0.0, 93.0);
+        let air_mass = 1.0 / (theta_s.to_radians().cos() + 0.15 * (93.885 - theta_s).powf(-1.253));
+        let beta = 0.04608 * self.turbidity - 0.04586;
+
+        let transmittance = |lambda: f64| {
+            let rayleigh = (-0.008735 * lambda.powf(-4.08) * air_mass).exp();
+            let aerosol = (-beta * lambda.powf(-1.3) * air_mass).exp();
+            rayleigh * aerosol
+        };
+        let colour = Vec3::new(
+            transmittance(0.65),
+            transmittance(0.55),
+            transmittance(0.45),
+        );
+
+        DirectionalLight::new(
+            -self.sun_direction,
+            SOLAR_ILLUMINANCE * SCALE * colour,
+            SUN_ANGULAR_DIAMETER,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::light::Light;
+    use crate::renderer::{Sky, Vec3};
+
+    #[test]
+    fn test_preetham_sky() {
+        let sky = Sky::new(30.0, 0.0, 3.0);
+        let luminance = |v: Vec3| 0.2126 * v.x() + 0.7152 * v.y() + 0.0722 * v.z();
+
+        // Brighter around the sun than on the far side of the sky, and blue overhead
+        let near_sun = sky.radiance(Vec3::new(0.0, 0.6, -1.0));
+        let away = sky.radiance(Vec3::new(0.0, 0.6, 1.0));
+        assert!(luminance(near_sun) > luminance(away));
+        let zenith = sky.radiance(Vec3::new(0.0, 1.0, 0.0));
+        assert!(zenith.z() > zenith.x());
+
+        // The sun goes red as it sets
+        let sunset = Sky::new(3.0, 0.0, 3.0).sun_light();
+        let noon = Sky::new(80.0, 0.0, 3.0).sun_light();
+        let origin = Vec3::new(0.0, 0.0, 0.0);
+        let sunset = sunset.sample_li(origin).unwrap();
+        let noon = noon.sample_li(origin).unwrap();
+        let redness = |v: Vec3| v.x() / v.z();
+        assert!(redness(sunset.radiance) > redness(noon.radiance));
+        assert!(luminance(noon.radiance / noon.pdf) > luminance(sunset.radiance / sunset.pdf));
+        assert!(noon.wi.y() > 0.98);
+    }
+}
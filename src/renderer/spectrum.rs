@@ -0,0 +1,176 @@
+use crate::renderer::{random_double, Vec3};
+
+/// The range of wavelengths sampled, in nanometres
+pub const LAMBDA_MIN: f64 = 360.0;
+pub const LAMBDA_MAX: f64 = 830.0;
+
+/// Picks a hero wavelength uniformly and spreads two more evenly around the range from it, one
+/// per channel of a `Vec3`
+pub fn sample_wavelengths() -> Vec3 {
+    let range = LAMBDA_MAX - LAMBDA_MIN;
+    let hero = random_double() * range;
+    let rotate = |offset: f64| LAMBDA_MIN + (hero + offset * range / 3.0) % range;
+    Vec3::new(rotate(0.0), rotate(1.0), rotate(2.0))
+}
+
+// Smits' basis spectra for upsampling reflectances, ten bins from 380nm to 720nm
+const SMITS_WHITE: [f64; 10] = [
+    1.0000, 1.0000, 0.9999, 0.9993, 0.9992, 0.9998, 1.0000, 1.0000, 1.0000, 1.0000,
+];
+const SMITS_CYAN: [f64; 10] = [
+    0.9710, 0.9426, 1.0007, 1.0007, 1.0007, 1.0007, 0.1564, 0.0000, 0.0000, 0.0000,
+];
+const SMITS_MAGENTA: [f64; 10] = [
+    1.0000, 1.0000, 0.9685, 0.2229, 0.0000, 0.0458, 0.8369, 1.0000, 1.0000, 0.9959,
+];
+const SMITS_YELLOW: [f64; 10] = [
+    0.0001, 0.0000, 0.1088, 0.6651, 1.0000, 1.0000, 0.9996, 0.9586, 0.9685, 0.9840,
+];
+const SMITS_RED: [f64; 10] = [
+    0.1012, 0.0515, 0.0000, 0.0000, 0.0000, 0.0000, 0.8325, 1.0149, 1.0149, 1.0149,
+];
+const SMITS_GREEN: [f64; 10] = [
+    0.0000, 0.0000, 0.0273, 0.7937, 1.0000, 0.9418, 0.1719, 0.0000, 0.0000, 0.0025,
+];
+const SMITS_BLUE: [f64; 10] = [
+    1.0000, 1.0000, 0.8916, 0.3323, 0.0000, 0.0000, 0.0003, 0.0369, 0.0483, 0.0496,
+];
+
+/// Upsamples an RGB colour to a smooth spectrum and evaluates it at one wavelength
+fn rgb_to_spectrum_at(rgb: Vec3, lambda: f64) -> f64 {
+    let bin = (((lambda - 380.0) / 34.0).floor().max(0.0) as usize).min(9);
+    let (r, g, b) = (rgb.x(), rgb.y(), rgb.z());
+
+    // Take as much white as possible, then the secondary colour, then the primary
+    if r <= g && r <= b {
+        let base = r * SMITS_WHITE[bin];
+        if g <= b {
+            base + (g - r) * SMITS_CYAN[bin] + (b - g) * SMITS_BLUE[bin]
+        } else {
+            base + (b - r) * SMITS_CYAN[bin] + (g - b) * SMITS_GREEN[bin]
+        }
+    } else if g <= r && g <= b {
+        let base = g * SMITS_WHITE[bin];
+        if r <= b {
+            base + (r - g) * SMITS_MAGENTA[bin] + (b - r) * SMITS_BLUE[bin]
+        } else {
+            base + (b - g) * SMITS_MAGENTA[bin] + (r - b) * SMITS_RED[bin]
+        }
+    } else {
+        let base = b * SMITS_WHITE[bin];
+        if r <= g {
+            base + (r - b) * SMITS_YELLOW[bin] + (g - r) * SMITS_GREEN[bin]
+        } else {
+            base + (g - b) * SMITS_YELLOW[bin] + (r - g) * SMITS_RED[bin]
+        }
+    }
+}
+
+/// An RGB colour's spectrum at each of the sampled wavelengths
+pub fn rgb_to_spectrum(rgb: Vec3, wavelengths: Vec3) -> Vec3 {
+    Vec3::new(
+        rgb_to_spectrum_at(rgb, wavelengths.x()),
+        rgb_to_spectrum_at(rgb, wavelengths.y()),
+        rgb_to_spectrum_at(rgb, wavelengths.z()),
+    )
+}
+
+/// A lobe of the piecewise Gaussian fit to the CIE 1931 observer, with its weight, peak and
+/// the widths either side of the peak
+type Lobe = (f64, f64, f64, f64);
+
+// Wyman, Sloan and Shirley's multi-lobe fit of the colour matching functions
+const CIE_X: [Lobe; 3] = [
+    (1.056, 599.8, 37.9, 31.0),
+    (0.362, 442.0, 16.0, 26.7),
+    (-0.065, 501.1, 20.4, 26.2),
+];
+const CIE_Y: [Lobe; 2] = [(0.821, 568.8, 46.9, 40.5), (0.286, 530.9, 16.3, 31.1)];
+const CIE_Z: [Lobe; 2] = [(1.217, 437.0, 11.8, 36.0), (0.681, 459.0, 26.0, 13.8)];
+
+fn matching(lobes: &[Lobe], lambda: f64) -> f64 {
+    lobes
+        .iter()
+        .map(|&(weight, peak, below, above)| {
+            let width = if lambda < peak { below } else { above };
+            let t = (lambda - peak) / width;
+            weight * (-0.5 * t * t).exp()
+        })
+        .sum()
+}
+
+/// Integral of a matching function, every lobe is two halves of a Gaussian
+fn matching_integral(lobes: &[Lobe]) -> f64 {
+    let half = (std::f64::consts::PI / 2.0).sqrt();
+    lobes
+        .iter()
+        .map(|&(weight, _, below, above)| weight * half * (below + above))
+        .sum()
+}
+
+/// XYZ of the sampled spectrum, scaled so a constant spectrum of one comes out as the D65
+/// white point. That makes a white surface lit by a white light render white
+pub fn spectrum_to_xyz(values: Vec3, wavelengths: Vec3) -> Vec3 {
+    let mut xyz = Vec3::new(0.0, 0.0, 0.0);
+    for (value, lambda) in [
+        (values.x(), wavelengths.x()),
+        (values.y(), wavelengths.y()),
+        (values.z(), wavelengths.z()),
+    ] {
+        xyz += value
+            * Vec3::new(
+                matching(&CIE_X, lambda),
+                matching(&CIE_Y, lambda),
+                matching(&CIE_Z, lambda),
+            );
+    }
+
+    // Each wavelength was picked uniformly, so the range over the count is its weight
+    let weight = (LAMBDA_MAX - LAMBDA_MIN) / 3.0;
+    Vec3::new(
+        xyz.x() * weight * 0.95047 / matching_integral(&CIE_X),
+        xyz.y() * weight / matching_integral(&CIE_Y),
+        xyz.z() * weight * 1.08883 / matching_integral(&CIE_Z),
+    )
+}
+
+/// Linear sRGB from XYZ
+pub fn xyz_to_rgb(xyz: Vec3) -> Vec3 {
+    let (x, y, z) = (xyz.x(), xyz.y(), xyz.z());
+    Vec3::new(
+        3.2404542 * x - 1.5371385 * y - 0.4985314 * z,
+        -0.9692660 * x + 1.8760108 * y + 0.0415560 * z,
+        0.0556434 * x - 0.2040259 * y + 1.0572252 * z,
+    )
+}
+
+pub fn spectrum_to_rgb(values: Vec3, wavelengths: Vec3) -> Vec3 {
+    xyz_to_rgb(spectrum_to_xyz(values, wavelengths))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::renderer::{rgb_to_spectrum, sample_wavelengths, spectrum_to_rgb, Vec3};
+
+    #[test]
+    fn test_spectral_round_trip() {
+        // Averaging lots of wavelength samples of an upsampled colour should give it back
+        for rgb in [
+            Vec3::new(1.0, 1.0, 1.0),
+            Vec3::new(0.8, 0.3, 0.1),
+            Vec3::new(0.2, 0.4, 0.7),
+        ] {
+            let runs = 50_000;
+            let mut total = Vec3::new(0.0, 0.0, 0.0);
+            for _ in 0..runs {
+                let wavelengths = sample_wavelengths();
+                total += spectrum_to_rgb(rgb_to_spectrum(rgb, wavelengths), wavelengths);
+            }
+            let average = total / runs as f64;
+            assert!(
+                (average - rgb).length() < 0.1,
+                "{rgb:?} came back as {average:?}"
+            );
+        }
+    }
+}
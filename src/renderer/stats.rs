@@ -0,0 +1,125 @@
+use std::time::{Duration, Instant};
+
+/// How often the rates are worked out again, any quicker and they're unreadable
+const UPDATE_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Keeps track of how fast a progressive render is going
+pub struct RenderStats {
+    started: Instant,
+    last_update: Instant,
+    last_samples: u64,
+    last_rays: u64,
+    pub samples_per_second: f64,
+    pub rays_per_second: f64,
+}
+
+impl RenderStats {
+    pub fn new(now: Instant) -> Self {
+        Self {
+            started: now,
+            last_update: now,
+            last_samples: 0,
+            last_rays: 0,
+            samples_per_second: 0.0,
+            rays_per_second: 0.0,
+        }
+    }
+
+    /// Starts timing again from the current totals, for when the image is thrown away or loaded
+    pub fn reset(&mut self, now: Instant, samples: u64, rays: u64) {
+        *self = Self {
+            last_samples: samples,
+            last_rays: rays,
+            ..Self::new(now)
+        };
+    }
+
+    /// Takes the image's total samples and a running count of rays traced
+    pub fn record(&mut self, now: Instant, samples: u64, rays: u64) {
+        let elapsed = now.duration_since(self.last_update);
+        if elapsed < UPDATE_INTERVAL {
+            return;
+        }
+        let seconds = elapsed.as_secs_f64();
+        self.samples_per_second = samples.saturating_sub(self.last_samples) as f64 / seconds;
+        self.rays_per_second = rays.saturating_sub(self.last_rays) as f64 / seconds;
+        self.last_update = now;
+        self.last_samples = samples;
+        self.last_rays = rays;
+    }
+
+    pub fn elapsed(&self, now: Instant) -> Duration {
+        now.duration_since(self.started)
+    }
+
+    /// How long until `remaining` more samples are taken at the current rate
+    pub fn eta(&self, remaining: u64) -> Option<Duration> {
+        if remaining == 0 {
+            Some(Duration::ZERO)
+        } else if self.samples_per_second > 0.0 {
+            Some(Duration::from_secs_f64(
+                remaining as f64 / self.samples_per_second,
+            ))
+        } else {
+            None
+        }
+    }
+}
+
+/// Hours, minutes and seconds, leaving off the hours when there aren't any
+pub fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if hours > 0 {
+        format!("{hours}:{minutes:02}:{seconds:02}")
+    } else {
+        format!("{minutes}:{seconds:02}")
+    }
+}
+
+/// A big number shortened with k, M or G
+pub fn format_count(count: f64) -> String {
+    match count {
+        c if c >= 1e9 => format!("{:.2}G", c / 1e9),
+        c if c >= 1e6 => format!("{:.2}M", c / 1e6),
+        c if c >= 1e3 => format!("{:.1}k", c / 1e3),
+        c => format!("{c:.0}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use crate::renderer::{format_count, format_duration, RenderStats};
+
+    #[test]
+    fn test_render_stats() {
+        let start = Instant::now();
+        let mut stats = RenderStats::new(start);
+        assert_eq!(stats.eta(100), None);
+
+        // Too soon to say anything
+        stats.record(start + Duration::from_millis(100), 50, 200);
+        assert_eq!(stats.samples_per_second, 0.0);
+
+        stats.record(start + Duration::from_secs(2), 1000, 4000);
+        assert_eq!(stats.samples_per_second, 500.0);
+        assert_eq!(stats.rays_per_second, 2000.0);
+        assert_eq!(stats.eta(1500), Some(Duration::from_secs(3)));
+        assert_eq!(stats.eta(0), Some(Duration::ZERO));
+
+        stats.reset(start + Duration::from_secs(3), 0, 4000);
+        assert_eq!(
+            stats.elapsed(start + Duration::from_secs(4)),
+            Duration::from_secs(1)
+        );
+        stats.record(start + Duration::from_secs(4), 100, 4300);
+        assert_eq!(stats.rays_per_second, 300.0);
+
+        assert_eq!(format_duration(Duration::from_secs(75)), "1:15");
+        assert_eq!(format_duration(Duration::from_secs(3725)), "1:02:05");
+        assert_eq!(format_count(999.0), "999");
+        assert_eq!(format_count(1_234_567.0), "1.23M");
+    }
+}
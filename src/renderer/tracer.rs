@@ -0,0 +1,126 @@
+use crate::hittable::HitRecord;
+use crate::integrator::Integrator;
+use crate::renderer::{random_double, Camera, F64x4, Ray, Scene, Vec3};
+
+/// Light an integrator traced from the lights onto some pixel of the image
+pub struct PixelSplat {
+    pub x: usize,
+    pub y: usize,
+    pub value: Vec3,
+}
+
+/// Where a point of a width by height image, given in pixels, lands on the camera. Rows count
+/// down from the top, row y covering v from `(height - y) / (height - 1)` upwards
+fn pixel_to_uv((x, y): (f64, f64), (width, height): (f64, f64)) -> (f64, f64) {
+    (x / (width - 1.), (height + 1. - y) / (height - 1.))
+}
+
+/// The inverse of `pixel_to_uv`
+fn uv_to_pixel((u, v): (f64, f64), (width, height): (f64, f64)) -> (f64, f64) {
+    (u * (width - 1.), height + 1. - v * (height - 1.))
+}
+
+/// The camera ray through a point of a width by height image, given in pixels, the same way
+/// `trace_pixel` maps pixels onto the camera
+pub fn camera_ray(camera: &Camera, point: (f64, f64), (width, height): (usize, usize)) -> Ray {
+    let (u, v) = pixel_to_uv(point, (width as f64, height as f64));
+    camera.get_ray(u, v)
+}
+
+/// Traces one camera path through a jittered point in pixel (x, y) of a width by height image,
+/// returning its colour and the normal and distance of the first hit for the AOVs. Anything
+/// the integrator splats onto other pixels is added to `splats`
+pub fn trace_pixel(
+    scene: &Scene,
+    integrator: &dyn Integrator,
+    (x, y): (usize, usize),
+    (width, height): (usize, usize),
+    splats: &mut Vec<PixelSplat>,
+) -> (Vec3, F64x4) {
+    let size = (width as f64, height as f64);
+    let point = (x as f64 + random_double(), y as f64 + random_double());
+    let (u, v) = pixel_to_uv(point, size);
+    let ray = scene.camera.get_ray(u, v);
+
+    let mut rec = HitRecord {
+        ..Default::default()
+    };
+    let aov = if scene.hit(&ray, 0.001, f64::INFINITY, &mut rec) {
+        let distance = (rec.p - ray.orig).length();
+        F64x4::from_array([rec.normal.x(), rec.normal.y(), rec.normal.z(), distance])
+    } else {
+        F64x4::splat(0.)
+    };
+
+    let mut light_splats = Vec::new();
+    let colour = integrator.li(&ray, scene, &mut light_splats);
+    for splat in light_splats {
+        // The pixel whose camera rays cover this point. Some of the image is seen by no pixel
+        // at all, and light landing there is dropped
+        let (x, y) = uv_to_pixel((splat.u, splat.v), size);
+        let (x, y) = (x.floor(), y.floor());
+        if (0. ..size.0).contains(&x) && (0. ..size.1).contains(&y) {
+            splats.push(PixelSplat {
+                x: x as usize,
+                y: y as usize,
+                value: splat.value,
+            });
+        }
+    }
+    (colour, aov)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hittable::{HitRecord, HittableList, Sphere};
+    use crate::integrator::NormalsIntegrator;
+    use crate::renderer::{camera_ray, seed_random, trace_pixel, Scene, Vec3};
+
+    #[test]
+    fn test_pick_matches_trace() {
+        let mut world = HittableList::new();
+        world.add_hittable(Box::new(Sphere::new(Vec3::new(0.0, 0.0, -2.0), 1.5)));
+        let scene = Scene::new(world);
+        let size = (16, 12);
+        seed_random(3);
+
+        // The normal seen through the middle of a pixel, as picking and highlighting use
+        let picked = |x: usize, y: usize| {
+            let ray = camera_ray(&scene.camera, (x as f64 + 0.5, y as f64 + 0.5), size);
+            let mut rec = HitRecord::default();
+            scene
+                .hit(&ray, 0.001, f64::INFINITY, &mut rec)
+                .then_some(rec.normal)
+        };
+
+        // Averaged over its jitter, what a pixel shades is what's picked at its middle, and
+        // nearer to that than to the row below
+        for y in 0..size.1 - 1 {
+            for x in 0..size.0 {
+                let (Some(middle), Some(below)) = (picked(x, y), picked(x, y + 1)) else {
+                    continue;
+                };
+                let mut traced = Vec3::new(0.0, 0.0, 0.0);
+                let mut misses = 0;
+                for _ in 0..64 {
+                    let (_, aov) =
+                        trace_pixel(&scene, &NormalsIntegrator, (x, y), size, &mut Vec::new());
+                    let aov = aov.to_array();
+                    if aov[3] == 0.0 {
+                        misses += 1;
+                    }
+                    traced = traced + Vec3::new(aov[0], aov[1], aov[2]) / 64.0;
+                }
+                // Pixels on the edge of the sphere average in the background
+                if misses > 0 {
+                    continue;
+                }
+                let error = (traced - middle).length();
+                assert!(
+                    error < 0.3 * (below - middle).length(),
+                    "pixel {x}, {y} is off by {error}"
+                );
+            }
+        }
+    }
+}
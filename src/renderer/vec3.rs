@@ -1,27 +1,27 @@
-use core::simd::{Simd, SimdFloat};
 use std::{
     f64,
     ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub},
-    simd::f64x4,
 };
 
+use crate::renderer::{random_double, random_range, F64x4};
+
 #[derive(Copy, Clone, PartialEq, Debug)]
-pub struct Vec3(pub f64x4);
+pub struct Vec3(pub F64x4);
 
 impl Default for Vec3 {
     fn default() -> Self {
-        Self(Simd::from_array([0., 0., 0., 0.]))
+        Self(F64x4::from_array([0., 0., 0., 0.]))
     }
 }
 
 impl Vec3 {
     pub fn new(x: f64, y: f64, z: f64) -> Self {
-        Self(Simd::from_array([x, y, z, 0.]))
+        Self(F64x4::from_array([x, y, z, 0.]))
     }
 
     pub fn dot(&self, other: &Vec3) -> f64 {
         // self.0[0] * other.0[0] + self.0[1] * other.0[1] + self.0[2] * other.0[2]
-        Simd::reduce_sum(self.0 * other.0)
+        (self.0 * other.0).reduce_sum()
     }
 
     pub fn cross(&self, other: &Vec3) -> Self {
@@ -33,8 +33,8 @@ impl Vec3 {
         // ]))
 
         // Who knows if marshalling to several simd values is faster than the previous approach?
-        let arr_a = Simd::as_array(&self.0);
-        let arr_b = Simd::as_array(&other.0);
+        let arr_a = self.0.to_array();
+        let arr_b = other.0.to_array();
 
         let arr_left = [
             arr_a[1] * arr_b[2],
@@ -49,8 +49,8 @@ impl Vec3 {
             0.,
         ];
 
-        let simd_a = Simd::from_array(arr_left);
-        let simd_b = Simd::from_array(arr_right);
+        let simd_a = F64x4::from_array(arr_left);
+        let simd_b = F64x4::from_array(arr_right);
         Self(simd_a - simd_b)
     }
 
@@ -60,9 +60,7 @@ impl Vec3 {
     }
 
     pub fn clamp(&mut self, min: f64, max: f64) {
-        let min = Simd::splat(min);
-        let max = Simd::splat(max);
-        self.0 = self.0.simd_clamp(min, max);
+        self.0 = self.0.clamp(min, max);
     }
 
     pub fn length(&self) -> f64 {
@@ -74,15 +72,53 @@ impl Vec3 {
     }
 
     pub fn x(&self) -> f64 {
-        Simd::as_array(&self.0)[0]
+        self.0[0]
     }
 
     pub fn y(&self) -> f64 {
-        Simd::as_array(&self.0)[1]
+        self.0[1]
     }
 
     pub fn z(&self) -> f64 {
-        Simd::as_array(&self.0)[2]
+        self.0[2]
+    }
+
+    pub fn is_black(&self) -> bool {
+        self.x() == 0.0 && self.y() == 0.0 && self.z() == 0.0
+    }
+
+    pub fn max_component(&self) -> f64 {
+        self.x().max(self.y()).max(self.z())
+    }
+
+    /// Relative luminance of a linear RGB colour
+    pub fn luminance(&self) -> f64 {
+        0.2126 * self.x() + 0.7152 * self.y() + 0.0722 * self.z()
+    }
+
+    /// Mirrors this vector about the normal `n`
+    pub fn reflect(&self, n: &Vec3) -> Vec3 {
+        *self - 2.0 * self.dot(n) * *n
+    }
+
+    /// A uniformly distributed direction on the unit sphere
+    pub fn random_unit_vector() -> Vec3 {
+        let z = random_range(-1., 1.);
+        let phi = 2. * f64::consts::PI * random_double();
+        let r = f64::sqrt(1. - z * z);
+        Vec3::new(r * phi.cos(), r * phi.sin(), z)
+    }
+
+    /// Builds two unit vectors perpendicular to this (unit length) vector and each other
+    pub fn orthonormal_basis(&self) -> (Vec3, Vec3) {
+        let a = if self.x().abs() > 0.9 {
+            Vec3::new(0., 1., 0.)
+        } else {
+            Vec3::new(1., 0., 0.)
+        };
+        let v = self.cross(&a).unit_vector();
+        let u = v.cross(self);
+        (u, v)
     }
 }
 
@@ -123,7 +159,7 @@ impl Mul for &Vec3 {
 impl Mul<Vec3> for f64 {
     type Output = Vec3;
     fn mul(self, rhs: Vec3) -> Self::Output {
-        let val = f64x4::splat(self);
+        let val = F64x4::splat(self);
         Vec3(rhs.0 * val)
     }
 }
@@ -131,14 +167,14 @@ impl Mul<Vec3> for f64 {
 impl Mul<f64> for Vec3 {
     type Output = Vec3;
     fn mul(self, rhs: f64) -> Self::Output {
-        let val = f64x4::splat(rhs);
+        let val = F64x4::splat(rhs);
         Vec3(val * self.0)
     }
 }
 
 impl MulAssign<f64> for &mut Vec3 {
     fn mul_assign(&mut self, rhs: f64) {
-        let val = f64x4::splat(rhs);
+        let val = F64x4::splat(rhs);
         self.0 *= val;
     }
 }
@@ -164,9 +200,9 @@ impl Neg for Vec3 {
     }
 }
 
-impl From<Vec3> for Simd<f64, 4> {
+impl From<Vec3> for F64x4 {
     fn from(value: Vec3) -> Self {
-        Self::from(value.0)
+        value.0
     }
 }
 